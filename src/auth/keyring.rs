@@ -1,13 +1,44 @@
 use base64::{Engine, engine::general_purpose::STANDARD};
+use secrecy::{ExposeSecret, SecretString};
+use std::sync::OnceLock;
 use zeroize::Zeroize;
 
 use crate::core::{crypto, errors::ChacrabResult};
 
+pub const DEFAULT_PROFILE: &str = "default";
+
 const KEYRING_SERVICE: &str = "chacrab";
 const KEYRING_USER: &str = "session-master-key";
+const REMOTE_SECRET_PREFIX: &str = "sync-remote";
+const DATABASE_SECRET_USER: &str = "database-url";
+
+static ACTIVE_PROFILE: OnceLock<String> = OnceLock::new();
+
+pub fn select_profile(profile: &str) {
+    let _ = ACTIVE_PROFILE.set(profile.to_owned());
+}
+
+pub fn active_profile() -> &'static str {
+    ACTIVE_PROFILE.get().map_or(DEFAULT_PROFILE, String::as_str)
+}
+
+pub fn profile_user(user: &str, profile: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        user.to_owned()
+    } else {
+        format!("{user}@{profile}")
+    }
+}
+
+pub fn entry(user: &str) -> ChacrabResult<keyring::Entry> {
+    Ok(keyring::Entry::new(
+        KEYRING_SERVICE,
+        &profile_user(user, active_profile()),
+    )?)
+}
 
 pub fn store_session_key(key: &[u8; crypto::KEY_SIZE]) -> ChacrabResult<()> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?;
+    let entry = entry(KEYRING_USER)?;
     let mut encoded = STANDARD.encode(key);
     entry.set_password(&encoded)?;
     encoded.zeroize();
@@ -15,7 +46,7 @@ pub fn store_session_key(key: &[u8; crypto::KEY_SIZE]) -> ChacrabResult<()> {
 }
 
 pub fn load_session_key() -> ChacrabResult<[u8; crypto::KEY_SIZE]> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?;
+    let entry = entry(KEYRING_USER)?;
     let mut encoded = entry.get_password()?;
     let mut decoded = STANDARD
         .decode(encoded.as_bytes())
@@ -32,7 +63,89 @@ pub fn load_session_key() -> ChacrabResult<[u8; crypto::KEY_SIZE]> {
 }
 
 pub fn clear_session_key() -> ChacrabResult<()> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)?;
+    let entry = entry(KEYRING_USER)?;
     let _ = entry.delete_password();
     Ok(())
 }
+
+pub fn store_remote_secret(remote: &str, secret: &SecretString) -> ChacrabResult<()> {
+    let entry = entry(&remote_secret_user(remote))?;
+    entry.set_password(secret.expose_secret())?;
+    Ok(())
+}
+
+pub fn load_remote_secret(remote: &str) -> ChacrabResult<Option<SecretString>> {
+    let entry = entry(&remote_secret_user(remote))?;
+    match entry.get_password() {
+        Ok(secret) => Ok(Some(SecretString::new(secret.into_boxed_str()))),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn clear_remote_secret(remote: &str) -> ChacrabResult<()> {
+    let entry = entry(&remote_secret_user(remote))?;
+    let _ = entry.delete_password();
+    Ok(())
+}
+
+pub fn store_database_secret(profile: &str, secret: &SecretString) -> ChacrabResult<()> {
+    let entry = profile_entry(DATABASE_SECRET_USER, profile)?;
+    entry.set_password(secret.expose_secret())?;
+    Ok(())
+}
+
+pub fn load_database_secret(profile: &str) -> ChacrabResult<Option<SecretString>> {
+    let entry = profile_entry(DATABASE_SECRET_USER, profile)?;
+    match entry.get_password() {
+        Ok(secret) => Ok(Some(SecretString::new(secret.into_boxed_str()))),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn clear_database_secret(profile: &str) -> ChacrabResult<()> {
+    let entry = profile_entry(DATABASE_SECRET_USER, profile)?;
+    let _ = entry.delete_password();
+    Ok(())
+}
+
+fn profile_entry(user: &str, profile: &str) -> ChacrabResult<keyring::Entry> {
+    Ok(keyring::Entry::new(
+        KEYRING_SERVICE,
+        &profile_user(user, profile),
+    )?)
+}
+
+fn remote_secret_user(remote: &str) -> String {
+    format!("{REMOTE_SECRET_PREFIX}:{remote}")
+}
+
+pub fn clear_profile_secrets(profile: &str, remotes: &[String]) -> ChacrabResult<()> {
+    let users = remotes
+        .iter()
+        .map(|remote| remote_secret_user(remote))
+        .chain([KEYRING_USER.to_owned(), DATABASE_SECRET_USER.to_owned()]);
+    for user in users {
+        let entry = profile_entry(&user, profile)?;
+        let _ = entry.delete_password();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DEFAULT_PROFILE, profile_user};
+
+    #[test]
+    fn default_profile_keeps_legacy_entry_names() {
+        assert_eq!(
+            profile_user("session-master-key", DEFAULT_PROFILE),
+            "session-master-key"
+        );
+        assert_eq!(
+            profile_user("session-master-key", "team"),
+            "session-master-key@team"
+        );
+    }
+}
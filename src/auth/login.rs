@@ -7,6 +7,7 @@ use crate::{
         crypto,
         errors::{ChacrabError, ChacrabResult},
         models::AuthRecord,
+        vault,
     },
     storage::r#trait::VaultRepository,
 };
@@ -36,20 +37,16 @@ impl SessionKeyStore for OsSessionKeyStore {
 pub async fn register<R: VaultRepository>(
     repo: &R,
     master_password: SecretString,
+    metadata_privacy: bool,
 ) -> ChacrabResult<()> {
-    let (material, mut derived) = crypto::create_registration_material(&master_password)?;
-
-    let auth = AuthRecord {
-        salt: material.salt_b64,
-        verifier: material.verifier,
-        argon2_m_cost: crypto::ARGON2_M_COST,
-        argon2_t_cost: crypto::ARGON2_T_COST,
-        argon2_p_cost: crypto::ARGON2_P_COST,
-    };
-    repo.set_auth_record(&auth).await?;
-
-    derived.zeroize();
-    Ok(())
+    let mut vault_key = crypto::generate_vault_key();
+    let auth = vault::wrap_vault_key(&master_password, &vault_key);
+    vault_key.zeroize();
+    repo.set_auth_record(&AuthRecord {
+        metadata_privacy,
+        ..auth?
+    })
+    .await
 }
 
 pub async fn login<R: VaultRepository>(
@@ -65,22 +62,32 @@ pub(crate) async fn login_with_store<R: VaultRepository, S: SessionKeyStore>(
     master_password: SecretString,
     key_store: &S,
 ) -> ChacrabResult<()> {
+    let mut vault_key = unlock(repo, &master_password).await?;
+    let stored = key_store.store(&vault_key);
+    vault_key.zeroize();
+    stored
+}
+
+pub async fn unlock<R: VaultRepository>(
+    repo: &R,
+    master_password: &SecretString,
+) -> ChacrabResult<[u8; crypto::KEY_SIZE]> {
     let auth = repo
         .get_auth_record()
         .await?
         .ok_or_else(|| ChacrabError::Config("vault not initialized; run init".to_owned()))?;
 
-    let mut derived = crypto::verify_password_with_params(
-        &master_password,
+    let mut master_key = crypto::verify_password_with_params(
+        master_password,
         &auth.salt,
         &auth.verifier,
         auth.argon2_m_cost,
         auth.argon2_t_cost,
         auth.argon2_p_cost,
     )?;
-    key_store.store(&derived)?;
-    derived.zeroize();
-    Ok(())
+    let vault_key = vault::unlock_vault_key(repo, &auth, &master_key).await;
+    master_key.zeroize();
+    vault_key
 }
 
 pub fn logout() -> ChacrabResult<()> {
@@ -118,7 +125,10 @@ mod tests {
     use crate::{
         core::{
             errors::{ChacrabError, ChacrabResult},
-            models::{AuthRecord, VaultItem},
+            models::{
+                AuthRecord, ChangeSet, ItemRevision, SyncState, Tombstone, VaultItem, VaultManifest,
+            },
+            vault::VaultService,
         },
         storage::r#trait::VaultRepository,
     };
@@ -142,6 +152,18 @@ mod tests {
             Ok(())
         }
 
+        async fn is_initialized(&self) -> ChacrabResult<bool> {
+            Ok(true)
+        }
+
+        async fn has_legacy_items(&self) -> ChacrabResult<bool> {
+            Ok(true)
+        }
+
+        async fn clear_legacy_items(&self) -> ChacrabResult<()> {
+            Ok(())
+        }
+
         async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()> {
             self.items
                 .lock()
@@ -174,6 +196,46 @@ mod tests {
             Ok(())
         }
 
+        async fn add_revision(&self, _: &ItemRevision) -> ChacrabResult<()> {
+            Ok(())
+        }
+
+        async fn list_revisions(&self, _: Uuid) -> ChacrabResult<Vec<ItemRevision>> {
+            Ok(Vec::new())
+        }
+
+        async fn prune_revisions(&self, _: Uuid, _: usize) -> ChacrabResult<()> {
+            Ok(())
+        }
+
+        async fn list_tombstones(&self) -> ChacrabResult<Vec<Tombstone>> {
+            Ok(Vec::new())
+        }
+
+        async fn record_tombstone(&self, _: &Tombstone) -> ChacrabResult<()> {
+            Ok(())
+        }
+
+        async fn changes_since(&self, _: u64) -> ChacrabResult<ChangeSet> {
+            Ok(ChangeSet::default())
+        }
+
+        async fn get_manifest(&self) -> ChacrabResult<Option<VaultManifest>> {
+            Ok(None)
+        }
+
+        async fn set_manifest(&self, _: &VaultManifest) -> ChacrabResult<()> {
+            Ok(())
+        }
+
+        async fn get_sync_state(&self, _: &str) -> ChacrabResult<Option<SyncState>> {
+            Ok(None)
+        }
+
+        async fn set_sync_state(&self, _: &SyncState) -> ChacrabResult<()> {
+            Ok(())
+        }
+
         async fn get_auth_record(&self) -> ChacrabResult<Option<AuthRecord>> {
             Ok(self.auth.lock().expect("poisoned").clone())
         }
@@ -182,6 +244,19 @@ mod tests {
             *self.auth.lock().expect("poisoned") = Some(auth.clone());
             Ok(())
         }
+
+        async fn replace_auth_and_items(
+            &self,
+            auth: &AuthRecord,
+            items: &[VaultItem],
+        ) -> ChacrabResult<()> {
+            let mut stored = self.items.lock().expect("poisoned");
+            for item in items {
+                stored.insert(item.id, item.clone());
+            }
+            *self.auth.lock().expect("poisoned") = Some(auth.clone());
+            Ok(())
+        }
     }
 
     #[derive(Default)]
@@ -216,9 +291,11 @@ mod tests {
         let store = MemorySessionStore::default();
         let master_password = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
 
-        register(&repo, master_password.clone())
+        register(&repo, master_password.clone(), true)
             .await
             .expect("register should succeed");
+        let auth = repo.get_auth_record().await.expect("auth record");
+        assert!(auth.is_some_and(|auth| auth.metadata_privacy));
 
         login_with_store(&repo, master_password.clone(), &store)
             .await
@@ -270,6 +347,10 @@ mod tests {
             argon2_m_cost: custom_m,
             argon2_t_cost: custom_t,
             argon2_p_cost: custom_p,
+            wrapped_vault_key: None,
+            wrapped_vault_key_nonce: None,
+            metadata_privacy: false,
+            revision: 0,
         })
         .await
         .expect("set auth");
@@ -279,4 +360,64 @@ mod tests {
             .expect("login should use stored argon2 params");
         assert!(current_session_key_with_store(&store).is_ok());
     }
+
+    #[tokio::test]
+    async fn login_migrates_legacy_vault_to_wrapped_key() {
+        let repo = MemoryRepo::default();
+        let store = MemorySessionStore::default();
+        let master_password = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
+        let (material, legacy_key) =
+            crate::core::crypto::create_registration_material(&master_password)
+                .expect("registration material");
+        repo.set_auth_record(&AuthRecord {
+            salt: material.salt_b64,
+            verifier: material.verifier,
+            argon2_m_cost: crate::core::crypto::ARGON2_M_COST,
+            argon2_t_cost: crate::core::crypto::ARGON2_T_COST,
+            argon2_p_cost: crate::core::crypto::ARGON2_P_COST,
+            wrapped_vault_key: None,
+            wrapped_vault_key_nonce: None,
+            metadata_privacy: false,
+            revision: 0,
+        })
+        .await
+        .expect("set auth");
+
+        let legacy_service = VaultService::new(repo.clone());
+        let item = legacy_service
+            .add_note(
+                "Legacy".to_owned(),
+                SecretString::new("legacy secret".to_owned().into_boxed_str()),
+                &legacy_key,
+            )
+            .await
+            .expect("legacy item");
+
+        login_with_store(&repo, master_password.clone(), &store)
+            .await
+            .expect("login should migrate legacy vault");
+
+        let auth = repo
+            .get_auth_record()
+            .await
+            .expect("auth")
+            .expect("auth record");
+        assert!(auth.wrapped_vault_key.is_some());
+        let vault_key = current_session_key_with_store(&store).expect("session key");
+        assert_ne!(vault_key, legacy_key);
+
+        let (_, payload) = legacy_service
+            .show_decrypted(item.id, &vault_key)
+            .await
+            .expect("item should decrypt with vault key");
+        assert_eq!(payload["notes"].as_str(), Some("legacy secret"));
+
+        login_with_store(&repo, master_password, &store)
+            .await
+            .expect("second login");
+        assert_eq!(
+            current_session_key_with_store(&store).expect("session key"),
+            vault_key
+        );
+    }
 }
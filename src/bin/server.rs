@@ -0,0 +1,15 @@
+#[tokio::main]
+async fn main() {
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("chacrab=warn")),
+        )
+        .with_target(false)
+        .init();
+
+    if let Err(err) = chacrab::sync::server::run().await {
+        eprintln!("chacrab-server: {err}");
+        std::process::exit(1);
+    }
+}
@@ -7,29 +7,52 @@ use uuid::Uuid;
 use zeroize::Zeroize;
 
 use crate::{
-    auth::login,
+    auth::{
+        keyring::{self, DEFAULT_PROFILE},
+        login,
+    },
     cli::{
         display::{
             SessionIndicator, UiOptions, clear_screen, configure_terminal, error as error_msg,
             is_insecure_terminal, print_header, secure, short_id, success, syncing, system,
             warning,
         },
-        parser::{Cli, Commands},
-        prompts, runtime_config, session, table,
+        forms,
+        parser::{
+            Cli, Commands, ConfigAction, GenerateArgs, PrivacyAction, ProfileAction, RemoteAction,
+            SecretArgs, SyncAction, TagAction, TrashAction,
+        },
+        prompts,
+        runtime_config::{self, Profile, RuntimeConfig, SyncRemote, VaultSettings},
+        session, table,
     },
     core::{
         backup::{EncryptedBackupFile, export_encrypted, import_encrypted},
         errors::{ChacrabError, ChacrabResult},
-        models::VaultItem,
+        generator::{self, PassphraseOptions, PasswordOptions},
+        models::{
+            CustomField, EncryptedPayload, ItemChanges, ItemFilter, NewVaultItem, TotpSecret,
+            VaultItem, VaultItemType,
+        },
         password_policy,
-        vault::VaultService,
+        totp::{self, TotpCode},
+        vault::{self, VaultService},
+    },
+    storage::{app::AppRepository, bundle::BundleRepository, r#trait::VaultRepository},
+    sync::{
+        bundle::{self as sync_bundle, EncryptedBundleFile},
+        sync_engine::{
+            ConflictChoice, ConflictPolicy, ConflictResolver, SyncEngine, SyncPlan, SyncReport,
+            SyncStep,
+        },
     },
-    storage::{app::AppRepository, r#trait::VaultRepository},
-    sync::sync_engine::SyncEngine,
 };
 
 async fn app_repo(cli: &Cli) -> ChacrabResult<AppRepository> {
-    let repo = AppRepository::connect(&cli.backend, &cli.database_url).await?;
+    let database_url =
+        runtime_config::url_with_secret(&cli.database_url, cli.database_secret.as_ref())
+            .ok_or_else(|| ChacrabError::Config("invalid database url".to_owned()))?;
+    let repo = AppRepository::connect(&cli.backend, database_url.expose_secret()).await?;
     repo.init().await?;
     Ok(repo)
 }
@@ -39,6 +62,7 @@ fn ui_options(cli: &Cli) -> UiOptions {
         json: cli.json,
         quiet: cli.quiet,
         color: !cli.no_color,
+        interactive: !cli.no_input,
     }
 }
 
@@ -49,13 +73,143 @@ fn map_user_error(err: &ChacrabError) -> &'static str {
         ChacrabError::SessionExpired => "Session timed out. Please login again.",
         ChacrabError::NotFound => "Item not found.",
         ChacrabError::UnsupportedBackend(_) => "Unsupported backend configuration.",
+        ChacrabError::UnsupportedItemType(_) => {
+            "Vault contains an item type from a newer chacrab version. Upgrade to read it."
+        }
         ChacrabError::Config(message) if message == "operation cancelled" => "Operation cancelled.",
+        ChacrabError::Config(message) if message == "confirmation required" => {
+            "This action requires --yes when prompts are disabled (--json or --no-input)."
+        }
         ChacrabError::Config(message) if message == "ambiguous item id prefix" => {
             "Ambiguous ID. Use a longer ID prefix."
         }
+        ChacrabError::Config(message) if message == "search matches several items" => {
+            "Several items match. Use an ID or a more specific search term."
+        }
+        ChacrabError::Config(message) if message == "input required" => {
+            "Input required. Pass the value with flags or --password-stdin, --password-fd or --password-file."
+        }
+        ChacrabError::Config(message) if message == "item has no secret to replace" => {
+            "Only passwords and notes can be edited with a piped secret."
+        }
+        ChacrabError::Config(message) if message == "title is empty" => "Title cannot be empty.",
+        ChacrabError::Config(message) if message == "invalid custom field argument" => {
+            "Custom fields are passed as --field [KIND:]NAME=VALUE."
+        }
+        ChacrabError::Config(message) if message == "hidden custom field argument" => {
+            "Hidden custom fields cannot be passed as arguments. Add them at the prompt."
+        }
+        ChacrabError::Config(message) if message == "unable to read secret input" => {
+            "Unable to read the secret from stdin, the file descriptor or the file."
+        }
+        ChacrabError::Config(message) if message == "secret input is empty" => {
+            "Secret input is empty."
+        }
+        ChacrabError::Config(message) if message == "secret input is too large" => {
+            "Secret input is larger than 1 MiB."
+        }
+        ChacrabError::Config(message) if message == "secret input is not valid utf-8" => {
+            "Secret input must be valid UTF-8 text."
+        }
+        ChacrabError::Config(message) if message == "item is not in trash" => {
+            "Item is not in trash."
+        }
+        ChacrabError::Config(message) if message == "revision not found" => {
+            "Revision not found. Run `chacrab history <id>` to list revisions."
+        }
+        ChacrabError::Config(message) if message == "search query is empty" => {
+            "Search query is empty."
+        }
+        ChacrabError::Config(message) if message == "unknown conflict policy" => {
+            "Unknown conflict policy. Use prefer-local, prefer-remote, keep-both or interactive."
+        }
+        ChacrabError::Config(message)
+            if message == "interactive conflict resolution needs a terminal" =>
+        {
+            "Interactive conflict resolution is not available with --json or --no-input."
+        }
+        ChacrabError::Config(message) if message == "sync server rejected the access token" => {
+            "Sync server rejected the access token."
+        }
+        ChacrabError::Config(message) if message == "sync server must use https" => {
+            "Sync server URLs must use https unless the server runs on localhost."
+        }
+        ChacrabError::Config(message) if message == "sync remote belongs to a different vault" => {
+            "Sync stopped: the remote belongs to a vault with a different master key. No items were changed."
+        }
+        ChacrabError::Config(message) if message == "sync remote is not initialised" => {
+            "The sync remote is empty or uses an older schema. Run `chacrab sync` without --dry-run to set it up."
+        }
+        ChacrabError::Config(message) if message == "unknown sync remote" => {
+            "Unknown sync remote. Run `chacrab remote list` to see configured remotes."
+        }
+        ChacrabError::Config(message) if message == "no sync remotes configured" => {
+            "No sync remotes configured. Add one with `chacrab remote add <name> <url>`."
+        }
+        ChacrabError::Config(message) if message == "several sync remotes configured" => {
+            "Several sync remotes configured. Name one or use `chacrab sync --all`."
+        }
+        ChacrabError::Config(message) if message == "sync remote already exists" => {
+            "A sync remote with this name already exists."
+        }
+        ChacrabError::Config(message)
+            if message == "cannot infer sync remote backend"
+                || message == "cannot infer profile backend" =>
+        {
+            "Cannot tell the backend from the URL. Pass --backend sqlite|postgres|mongo|http."
+        }
+        ChacrabError::Config(message) if message == "sync bundle is missing earlier changes" => {
+            "This bundle skips an earlier export. Import the missing bundles first, or run `chacrab sync export-bundle --full` on the other machine."
+        }
+        ChacrabError::Config(message) if message == "sync bundle could not be decrypted" => {
+            "Sync bundle could not be decrypted. It is damaged or belongs to another vault."
+        }
+        ChacrabError::Config(message) if message == "unsupported sync server api version" => {
+            "Sync server speaks an unsupported API version. Upgrade chacrab or the server."
+        }
+        ChacrabError::Config(message) if message == "unknown profile" => {
+            "Unknown profile. Run `chacrab profile list` to see profiles."
+        }
+        ChacrabError::Config(message) if message == "invalid profile name" => {
+            "Profile names use letters, digits, '-' and '_' (at most 32 characters)."
+        }
+        ChacrabError::Config(message) if message == "profile already exists" => {
+            "A profile with this name already exists."
+        }
+        ChacrabError::Config(message) if message == "default profile cannot be removed" => {
+            "The default profile cannot be removed."
+        }
+        ChacrabError::Config(message) if message == "unknown config key" => {
+            "Unknown config key. Run `chacrab config list` to see available keys."
+        }
+        ChacrabError::Config(message) if message == "invalid config value" => {
+            "Invalid value for this config key."
+        }
+        ChacrabError::Config(message) if message == "invalid runtime config format" => {
+            "Config file is invalid. Fix it or run `chacrab config reset`."
+        }
+        ChacrabError::Config(message) if message == "runtime config has an unknown key" => {
+            "Config file contains an unknown key. Fix it or run `chacrab config reset`."
+        }
+        ChacrabError::Config(message)
+            if message == "runtime config is from a newer chacrab version" =>
+        {
+            "Config file was written by a newer chacrab version. Upgrade chacrab."
+        }
+        ChacrabError::Config(message) if message == "clipboard is disabled" => {
+            "Clipboard is disabled. Enable it with `chacrab config set clipboard.enabled true`."
+        }
         ChacrabError::Config(message) if message.starts_with("weak master password") => {
             "Weak master password. Use at least 12 chars and 3 of upper/lower/digit/symbol."
         }
+        ChacrabError::Config(message)
+            if message == "invalid otpauth uri" || message == "invalid base32 secret" =>
+        {
+            "Invalid TOTP secret or otpauth:// URI."
+        }
+        ChacrabError::Config(message) if message == "item has no totp secret" => {
+            "This item has no TOTP secret."
+        }
         ChacrabError::Config(message) if message == "confirmation text did not match title" => {
             "Confirmation text did not match title."
         }
@@ -63,6 +217,9 @@ fn map_user_error(err: &ChacrabError) -> &'static str {
         ChacrabError::KeyringLocked => "Secure keyring is locked. Unlock your keyring and retry.",
         ChacrabError::KeyringUnavailable => "Secure keyring unavailable. Unlock keyring and retry.",
         ChacrabError::Crypto => "Security operation failed.",
+        ChacrabError::Integrity(_) => {
+            "Sync stopped: the remote vault failed an integrity check (rollback or tampering). No items were changed."
+        }
         ChacrabError::Serialization => "Data format error.",
         ChacrabError::Storage => "Storage operation failed.",
     }
@@ -89,22 +246,59 @@ fn parse_or_resolve_id(id_input: &str, items: &[VaultItem]) -> ChacrabResult<Uui
     Ok(first)
 }
 
-pub async fn run() -> ChacrabResult<()> {
-    let mut cli = Cli::parse();
-    let args = std::env::args().collect::<Vec<_>>();
-    let backend_explicit = runtime_config::cli_flag_present(&args, "--backend");
-    let database_url_explicit = runtime_config::cli_flag_present(&args, "--database-url");
-
-    if (!backend_explicit || !database_url_explicit)
-        && let Some(saved_config) = runtime_config::load()?
-    {
-        if !backend_explicit {
-            cli.backend = saved_config.backend;
+async fn resolve_item(
+    vault: &VaultService<AppRepository>,
+    input: &str,
+    key: &[u8; 32],
+    options: UiOptions,
+) -> ChacrabResult<VaultItem> {
+    let items = vault.list_revealed(key).await?;
+    match parse_or_resolve_id(input, &items) {
+        Ok(id) => {
+            return items
+                .into_iter()
+                .find(|item| item.id == id)
+                .ok_or(ChacrabError::NotFound);
         }
-        if !database_url_explicit {
-            cli.database_url = saved_config.database_url;
+        Err(ChacrabError::NotFound) => {}
+        Err(error) => return Err(error),
+    }
+
+    let mut hits = vault.search(input, false, key).await?;
+    match hits.len() {
+        0 => Err(ChacrabError::NotFound),
+        1 => Ok(hits.remove(0).item),
+        _ if options.json || !options.interactive => Err(ChacrabError::Config(
+            "search matches several items".to_owned(),
+        )),
+        _ => {
+            let labels = hits
+                .iter()
+                .map(|hit| {
+                    format!(
+                        "{}  {}  ({})",
+                        short_id(&hit.item.id.to_string()),
+                        hit.item.title,
+                        hit.item
+                            .username
+                            .as_deref()
+                            .unwrap_or(hit.item.r#type.as_str())
+                    )
+                })
+                .collect::<Vec<_>>();
+            let labels = labels.iter().map(String::as_str).collect::<Vec<_>>();
+            let choice = prompts::select("Several items match", &labels)?;
+            Ok(hits.swap_remove(choice).item)
         }
     }
+}
+
+pub async fn run() -> ChacrabResult<()> {
+    let mut cli = Cli::parse();
+    if let Err(err) = load_saved_config(&mut cli) {
+        error_msg(map_user_error(&err), ui_options(&cli));
+        return Err(err);
+    }
 
     let options = ui_options(&cli);
     configure_terminal(options.color);
@@ -116,31 +310,216 @@ pub async fn run() -> ChacrabResult<()> {
         );
     }
 
-    let repo = app_repo(&cli).await?;
-    let vault = VaultService::new(repo.clone());
-
     let session_indicator = match session::session_state() {
         session::SessionState::Active => SessionIndicator::Active,
         session::SessionState::Locked => SessionIndicator::Locked,
     };
 
+    let config_result = match &cli.command {
+        Commands::Config { action } => Some(run_config(
+            &cli,
+            options,
+            session_indicator,
+            action.as_ref(),
+        )),
+        Commands::Profile { action } => Some(run_profile(&cli, options, session_indicator, action)),
+        _ => None,
+    };
+    if let Some(result) = config_result {
+        if let Err(err) = &result {
+            error_msg(map_user_error(err), options);
+        }
+        return result;
+    }
+
+    let repo = app_repo(&cli).await?;
+    let vault = VaultService::new(repo.clone())
+        .with_revision_retention(cli.revision_retention)
+        .with_trash_retention_days(cli.trash_retention_days);
     let result = match &cli.command {
-        Commands::Init => run_init(&repo, &cli, options, session_indicator).await,
-        Commands::Login => run_login(&repo, &cli, options, session_indicator).await,
+        Commands::Init { private_metadata } => {
+            run_init(&repo, &cli, options, session_indicator, *private_metadata).await
+        }
+        Commands::Login { secret } => run_login(&repo, options, session_indicator, secret).await,
         Commands::Logout => run_logout(options, session_indicator),
-        Commands::AddPassword => run_add_password(&vault, &cli, options, session_indicator).await,
-        Commands::AddNote => run_add_note(&vault, &cli, options, session_indicator).await,
-        Commands::List => run_list(&vault, &cli, options, session_indicator).await,
+        Commands::ChangeMasterPassword => {
+            run_change_master_password(&vault, &cli, options, session_indicator).await
+        }
+        Commands::AddPassword {
+            title,
+            username,
+            url,
+            secret,
+            fields: custom_fields,
+        } => {
+            let fields = PasswordFields {
+                title: title.clone(),
+                username: username.clone(),
+                url: url.clone(),
+                custom_fields: custom_fields.fields.clone(),
+            };
+            run_add_password(&vault, &cli, options, session_indicator, fields, secret).await
+        }
+        Commands::AddNote {
+            title,
+            secret,
+            fields,
+        } => {
+            run_add_note(
+                &vault,
+                &cli,
+                options,
+                session_indicator,
+                title.clone(),
+                secret,
+                &fields.fields,
+            )
+            .await
+        }
+        Commands::AddCard { fields } => {
+            run_add_typed(
+                &vault,
+                &cli,
+                options,
+                session_indicator,
+                VaultItemType::Card,
+                &fields.fields,
+            )
+            .await
+        }
+        Commands::AddIdentity { fields } => {
+            run_add_typed(
+                &vault,
+                &cli,
+                options,
+                session_indicator,
+                VaultItemType::Identity,
+                &fields.fields,
+            )
+            .await
+        }
+        Commands::AddSshKey { fields } => {
+            run_add_typed(
+                &vault,
+                &cli,
+                options,
+                session_indicator,
+                VaultItemType::SshKey,
+                &fields.fields,
+            )
+            .await
+        }
+        Commands::AddApiKey { fields } => {
+            run_add_typed(
+                &vault,
+                &cli,
+                options,
+                session_indicator,
+                VaultItemType::ApiKey,
+                &fields.fields,
+            )
+            .await
+        }
+        Commands::List {
+            tag,
+            folder,
+            favorites,
+        } => {
+            let filter = ItemFilter {
+                tag: tag.clone(),
+                folder: folder.clone(),
+                favorites_only: *favorites,
+            };
+            run_list(&vault, &cli, options, session_indicator, &filter).await
+        }
+        Commands::Search { query, deep } => {
+            let query = query.join(" ");
+            run_search(&vault, &cli, options, session_indicator, &query, *deep).await
+        }
         Commands::Show { id } => run_show(&vault, &cli, options, session_indicator, id).await,
-        Commands::Delete { id } => run_delete(&vault, &cli, options, session_indicator, id).await,
+        Commands::Edit { id, secret } => {
+            run_edit(&vault, &cli, options, session_indicator, id, secret).await
+        }
+        Commands::History { id } => run_history(&vault, &cli, options, session_indicator, id).await,
+        Commands::Restore { id, revision } => {
+            run_restore(&vault, &cli, options, session_indicator, id, *revision).await
+        }
+        Commands::Totp { id, copy } => {
+            run_totp(&vault, &cli, options, session_indicator, id, *copy).await
+        }
+        Commands::Delete { id, yes } => {
+            run_delete(&vault, &cli, options, session_indicator, id, *yes).await
+        }
+        Commands::Tag {
+            action: TagAction::Add { id, tags },
+        } => {
+            let change = Organize::AddTags(tags);
+            run_organize(&vault, &cli, options, session_indicator, id, change).await
+        }
+        Commands::Tag {
+            action: TagAction::Remove { id, tags },
+        } => {
+            let change = Organize::RemoveTags(tags);
+            run_organize(&vault, &cli, options, session_indicator, id, change).await
+        }
+        Commands::Mv { id, folder } => {
+            let change = Organize::Move(folder);
+            run_organize(&vault, &cli, options, session_indicator, id, change).await
+        }
+        Commands::Favorite { id, remove } => {
+            let change = Organize::Favorite(!remove);
+            run_organize(&vault, &cli, options, session_indicator, id, change).await
+        }
         Commands::BackupExport { path } => {
             run_backup_export(&vault, &cli, options, session_indicator, path).await
         }
         Commands::BackupImport { path } => {
             run_backup_import(&vault, &cli, options, session_indicator, path).await
         }
-        Commands::Sync => run_sync(&vault, &cli, options, session_indicator).await,
-        Commands::Config => run_config(&cli, options, session_indicator),
+        Commands::Generate(args) => run_generate(args, options, session_indicator),
+        Commands::Sync {
+            action: Some(SyncAction::ExportBundle { path, full }),
+            ..
+        } => run_export_bundle(&vault, &cli, options, session_indicator, path, *full).await,
+        Commands::Sync {
+            action: Some(SyncAction::ImportBundle { path, on_conflict }),
+            ..
+        } => {
+            run_import_bundle(
+                &vault,
+                &cli,
+                options,
+                session_indicator,
+                path,
+                on_conflict.as_deref(),
+            )
+            .await
+        }
+        Commands::Sync {
+            action: None,
+            name,
+            all,
+            on_conflict,
+            dry_run,
+        } => {
+            let request = SyncRequest {
+                remote: name.as_deref(),
+                all: *all,
+                on_conflict: on_conflict.as_deref(),
+                dry_run: *dry_run,
+            };
+            run_sync(&vault, &cli, options, session_indicator, request).await
+        }
+        Commands::Remote { action } => run_remote(&cli, options, session_indicator, action),
+        Commands::Config { .. } | Commands::Profile { .. } => {
+            unreachable!("config and profile commands run before the vault opens")
+        }
+        Commands::Trash { action } => {
+            run_trash(&vault, &cli, options, session_indicator, action).await
+        }
+        Commands::Privacy { action } => {
+            run_privacy(&vault, &cli, options, session_indicator, action).await
+        }
     };
 
     if let Err(err) = &result {
@@ -150,13 +529,96 @@ pub async fn run() -> ChacrabResult<()> {
     result
 }
 
+fn load_saved_config(cli: &mut Cli) -> ChacrabResult<()> {
+    let recovering = matches!(
+        cli.command,
+        Commands::Config {
+            action: Some(ConfigAction::Reset { key: None })
+        }
+    );
+    let saved_config = match runtime_config::load() {
+        Err(_) if recovering => None,
+        result => result?,
+    };
+    let profile = cli
+        .profile
+        .clone()
+        .or_else(|| saved_config.as_ref()?.active_profile.clone())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_owned());
+    keyring::select_profile(&profile);
+
+    let managing_profiles = matches!(cli.command, Commands::Profile { .. });
+    let Some(saved_config) = saved_config else {
+        if profile == DEFAULT_PROFILE || managing_profiles {
+            return Ok(());
+        }
+        return Err(ChacrabError::Config("unknown profile".to_owned()));
+    };
+    match saved_config.profile() {
+        Ok(profile) => apply_saved_config(cli, &saved_config, profile),
+        Err(_) if managing_profiles => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+fn apply_saved_config(
+    cli: &mut Cli,
+    saved_config: &RuntimeConfig,
+    profile: &Profile,
+) -> ChacrabResult<()> {
+    let args = std::env::args().collect::<Vec<_>>();
+    let explicit = |flag: &str| runtime_config::cli_flag_present(&args, flag);
+
+    if !explicit("--backend") {
+        cli.backend = profile.backend.clone();
+    }
+    if !explicit("--database-url") {
+        cli.database_url = profile.database_url.clone();
+        if runtime_config::url_needs_secret(&cli.database_url) {
+            cli.database_secret = keyring::load_database_secret(keyring::active_profile())?;
+        }
+    }
+    if !explicit("--session-timeout-secs") {
+        cli.session_timeout_secs = saved_config.session.timeout_secs();
+    }
+    if !explicit("--revision-retention")
+        && let Some(retention) = profile.vault.revision_retention
+    {
+        cli.revision_retention = retention;
+    }
+    if !explicit("--trash-retention-days")
+        && let Some(days) = profile.vault.trash_retention_days
+    {
+        cli.trash_retention_days = days;
+    }
+    if !cli.json && !cli.quiet {
+        match saved_config.display.output.as_deref() {
+            Some("json") => cli.json = true,
+            Some("quiet") => cli.quiet = true,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn new_saved_config(cli: &Cli) -> RuntimeConfig {
+    let (database_url, _) = runtime_config::split_url_secret(&cli.database_url);
+    RuntimeConfig::with_profile(Profile::new(cli.backend.clone(), database_url))
+}
+
+fn saved_config_or(cli: &Cli) -> ChacrabResult<RuntimeConfig> {
+    Ok(runtime_config::load()?.unwrap_or_else(|| new_saved_config(cli)))
+}
+
 async fn run_init(
     repo: &AppRepository,
     cli: &Cli,
     options: UiOptions,
     session_indicator: SessionIndicator,
+    private_metadata: bool,
 ) -> ChacrabResult<()> {
     print_header("Chacrab Vault Initialization", session_indicator, options);
+    require_input(options)?;
     secure("Create master password:", options);
     let password = prompts::secure_password_with_confirmation(
         "Master password: ",
@@ -170,7 +632,7 @@ async fn run_init(
         return Err(ChacrabError::Config("operation cancelled".to_owned()));
     }
 
-    login::register(repo, password).await?;
+    login::register(repo, password, private_metadata).await?;
     let vault_id = repo
         .get_auth_record()
         .await?
@@ -183,24 +645,42 @@ async fn run_init(
         &format!("Storage: {}", backend_display(&cli.backend)),
         options,
     );
+    if private_metadata {
+        system("Metadata privacy: enabled", options);
+    }
 
-    runtime_config::save(&runtime_config::RuntimeConfig {
-        backend: cli.backend.clone(),
-        database_url: cli.database_url.clone(),
-    })?;
+    let (database_url, secret) = runtime_config::split_url_secret(&cli.database_url);
+    if let Some(secret) = &secret {
+        keyring::store_database_secret(keyring::active_profile(), secret)?;
+    }
+    let mut saved_config = saved_config_or(cli)?;
+    let profile = saved_config.profile_mut()?;
+    profile.backend = cli.backend.clone();
+    profile.database_url = database_url;
+    profile.vault = VaultSettings {
+        revision_retention: Some(cli.revision_retention),
+        trash_retention_days: Some(cli.trash_retention_days),
+    };
+    runtime_config::save(&saved_config)?;
 
     Ok(())
 }
 
 async fn run_login(
     repo: &AppRepository,
-    _cli: &Cli,
     options: UiOptions,
     session_indicator: SessionIndicator,
+    secret: &SecretArgs,
 ) -> ChacrabResult<()> {
     print_header("Chacrab Login", session_indicator, options);
-    secure("Enter master password:", options);
-    let password = prompts::secure_password_prompt("Master password: ")?;
+    let password = match prompts::piped_secret(secret)? {
+        Some(password) => password,
+        None => {
+            require_input(options)?;
+            secure("Enter master password:", options);
+            prompts::secure_password_prompt("Master password: ")?
+        }
+    };
     login::login(repo, password).await?;
     session::touch_session()?;
     success("Login successful.", options);
@@ -217,26 +697,121 @@ fn run_logout(options: UiOptions, session_indicator: SessionIndicator) -> Chacra
     Ok(())
 }
 
+async fn run_change_master_password(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+) -> ChacrabResult<()> {
+    print_header("Change Master Password", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+    require_input(options)?;
+
+    secure("Enter current master password:", options);
+    let current = prompts::secure_password_prompt("Current master password: ")?;
+    secure("Create new master password:", options);
+    let new_password = prompts::secure_password_with_confirmation(
+        "New master password: ",
+        "Confirm new master password: ",
+    )?;
+    password_policy::validate_master_password(new_password.expose_secret())?;
+
+    warning("The new password cannot be recovered.", options);
+    let proceed = prompts::confirmation_prompt("Proceed?", false)?;
+    if !proceed {
+        return Err(ChacrabError::Config("operation cancelled".to_owned()));
+    }
+
+    vault
+        .change_master_password(&current, &new_password)
+        .await?;
+    session::touch_session()?;
+
+    success("Master password changed.", options);
+    Ok(())
+}
+
+struct PasswordFields {
+    title: Option<String>,
+    username: Option<String>,
+    url: Option<String>,
+    custom_fields: Vec<String>,
+}
+
+fn require_input(options: UiOptions) -> ChacrabResult<()> {
+    if options.interactive {
+        Ok(())
+    } else {
+        Err(ChacrabError::Config("input required".to_owned()))
+    }
+}
+
+fn title_input(title: Option<String>, options: UiOptions) -> ChacrabResult<String> {
+    match title {
+        Some(title) if !title.trim().is_empty() => Ok(title),
+        Some(_) => Err(ChacrabError::Config("title is empty".to_owned())),
+        None => {
+            require_input(options)?;
+            prompts::input("Title")
+        }
+    }
+}
+
 async fn run_add_password(
     vault: &VaultService<AppRepository>,
     cli: &Cli,
     options: UiOptions,
     session_indicator: SessionIndicator,
+    fields: PasswordFields,
+    secret: &SecretArgs,
 ) -> ChacrabResult<()> {
     print_header("Add New Credential", session_indicator, options);
     session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
+
+    let custom_fields = forms::parse_custom_fields(&fields.custom_fields)?;
+    let piped = prompts::piped_secret(secret)?;
+    let prompt_rest = options.interactive && fields.title.is_none() && piped.is_none();
+    let title = title_input(fields.title, options)?;
+    let (username, url) = if prompt_rest {
+        (
+            prompts::optional_input("Username/Email")?,
+            prompts::optional_input("URL")?,
+        )
+    } else {
+        (fields.username, fields.url)
+    };
+    let password = match piped {
+        Some(password) => password,
+        None => {
+            require_input(options)?;
+            password_prompt("Password: ", options)?
+        }
+    };
 
-    let title = prompts::input("Title")?;
-    let username = prompts::optional_input("Username/Email")?;
-    let url = prompts::optional_input("URL")?;
-    let password = prompts::secure_password_prompt("Password: ")?;
-    let notes = prompts::multiline("Notes (optional multiline)")?;
+    let mut payload = EncryptedPayload::for_password(password, None);
+    payload.custom_fields = custom_fields;
+    if prompt_rest {
+        payload.notes = prompts::multiline("Notes (optional multiline)")?;
+        payload.totp = totp_prompt("TOTP secret or otpauth:// URI (optional): ")?;
+        payload.custom_fields = custom_fields_prompt(payload.custom_fields)?;
+    }
 
     let mut key = login::current_session_key()?;
     let item = vault
-        .add_password(title, username, url, password, notes, &key)
-        .await?;
+        .add_item(
+            NewVaultItem {
+                r#type: VaultItemType::Password,
+                title,
+                username,
+                url,
+                payload,
+            },
+            &key,
+        )
+        .await;
     key.zeroize();
+    let item = item?;
     session::touch_session()?;
 
     success("Credential stored securely.", options);
@@ -244,26 +819,157 @@ async fn run_add_password(
     Ok(())
 }
 
+fn custom_fields_prompt(fields: Vec<CustomField>) -> ChacrabResult<Vec<CustomField>> {
+    if prompts::confirmation_prompt("Add custom fields?", false)? {
+        forms::prompt_custom_fields(fields)
+    } else {
+        Ok(fields)
+    }
+}
+
+fn totp_prompt(prompt: &str) -> ChacrabResult<Option<TotpSecret>> {
+    let input = prompts::secure_password_prompt(prompt)?;
+    if input.expose_secret().trim().is_empty() {
+        return Ok(None);
+    }
+    totp::parse_totp_input(input.expose_secret()).map(Some)
+}
+
+fn password_prompt(prompt: &str, options: UiOptions) -> ChacrabResult<SecretString> {
+    let choice = prompts::select(
+        "Password source",
+        &["Enter manually", "Generate password", "Generate passphrase"],
+    )?;
+    let defaults = runtime_config::load()?.unwrap_or_default().generator;
+    let generated = match choice {
+        1 => generator::generate_password(&defaults.password_options())?,
+        2 => generator::generate_passphrase(&PassphraseOptions {
+            capitalize: defaults.capitalize.unwrap_or(true),
+            ..defaults.passphrase_options()
+        })?,
+        _ => return prompts::secure_password_prompt(prompt),
+    };
+    system(
+        &format!(
+            "Generated secret entropy: {:.1} bits",
+            generated.entropy_bits
+        ),
+        options,
+    );
+    Ok(generated.value)
+}
+
 async fn run_add_note(
     vault: &VaultService<AppRepository>,
     cli: &Cli,
     options: UiOptions,
     session_indicator: SessionIndicator,
+    title: Option<String>,
+    secret: &SecretArgs,
+    custom_fields: &[String],
 ) -> ChacrabResult<()> {
     print_header("Add Secure Note", session_indicator, options);
     session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
+
+    let custom_fields = forms::parse_custom_fields(custom_fields)?;
+    let piped = prompts::piped_secret(secret)?;
+    let prompt_rest = options.interactive && title.is_none() && piped.is_none();
+    let title = title_input(title, options)?;
+    let note = match piped {
+        Some(note) => note,
+        None => {
+            require_input(options)?;
+            let note = prompts::multiline("Content (multiline)")?.unwrap_or_default();
+            SecretString::new(note.into_boxed_str())
+        }
+    };
+    let mut payload = EncryptedPayload::for_note(note);
+    payload.custom_fields = custom_fields;
+    if prompt_rest {
+        payload.custom_fields = custom_fields_prompt(payload.custom_fields)?;
+    }
+
+    let mut key = login::current_session_key()?;
+    let result = vault
+        .add_item(
+            NewVaultItem {
+                r#type: VaultItemType::Note,
+                title,
+                username: None,
+                url: None,
+                payload,
+            },
+            &key,
+        )
+        .await;
+    key.zeroize();
+    result?;
+    session::touch_session()?;
+
+    success("Secure note stored.", options);
+    Ok(())
+}
+
+async fn run_add_typed(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    item_type: VaultItemType,
+    custom_fields: &[String],
+) -> ChacrabResult<()> {
+    let header = match item_type {
+        VaultItemType::Card => "Add Payment Card",
+        VaultItemType::Identity => "Add Identity",
+        VaultItemType::SshKey => "Add SSH Key",
+        VaultItemType::ApiKey => "Add API Credential",
+        VaultItemType::Password | VaultItemType::Note => {
+            return Err(ChacrabError::UnsupportedItemType(
+                item_type.as_str().to_owned(),
+            ));
+        }
+    };
+    print_header(header, session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
 
+    require_input(options)?;
+    let custom_fields = forms::parse_custom_fields(custom_fields)?;
     let title = prompts::input("Title")?;
-    let note = prompts::multiline("Content (multiline)")?.unwrap_or_default();
+    let payload = match item_type {
+        VaultItemType::Card => EncryptedPayload::for_card(forms::prompt_card(None)?, None),
+        VaultItemType::Identity => {
+            EncryptedPayload::for_identity(forms::prompt_identity(None)?, None)
+        }
+        VaultItemType::SshKey => EncryptedPayload::for_ssh_key(forms::prompt_ssh_key(None)?, None),
+        _ => EncryptedPayload::for_api_key(forms::prompt_api_key(None)?, None),
+    };
+    let payload = EncryptedPayload {
+        notes: prompts::multiline("Notes (optional multiline)")?,
+        custom_fields: custom_fields_prompt(custom_fields)?,
+        ..payload
+    };
 
     let mut key = login::current_session_key()?;
-    vault
-        .add_note(title, SecretString::new(note.into_boxed_str()), &key)
-        .await?;
+    let item = vault
+        .add_item(
+            NewVaultItem {
+                r#type: item_type,
+                title,
+                username: None,
+                url: None,
+                payload,
+            },
+            &key,
+        )
+        .await;
     key.zeroize();
+    let item = item?;
     session::touch_session()?;
 
-    success("Secure note stored.", options);
+    success("Item stored securely.", options);
+    system(&format!("ID: {}", short_id(&item.id.to_string())), options);
     Ok(())
 }
 
@@ -272,11 +978,18 @@ async fn run_list(
     cli: &Cli,
     options: UiOptions,
     session_indicator: SessionIndicator,
+    filter: &ItemFilter,
 ) -> ChacrabResult<()> {
     print_header("Vault Items", session_indicator, options);
     session::enforce_timeout(cli.session_timeout_secs)?;
 
-    let items = vault.list().await?;
+    let mut key = login::current_session_key()?;
+    let items = vault.list_revealed(&key).await;
+    key.zeroize();
+    let items = items?
+        .into_iter()
+        .filter(|item| filter.matches(item))
+        .collect::<Vec<_>>();
     session::touch_session()?;
 
     if options.json {
@@ -285,8 +998,11 @@ async fn run_list(
             .map(|item| {
                 json!({
                     "id": short_id(&item.id.to_string()),
-                    "type": format!("{:?}", item.r#type).to_lowercase(),
+                    "type": item.r#type.as_str(),
                     "title": item.title,
+                    "folder": item.folder,
+                    "tags": item.tags,
+                    "favorite": item.favorite,
                     "updated": item.updated_at.format("%Y-%m-%d").to_string()
                 })
             })
@@ -302,6 +1018,51 @@ async fn run_list(
     Ok(())
 }
 
+async fn run_search(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    query: &str,
+    deep: bool,
+) -> ChacrabResult<()> {
+    print_header("Search Results", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+
+    let mut key = login::current_session_key()?;
+    let hits = vault.search(query, deep, &key).await;
+    key.zeroize();
+    let hits = hits?;
+    session::touch_session()?;
+
+    if options.json {
+        let output = hits
+            .iter()
+            .map(|hit| {
+                json!({
+                    "id": short_id(&hit.item.id.to_string()),
+                    "type": hit.item.r#type.as_str(),
+                    "title": hit.item.title,
+                    "matched": hit.matched,
+                    "score": hit.score
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string(&output).map_err(|_| ChacrabError::Serialization)?
+        );
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        system("No items match.", options);
+        return Ok(());
+    }
+    table::print_search_table(&hits);
+    Ok(())
+}
+
 async fn run_show(
     vault: &VaultService<AppRepository>,
     cli: &Cli,
@@ -312,169 +1073,1320 @@ async fn run_show(
     print_header("Credential Details", session_indicator, options);
     session::enforce_timeout(cli.session_timeout_secs)?;
 
-    let all_items = vault.list().await?;
-    let resolved_id = parse_or_resolve_id(id, &all_items)?;
-
     let mut key = login::current_session_key()?;
-    let (item, payload) = vault.show_decrypted(resolved_id, &key).await?;
+    let resolved_id = match resolve_item(vault, id, &key, options).await {
+        Ok(item) => item.id,
+        Err(error) => {
+            key.zeroize();
+            return Err(error);
+        }
+    };
+    let (item, mut payload) = match vault.show_payload(resolved_id, &key).await {
+        Ok(shown) => shown,
+        Err(error) => {
+            key.zeroize();
+            return Err(error);
+        }
+    };
     key.zeroize();
+    let view = forms::describe(&item, &payload);
+    let has_totp = payload.totp.is_some();
+    payload.zeroize();
     session::touch_session()?;
 
-    let username = item.username.clone().unwrap_or_else(|| "-".to_owned());
-    let url = item.url.clone().unwrap_or_else(|| "-".to_owned());
-    let mut password = payload
-        .get("password")
-        .and_then(|value| value.as_str())
-        .unwrap_or_default()
-        .to_owned();
-
     if options.json {
-        let out = json!({
+        let mut out = json!({
             "id": short_id(&item.id.to_string()),
-            "type": format!("{:?}", item.r#type).to_lowercase(),
+            "type": item.r#type.as_str(),
             "title": item.title,
-            "username": username,
-            "url": url,
-            "password": "********"
+            "totp": has_totp,
         });
+        for (label, value) in &view.details {
+            out[label.to_lowercase().replace(' ', "_")] = json!(value);
+        }
+        out["custom_fields"] = view
+            .custom_fields
+            .iter()
+            .map(|(name, value)| json!({"name": name, "value": value}))
+            .collect();
         println!(
             "{}",
             serde_json::to_string(&out).map_err(|_| ChacrabError::Serialization)?
         );
-        password.zeroize();
         return Ok(());
     }
 
     system(&format!("Title: {}", item.title), options);
-    system(&format!("Username: {username}"), options);
-    system(&format!("URL: {url}"), options);
-    system("Password: ********", options);
+    for (label, value) in view.details.iter().chain(&view.custom_fields) {
+        system(&format!("{label}: {value}"), options);
+    }
+    if has_totp {
+        system("TOTP: configured (use `chacrab totp`)", options);
+    }
+    if !options.interactive {
+        return Ok(());
+    }
 
     if is_insecure_terminal() {
         warning(
             "Sensitive actions are blocked on insecure terminal output.",
             options,
         );
-        password.zeroize();
         return Ok(());
     }
 
-    let choice = prompts::select("Options", &["Reveal password", "Copy to clipboard", "Exit"])?;
-
-    match choice {
-        0 => {
-            if is_insecure_terminal() {
-                warning("Reveal blocked on insecure terminal.", options);
-            } else if password.is_empty() {
-                warning("No password stored for this item.", options);
-            } else {
-                system(&format!("Password: {}", password), options);
-                warning("Password will clear in 10 seconds.", options);
-                tokio::time::sleep(Duration::from_secs(10)).await;
-                clear_screen(options);
-                system("Password view cleared.", options);
-            }
-        }
-        1 => {
-            if is_insecure_terminal() {
-                warning("Clipboard copy blocked on insecure terminal.", options);
-            } else if password.is_empty() {
-                warning("No password stored for this item.", options);
-            } else {
-                let mut clipboard = arboard::Clipboard::new()
-                    .map_err(|_| ChacrabError::Config("clipboard unavailable".to_owned()))?;
-                clipboard
-                    .set_text(password.clone())
-                    .map_err(|_| ChacrabError::Config("clipboard write failed".to_owned()))?;
-                success(
-                    "Password copied. Clearing clipboard in 15 seconds.",
-                    options,
-                );
-                tokio::time::sleep(Duration::from_secs(15)).await;
-                let _ = clipboard.set_text(String::new());
-                system("Clipboard cleared.", options);
-            }
+    let saved_config = runtime_config::load()?.unwrap_or_default();
+    let mut actions = Vec::new();
+    for entry in &view.secrets {
+        actions.push((format!("Reveal {}", entry.0), Some((entry, false))));
+        if saved_config.clipboard.enabled() {
+            actions.push((
+                format!("Copy {} to clipboard", entry.0),
+                Some((entry, true)),
+            ));
         }
-        _ => {}
     }
-
-    password.zeroize();
+    if saved_config.clipboard.enabled() {
+        for entry in &view.plain_fields {
+            actions.push((
+                format!("Copy {} to clipboard", entry.0),
+                Some((entry, true)),
+            ));
+        }
+    }
+    actions.push(("Exit".to_owned(), None));
+    let labels = actions
+        .iter()
+        .map(|(label, _)| label.as_str())
+        .collect::<Vec<_>>();
+    let choice = prompts::select("Options", &labels)?;
+
+    let Some(((label, secret), copy)) = actions.get(choice).and_then(|(_, action)| *action) else {
+        return Ok(());
+    };
+    if secret.is_empty() {
+        warning(&format!("No {label} stored for this item."), options);
+    } else if copy {
+        let clear_secs = saved_config.clipboard.clear_secs();
+        copy_with_auto_clear(
+            secret.clone(),
+            &format!(
+                "{} copied. Clearing clipboard in {clear_secs} seconds.",
+                capitalize(label)
+            ),
+            Duration::from_secs(clear_secs),
+            options,
+        )
+        .await?;
+    } else {
+        let reveal_secs = saved_config.display.reveal_secs();
+        system(&format!("{}: {secret}", capitalize(label)), options);
+        warning(
+            &format!("{} will clear in {reveal_secs} seconds.", capitalize(label)),
+            options,
+        );
+        tokio::time::sleep(Duration::from_secs(reveal_secs)).await;
+        clear_screen(options);
+        system(&format!("{} view cleared.", capitalize(label)), options);
+    }
+
+    Ok(())
+}
+
+fn capitalize(label: &str) -> String {
+    let mut chars = label.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+async fn copy_with_auto_clear(
+    value: String,
+    message: &str,
+    clear_after: Duration,
+    options: UiOptions,
+) -> ChacrabResult<()> {
+    let mut clipboard = arboard::Clipboard::new()
+        .map_err(|_| ChacrabError::Config("clipboard unavailable".to_owned()))?;
+    clipboard
+        .set_text(value)
+        .map_err(|_| ChacrabError::Config("clipboard write failed".to_owned()))?;
+    success(message, options);
+    tokio::time::sleep(clear_after).await;
+    let _ = clipboard.set_text(String::new());
+    system("Clipboard cleared.", options);
+    Ok(())
+}
+
+async fn run_totp(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    id: &str,
+    copy: bool,
+) -> ChacrabResult<()> {
+    print_header("One-Time Code", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+
+    let saved_config = runtime_config::load()?.unwrap_or_default();
+    if copy && !saved_config.clipboard.enabled() {
+        return Err(ChacrabError::Config("clipboard is disabled".to_owned()));
+    }
+
+    if is_insecure_terminal() {
+        warning(
+            "Sensitive actions are blocked on insecure terminal output.",
+            options,
+        );
+        return Ok(());
+    }
+
+    let mut key = login::current_session_key()?;
+    let result = resolve_totp(vault, id, &key, options).await;
+    key.zeroize();
+    let (item, mut code) = result?;
+    session::touch_session()?;
+
+    if options.json {
+        let out = json!({
+            "id": short_id(&item.id.to_string()),
+            "title": item.title,
+            "code": code.code,
+            "remaining_secs": code.remaining_secs,
+        });
+        println!(
+            "{}",
+            serde_json::to_string(&out).map_err(|_| ChacrabError::Serialization)?
+        );
+    } else {
+        system(&format!("Title: {}", item.title), options);
+        system(&format!("Code: {}", code.code), options);
+        system(
+            &format!("Expires in: {} seconds", code.remaining_secs),
+            options,
+        );
+    }
+
+    if copy {
+        let clear_secs = saved_config.clipboard.totp_clear_secs();
+        copy_with_auto_clear(
+            code.code.clone(),
+            &format!("Code copied. Clearing clipboard in {clear_secs} seconds."),
+            Duration::from_secs(clear_secs),
+            options,
+        )
+        .await?;
+    }
+
+    code.code.zeroize();
+    Ok(())
+}
+
+async fn resolve_totp(
+    vault: &VaultService<AppRepository>,
+    id: &str,
+    key: &[u8; 32],
+    options: UiOptions,
+) -> ChacrabResult<(VaultItem, TotpCode)> {
+    let item = resolve_item(vault, id, key, options).await?;
+    vault.totp_code(item.id, key).await
+}
+
+async fn run_history(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    id: &str,
+) -> ChacrabResult<()> {
+    print_header("Item History", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+
+    let mut key = login::current_session_key()?;
+    let result = match resolve_item(vault, id, &key, options).await {
+        Ok(item) => vault
+            .history(item.id, &key)
+            .await
+            .map(|entries| (item, entries)),
+        Err(error) => Err(error),
+    };
+    key.zeroize();
+    let (item, entries) = result?;
+    session::touch_session()?;
+
+    if options.json {
+        let output = entries
+            .iter()
+            .rev()
+            .map(|entry| {
+                json!({
+                    "revision": entry.revision,
+                    "recorded": entry.recorded_at.to_rfc3339(),
+                    "title": entry.item.title,
+                    "changes": entry
+                        .changes
+                        .iter()
+                        .map(|change| json!({
+                            "field": change.field,
+                            "before": change.before,
+                            "after": change.after,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "secrets_changed": entry.secrets_changed,
+                })
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string(&output).map_err(|_| ChacrabError::Serialization)?
+        );
+        return Ok(());
+    }
+
+    system(&format!("Title: {}", item.title), options);
+    if entries.is_empty() {
+        system("No revisions recorded.", options);
+        return Ok(());
+    }
+    for entry in entries.iter().rev() {
+        println!(
+            "Revision {}  {}",
+            entry.revision,
+            entry.recorded_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        for change in &entry.changes {
+            println!("  {}: {} -> {}", change.field, change.before, change.after);
+        }
+        if entry.secrets_changed {
+            println!("  secret fields changed");
+        }
+        if entry.changes.is_empty() && !entry.secrets_changed {
+            println!("  no visible changes");
+        }
+    }
+    Ok(())
+}
+
+async fn run_restore(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    id: &str,
+    revision: u32,
+) -> ChacrabResult<()> {
+    print_header("Restore Item", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
+
+    let mut key = login::current_session_key()?;
+    let result = match resolve_item(vault, id, &key, options).await {
+        Ok(item) => vault.restore_revision(item.id, revision, &key).await,
+        Err(error) => Err(error),
+    };
+    key.zeroize();
+    let item = result?;
+    session::touch_session()?;
+
+    success(&format!("Item restored to revision {revision}."), options);
+    system(&format!("Title: {}", item.title), options);
+    system("The replaced version was kept as a new revision.", options);
+    Ok(())
+}
+
+async fn run_edit(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    id: &str,
+    secret: &SecretArgs,
+) -> ChacrabResult<()> {
+    print_header("Edit Item", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
+
+    let piped = prompts::piped_secret(secret)?;
+    if piped.is_none() {
+        require_input(options)?;
+    }
+    let mut key = login::current_session_key()?;
+    let result = match piped {
+        Some(secret) => replace_item_secret(vault, id, secret, &key, options).await,
+        None => edit_item(vault, id, &key, options).await,
+    };
+    key.zeroize();
+    let item = result?;
+    session::touch_session()?;
+
+    success("Item updated.", options);
+    system(&format!("ID: {}", short_id(&item.id.to_string())), options);
+    Ok(())
+}
+
+async fn replace_item_secret(
+    vault: &VaultService<AppRepository>,
+    id: &str,
+    secret: SecretString,
+    key: &[u8; 32],
+    options: UiOptions,
+) -> ChacrabResult<VaultItem> {
+    let item = resolve_item(vault, id, key, options).await?;
+    let changes = match item.r#type {
+        VaultItemType::Password => ItemChanges {
+            password: Some(secret),
+            ..ItemChanges::default()
+        },
+        VaultItemType::Note => ItemChanges {
+            notes: Some(Some(secret)),
+            ..ItemChanges::default()
+        },
+        _ => {
+            return Err(ChacrabError::Config(
+                "item has no secret to replace".to_owned(),
+            ));
+        }
+    };
+    vault.update_item(item.id, changes, key).await
+}
+
+async fn edit_item(
+    vault: &VaultService<AppRepository>,
+    id: &str,
+    key: &[u8; 32],
+    options: UiOptions,
+) -> ChacrabResult<VaultItem> {
+    let resolved_id = resolve_item(vault, id, key, options).await?.id;
+    let (item, mut payload) = vault.show_payload(resolved_id, key).await?;
+    let mut current_notes = payload.notes.clone().unwrap_or_default();
+
+    let mut changes = ItemChanges {
+        title: Some(prompts::input_with_initial("Title", &item.title)?),
+        ..ItemChanges::default()
+    };
+
+    match item.r#type {
+        VaultItemType::Password => {
+            changes.username = Some(prompts::optional_input_with_initial(
+                "Username/Email",
+                item.username.as_deref().unwrap_or_default(),
+            )?);
+            changes.url = Some(prompts::optional_input_with_initial(
+                "URL",
+                item.url.as_deref().unwrap_or_default(),
+            )?);
+            if prompts::confirmation_prompt("Change password?", false)? {
+                changes.password = Some(password_prompt("New password: ", options)?);
+            }
+            if prompts::confirmation_prompt("Change TOTP secret?", false)? {
+                changes.totp = Some(totp_prompt(
+                    "New TOTP secret or otpauth:// URI (empty to remove): ",
+                )?);
+            }
+        }
+        VaultItemType::Note => {
+            if let Some(content) = prompts::multiline(&current_notes)? {
+                changes.notes = Some(Some(SecretString::new(content.into_boxed_str())));
+            }
+        }
+        VaultItemType::Card => changes.card = Some(forms::prompt_card(payload.card.as_ref())?),
+        VaultItemType::Identity => {
+            changes.identity = Some(forms::prompt_identity(payload.identity.as_ref())?)
+        }
+        VaultItemType::SshKey => {
+            changes.ssh_key = Some(forms::prompt_ssh_key(payload.ssh_key.as_ref())?)
+        }
+        VaultItemType::ApiKey => {
+            changes.api_key = Some(forms::prompt_api_key(payload.api_key.as_ref())?)
+        }
+    }
+
+    if prompts::confirmation_prompt("Edit custom fields?", false)? {
+        changes.custom_fields = Some(forms::prompt_custom_fields(payload.custom_fields.clone())?);
+    }
+    if item.r#type != VaultItemType::Note && prompts::confirmation_prompt("Edit notes?", false)? {
+        changes.notes = Some(
+            prompts::multiline(&current_notes)?
+                .filter(|notes| !notes.trim().is_empty())
+                .map(|notes| SecretString::new(notes.into_boxed_str())),
+        );
+    }
+    current_notes.zeroize();
+    payload.zeroize();
+
+    vault.update_item(resolved_id, changes, key).await
+}
+
+async fn run_delete(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    id: &str,
+    yes: bool,
+) -> ChacrabResult<()> {
+    print_header("Delete Item", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
+
+    let mut key = login::current_session_key()?;
+    let item = resolve_item(vault, id, &key, options).await;
+    key.zeroize();
+    let item = item?;
+
+    if !yes {
+        if options.json || !options.interactive {
+            return Err(ChacrabError::Config("confirmation required".to_owned()));
+        }
+        warning("Are you sure you want to delete this item?", options);
+        let typed = prompts::input("Type the title to confirm")?;
+        if typed != item.title {
+            return Err(ChacrabError::Config(
+                "confirmation text did not match title".to_owned(),
+            ));
+        }
+    }
+
+    vault.move_to_trash(item.id).await?;
+    session::touch_session()?;
+    success("Item moved to trash.", options);
+    system(
+        &format!(
+            "Restore it with `chacrab trash restore {}`.",
+            short_id(&item.id.to_string())
+        ),
+        options,
+    );
+    Ok(())
+}
+
+fn confirm_destructive(prompt: &str, yes: bool, options: UiOptions) -> ChacrabResult<()> {
+    if yes {
+        return Ok(());
+    }
+    if options.json || !options.interactive {
+        return Err(ChacrabError::Config("confirmation required".to_owned()));
+    }
+    if !prompts::confirmation_prompt(prompt, false)? {
+        return Err(ChacrabError::Config("operation cancelled".to_owned()));
+    }
+    Ok(())
+}
+
+async fn purge_expired_trash(vault: &VaultService<AppRepository>, options: UiOptions) {
+    if vault.purge_expired_trash().await.is_err() {
+        warning("Expired trash items could not be purged.", options);
+    }
+}
+
+async fn run_trash(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    action: &TrashAction,
+) -> ChacrabResult<()> {
+    print_header("Trash", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
+
+    let mut key = login::current_session_key()?;
+    let items = vault.list_trash(&key).await;
+    let items = match items {
+        Ok(items) => items,
+        Err(error) => {
+            key.zeroize();
+            return Err(error);
+        }
+    };
+
+    match action {
+        TrashAction::List => {
+            key.zeroize();
+            session::touch_session()?;
+            if options.json {
+                let output = items
+                    .iter()
+                    .map(|item| {
+                        json!({
+                            "id": short_id(&item.id.to_string()),
+                            "type": item.r#type.as_str(),
+                            "title": item.title,
+                            "deleted": item.deleted_at.map(|deleted_at| deleted_at.to_rfc3339()),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                println!(
+                    "{}",
+                    serde_json::to_string(&output).map_err(|_| ChacrabError::Serialization)?
+                );
+            } else if items.is_empty() {
+                system("Trash is empty.", options);
+            } else {
+                table::print_trash_table(&items);
+                system(
+                    &format!(
+                        "Items are purged {} days after deletion.",
+                        cli.trash_retention_days
+                    ),
+                    options,
+                );
+            }
+        }
+        TrashAction::Restore { id } => {
+            let restored = match parse_or_resolve_id(id, &items) {
+                Ok(resolved_id) => vault.restore_from_trash(resolved_id, &key).await,
+                Err(error) => Err(error),
+            };
+            key.zeroize();
+            let item = restored?;
+            session::touch_session()?;
+            success("Item restored from trash.", options);
+            system(&format!("Title: {}", item.title), options);
+        }
+        TrashAction::Empty { yes } => {
+            key.zeroize();
+            warning(
+                &format!("{} item(s) will be deleted permanently.", items.len()),
+                options,
+            );
+            confirm_destructive("Empty trash?", *yes, options)?;
+            let purged = vault.empty_trash().await?;
+            session::touch_session()?;
+            success(&format!("Deleted {purged} item(s) permanently."), options);
+        }
+    }
+    Ok(())
+}
+
+fn run_generate(
+    args: &GenerateArgs,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+) -> ChacrabResult<()> {
+    print_header("Generate Secret", session_indicator, options);
+    let defaults = runtime_config::load()?.unwrap_or_default().generator;
+    let generated = if args.passphrase || defaults.passphrase() {
+        let passphrase = defaults.passphrase_options();
+        generator::generate_passphrase(&PassphraseOptions {
+            words: args.words.unwrap_or(passphrase.words),
+            separator: args.separator.clone().unwrap_or(passphrase.separator),
+            capitalize: args.capitalize || passphrase.capitalize,
+            enforce_policy: args.policy,
+        })?
+    } else {
+        let password = defaults.password_options();
+        generator::generate_password(&PasswordOptions {
+            length: args.length.unwrap_or(password.length),
+            lowercase: !args.no_lowercase,
+            uppercase: !args.no_uppercase,
+            digits: !args.no_digits,
+            symbols: !args.no_symbols && password.symbols,
+            exclude_ambiguous: args.exclude_ambiguous || password.exclude_ambiguous,
+            enforce_policy: args.policy,
+        })?
+    };
+
+    if options.json {
+        let out = json!({
+            "value": generated.value.expose_secret(),
+            "entropy_bits": generated.entropy_bits,
+        });
+        println!(
+            "{}",
+            serde_json::to_string(&out).map_err(|_| ChacrabError::Serialization)?
+        );
+        return Ok(());
+    }
+
+    println!("{}", generated.value.expose_secret());
+    system(
+        &format!("Entropy: {:.1} bits", generated.entropy_bits),
+        options,
+    );
+    Ok(())
+}
+
+enum Organize<'a> {
+    AddTags(&'a [String]),
+    RemoveTags(&'a [String]),
+    Move(&'a str),
+    Favorite(bool),
+}
+
+async fn run_organize(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    id: &str,
+    change: Organize<'_>,
+) -> ChacrabResult<()> {
+    print_header("Organize Item", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
+
+    let mut key = login::current_session_key()?;
+    let result = organize_item(vault, id, &key, options, change).await;
+    key.zeroize();
+    let item = result?;
+    session::touch_session()?;
+
+    success("Item updated.", options);
+    system(&format!("Title: {}", item.title), options);
+    system(
+        &format!("Folder: {}", item.folder.as_deref().unwrap_or("/")),
+        options,
+    );
+    system(
+        &format!(
+            "Tags: {}",
+            if item.tags.is_empty() {
+                "-".to_owned()
+            } else {
+                item.tags.join(", ")
+            }
+        ),
+        options,
+    );
+    system(&format!("Favorite: {}", item.favorite), options);
     Ok(())
 }
 
-async fn run_delete(
+async fn organize_item(
+    vault: &VaultService<AppRepository>,
+    id: &str,
+    key: &[u8; 32],
+    options: UiOptions,
+    change: Organize<'_>,
+) -> ChacrabResult<VaultItem> {
+    let resolved_id = resolve_item(vault, id, key, options).await?.id;
+    match change {
+        Organize::AddTags(tags) => vault.add_tags(resolved_id, tags.to_vec(), key).await,
+        Organize::RemoveTags(tags) => vault.remove_tags(resolved_id, tags, key).await,
+        Organize::Move(folder) => vault.move_to_folder(resolved_id, folder, key).await,
+        Organize::Favorite(favorite) => vault.set_favorite(resolved_id, favorite, key).await,
+    }
+}
+
+struct SyncRequest<'a> {
+    remote: Option<&'a str>,
+    all: bool,
+    on_conflict: Option<&'a str>,
+    dry_run: bool,
+}
+
+struct SyncTarget {
+    remote: SyncRemote,
+    secret: Option<SecretString>,
+    policy: ConflictPolicy,
+}
+
+async fn run_sync(
     vault: &VaultService<AppRepository>,
     cli: &Cli,
     options: UiOptions,
     session_indicator: SessionIndicator,
-    id: &str,
+    request: SyncRequest<'_>,
 ) -> ChacrabResult<()> {
-    print_header("Delete Item", session_indicator, options);
+    print_header(
+        if request.dry_run { "Sync Plan" } else { "Sync" },
+        session_indicator,
+        options,
+    );
     session::enforce_timeout(cli.session_timeout_secs)?;
+    if !request.dry_run {
+        purge_expired_trash(vault, options).await;
+    }
 
-    let all_items = vault.list().await?;
-    let resolved_id = parse_or_resolve_id(id, &all_items)?;
-    let item = vault.repository().get_item(resolved_id).await?;
-
-    warning("Are you sure you want to delete this item?", options);
-    let typed = prompts::input("Type the title to confirm")?;
-    if typed != item.title {
+    let targets = sync_targets(&request)?;
+    if !request.dry_run
+        && (options.json || !options.interactive)
+        && targets
+            .iter()
+            .any(|target| target.policy == ConflictPolicy::Interactive)
+    {
         return Err(ChacrabError::Config(
-            "confirmation text did not match title".to_owned(),
+            "interactive conflict resolution needs a terminal".to_owned(),
         ));
     }
 
-    vault.delete(resolved_id).await?;
+    for target in targets {
+        if request.all {
+            system(&format!("Remote: {}", target.remote.name), options);
+        }
+        sync_with_target(vault, target, request.dry_run, options).await?;
+    }
+    Ok(())
+}
+
+async fn sync_with_target(
+    vault: &VaultService<AppRepository>,
+    target: SyncTarget,
+    dry_run: bool,
+    options: UiOptions,
+) -> ChacrabResult<()> {
+    syncing(
+        if dry_run {
+            "Comparing encrypted vault with the sync remote..."
+        } else {
+            "Syncing encrypted vault..."
+        },
+        options,
+    );
+    let database_url = target.remote.url_with_secret(target.secret.as_ref())?;
+    let remote = if dry_run {
+        AppRepository::open_existing(&target.remote.backend, database_url.expose_secret()).await?
+    } else {
+        let remote =
+            AppRepository::connect(&target.remote.backend, database_url.expose_secret()).await?;
+        remote.init().await?;
+        remote
+    };
+    let remote_name = target.remote.identity();
+    let mut key = login::current_session_key()?;
+    let outcome = match SyncEngine::plan(
+        vault.repository(),
+        &remote,
+        &remote_name,
+        &key,
+        target.policy,
+    )
+    .await
+    {
+        Ok(plan) if dry_run => print_sync_plan(&plan, &key, options).map(|()| None),
+        Ok(plan) => apply_sync_plan(vault.repository(), &remote, &key, plan, options)
+            .await
+            .map(Some),
+        Err(err) => Err(err),
+    };
+    key.zeroize();
+    if let Err(ChacrabError::Integrity(detail)) = &outcome {
+        warning(&format!("Integrity check: {detail}"), options);
+    }
+    let outcome = outcome?;
     session::touch_session()?;
-    success("Item deleted permanently.", options);
+    let Some(report) = outcome else {
+        return Ok(());
+    };
+    success("Sync complete.", options);
+    system(&format!("Items uploaded: {}", report.uploaded), options);
+    system(&format!("Items downloaded: {}", report.downloaded), options);
+    system(
+        &format!(
+            "Deletions applied: {} local, {} remote",
+            report.local_deletions, report.remote_deletions
+        ),
+        options,
+    );
+    warn_conflicts(&report, options);
     Ok(())
 }
 
-async fn run_sync(
+async fn apply_sync_plan(
+    local: &AppRepository,
+    remote: &AppRepository,
+    key: &[u8; 32],
+    plan: SyncPlan,
+    options: UiOptions,
+) -> ChacrabResult<SyncReport> {
+    let progress = if options.json || options.quiet || plan.steps.is_empty() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(plan.steps.len() as u64)
+    };
+    progress.set_style(
+        ProgressStyle::with_template("{bar:40.cyan/blue} {pos}/{len}")
+            .map_err(|_| ChacrabError::Config("invalid progress style".to_owned()))?,
+    );
+    let report = SyncEngine::apply(
+        local,
+        remote,
+        key,
+        plan,
+        &mut CliConflictResolver {
+            progress: &progress,
+        },
+        |_| progress.inc(1),
+    )
+    .await;
+    progress.finish_and_clear();
+    report
+}
+
+async fn run_export_bundle(
     vault: &VaultService<AppRepository>,
     cli: &Cli,
     options: UiOptions,
     session_indicator: SessionIndicator,
+    path: &str,
+    full: bool,
 ) -> ChacrabResult<()> {
-    print_header("Sync", session_indicator, options);
+    print_header("Sync Bundle Export", session_indicator, options);
     session::enforce_timeout(cli.session_timeout_secs)?;
 
-    syncing("Syncing encrypted vault...", options);
-    let local_count = vault.list().await?.len() as u64;
-    let remote = sync_remote_repo().await?;
-    let remote_count = remote.list_items().await?.len() as u64;
-    let total = (local_count + remote_count).max(1);
-
-    if !options.json && !options.quiet {
-        let progress = ProgressBar::new(total.max(1));
-        progress.set_style(
-            ProgressStyle::with_template("{bar:40.cyan/blue} {pos}/{len}")
-                .map_err(|_| ChacrabError::Config("invalid progress style".to_owned()))?,
-        );
-        for _ in 0..total.max(1) {
-            progress.inc(1);
-            tokio::time::sleep(Duration::from_millis(50)).await;
-        }
-        progress.finish_and_clear();
+    let mut key = login::current_session_key()?;
+    let bundle = SyncEngine::export_bundle(vault.repository(), &key, full, |bundle| {
+        let serialized = serde_json::to_string_pretty(&sync_bundle::seal(bundle, &key)?)?;
+        fs::write(path, serialized).map_err(|_| ChacrabError::Storage)
+    })
+    .await;
+    key.zeroize();
+    let bundle = bundle?;
+    session::touch_session()?;
+
+    success("Encrypted sync bundle exported.", options);
+    system(&format!("Path: {path}"), options);
+    system(&format!("Items exported: {}", bundle.items.len()), options);
+    system(
+        &format!("Deletions exported: {}", bundle.tombstones.len()),
+        options,
+    );
+    Ok(())
+}
+
+async fn run_import_bundle(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    path: &str,
+    on_conflict: Option<&str>,
+) -> ChacrabResult<()> {
+    print_header("Sync Bundle Import", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
+
+    let saved_config = runtime_config::load()?.unwrap_or_default();
+    let policy = sync_policy(
+        on_conflict,
+        None,
+        saved_config.profile()?.sync.conflict_policy.as_deref(),
+    )?;
+    if policy == ConflictPolicy::Interactive && (options.json || !options.interactive) {
+        return Err(ChacrabError::Config(
+            "interactive conflict resolution needs a terminal".to_owned(),
+        ));
     }
 
-    let report = SyncEngine::sync_bidirectional(vault.repository(), &remote).await?;
+    let content = fs::read_to_string(path).map_err(|_| ChacrabError::Storage)?;
+    let bundle_file: EncryptedBundleFile = serde_json::from_str(&content)?;
+    let mut key = login::current_session_key()?;
+    let outcome = match sync_bundle::open(&bundle_file, &key) {
+        Ok(bundle) => {
+            let since = bundle.since;
+            let remote = AppRepository::Bundle(BundleRepository::new(bundle));
+            match SyncEngine::plan_bundle_import(vault.repository(), &remote, since, &key, policy)
+                .await
+            {
+                Ok(plan) => apply_sync_plan(vault.repository(), &remote, &key, plan, options).await,
+                Err(err) => Err(err),
+            }
+        }
+        Err(err) => Err(err),
+    };
+    key.zeroize();
+    if let Err(ChacrabError::Integrity(detail)) = &outcome {
+        warning(&format!("Integrity check: {detail}"), options);
+    }
+    let report = outcome?;
     session::touch_session()?;
-    success("Sync complete.", options);
-    system(&format!("Items uploaded: {}", report.uploaded), options);
-    system(&format!("Items downloaded: {}", report.downloaded), options);
+
+    success("Encrypted sync bundle imported.", options);
+    system(&format!("Items applied: {}", report.downloaded), options);
+    system(
+        &format!("Deletions applied: {}", report.local_deletions),
+        options,
+    );
+    warn_conflicts(&report, options);
+    Ok(())
+}
+
+fn sync_plan_rows(plan: &SyncPlan, key: &[u8; 32]) -> ChacrabResult<Vec<table::SyncPlanRow>> {
+    let row = |action, item: &VaultItem, local_revision, remote_revision| {
+        let mut item = item.clone();
+        vault::reveal_metadata(&mut item, key)?;
+        Ok::<_, ChacrabError>(table::SyncPlanRow {
+            action,
+            id: item.id,
+            title: item.title,
+            local_revision,
+            remote_revision,
+        })
+    };
+    let mut rows = Vec::new();
+    for step in &plan.steps {
+        match step {
+            SyncStep::Upload(item) => rows.push(row("upload", item, Some(item.revision), None)?),
+            SyncStep::Download(item) => {
+                rows.push(row("download", item, None, Some(item.revision))?);
+            }
+            SyncStep::Conflict { local, remote } => rows.push(row(
+                "conflict",
+                local,
+                Some(local.revision),
+                Some(remote.revision),
+            )?),
+            SyncStep::Delete {
+                tombstone,
+                item,
+                removes_local,
+                removes_remote,
+                ..
+            } => {
+                for (action, removed) in [
+                    ("delete-local", *removes_local),
+                    ("delete-remote", *removes_remote),
+                ] {
+                    if !removed {
+                        continue;
+                    }
+                    rows.push(match item {
+                        Some(item) => row(action, item, None, None)?,
+                        None => table::SyncPlanRow {
+                            action,
+                            id: tombstone.id,
+                            title: String::new(),
+                            local_revision: None,
+                            remote_revision: None,
+                        },
+                    });
+                }
+            }
+            SyncStep::Settle { .. } => {}
+        }
+    }
+    Ok(rows)
+}
+
+fn print_sync_plan(plan: &SyncPlan, key: &[u8; 32], options: UiOptions) -> ChacrabResult<()> {
+    let rows = sync_plan_rows(plan, key)?;
+    let count = |action: &str| {
+        rows.iter()
+            .filter(|row| row.action.starts_with(action))
+            .count()
+    };
+
+    if options.json {
+        let entries = |action: &str| {
+            rows.iter()
+                .filter(|row| row.action.starts_with(action))
+                .map(|row| {
+                    let mut entry = json!({
+                        "id": row.id.to_string(),
+                        "short_id": short_id(&row.id.to_string()),
+                        "title": row.title,
+                        "local_revision": row.local_revision,
+                        "remote_revision": row.remote_revision,
+                    });
+                    if let Some(side) = row.action.strip_prefix("delete-") {
+                        entry["side"] = json!(side);
+                    }
+                    if row.action == "conflict" {
+                        entry["resolution"] = json!(plan.policy.as_str());
+                    }
+                    entry
+                })
+                .collect::<Vec<_>>()
+        };
+        let out = json!({
+            "version": 1,
+            "remote": plan.remote(),
+            "bootstrap_remote": plan.bootstraps_remote(),
+            "update_remote_auth": plan.updates_remote_auth(),
+            "policy": plan.policy.as_str(),
+            "uploads": entries("upload"),
+            "downloads": entries("download"),
+            "conflicts": entries("conflict"),
+            "deletions": entries("delete"),
+        });
+        println!(
+            "{}",
+            serde_json::to_string(&out).map_err(|_| ChacrabError::Serialization)?
+        );
+        return Ok(());
+    }
+
+    if plan.bootstraps_remote() {
+        system(
+            "The remote has no vault yet; it will be initialized with this vault's key material.",
+            options,
+        );
+    }
+    if plan.updates_remote_auth() {
+        system(
+            "The master password was changed here; the remote will receive the new key wrapping.",
+            options,
+        );
+    }
+    if rows.is_empty() {
+        if !plan.bootstraps_remote() && !plan.updates_remote_auth() {
+            success("Already in sync. Nothing to do.", options);
+        }
+        return Ok(());
+    }
+    table::print_sync_plan_table(&rows);
+    system(
+        &format!(
+            "Plan: {} uploads, {} downloads, {} conflicts ({}), {} deletions. Nothing was changed.",
+            count("upload"),
+            count("download"),
+            count("conflict"),
+            plan.policy.as_str(),
+            count("delete"),
+        ),
+        options,
+    );
     Ok(())
 }
 
-async fn sync_remote_repo() -> ChacrabResult<AppRepository> {
+struct CliConflictResolver<'a> {
+    progress: &'a ProgressBar,
+}
+
+impl ConflictResolver for CliConflictResolver<'_> {
+    fn choose(&mut self, local: &VaultItem, remote: &VaultItem) -> ChacrabResult<ConflictChoice> {
+        let labels = [
+            format!(
+                "Keep local (revision {}, {})",
+                local.revision,
+                local.updated_at.format("%Y-%m-%d %H:%M:%S")
+            ),
+            format!(
+                "Keep remote (revision {}, {})",
+                remote.revision,
+                remote.updated_at.format("%Y-%m-%d %H:%M:%S")
+            ),
+            "Keep both".to_owned(),
+        ];
+        let labels = labels.iter().map(String::as_str).collect::<Vec<_>>();
+        let prompt = format!("Conflict on {}", short_id(&local.id.to_string()));
+        let choice = self
+            .progress
+            .suspend(|| prompts::select(&prompt, &labels))?;
+        Ok([
+            ConflictChoice::Local,
+            ConflictChoice::Remote,
+            ConflictChoice::KeepBoth,
+        ][choice])
+    }
+
+    fn duplicate(&mut self, item: &VaultItem) -> ChacrabResult<VaultItem> {
+        let mut key = login::current_session_key()?;
+        let copy = vault::duplicate_item(item, &key);
+        key.zeroize();
+        copy
+    }
+}
+
+fn warn_conflicts(report: &SyncReport, options: UiOptions) {
+    if report.conflicts.is_empty() {
+        return;
+    }
+    let summary = report
+        .conflicts
+        .iter()
+        .map(|conflict| {
+            let mut line = format!(
+                "{} ({})",
+                short_id(&conflict.id.to_string()),
+                conflict.resolution.as_str()
+            );
+            if let Some(copy_id) = conflict.copy_id {
+                line.push_str(&format!(", copy {}", short_id(&copy_id.to_string())));
+            }
+            line
+        })
+        .collect::<Vec<_>>()
+        .join("; ");
+    warning(
+        &format!("{} sync conflicts: {summary}", report.conflicts.len()),
+        options,
+    );
+}
+
+fn sync_targets(request: &SyncRequest<'_>) -> ChacrabResult<Vec<SyncTarget>> {
+    let saved_config = runtime_config::load()?.unwrap_or_default();
+    let profile = saved_config.profile()?;
+    let remotes = match request.remote {
+        Some(name) => vec![
+            profile
+                .sync_remote(name)
+                .cloned()
+                .ok_or_else(|| ChacrabError::Config("unknown sync remote".to_owned()))?,
+        ],
+        None if request.all && profile.sync.remotes.is_empty() => {
+            return Err(ChacrabError::Config(
+                "no sync remotes configured".to_owned(),
+            ));
+        }
+        None if request.all || profile.sync.remotes.len() == 1 => profile.sync.remotes.clone(),
+        None if profile.sync.remotes.is_empty() => {
+            return Ok(vec![environment_sync_target(
+                request,
+                profile.sync.conflict_policy.as_deref(),
+            )?]);
+        }
+        None => {
+            return Err(ChacrabError::Config(
+                "several sync remotes configured".to_owned(),
+            ));
+        }
+    };
+
+    remotes
+        .into_iter()
+        .map(|remote| {
+            let policy = sync_policy(
+                request.on_conflict,
+                remote.conflict_policy.as_deref(),
+                profile.sync.conflict_policy.as_deref(),
+            )?;
+            let secret = keyring::load_remote_secret(&remote.name)?;
+            Ok(SyncTarget {
+                remote,
+                secret,
+                policy,
+            })
+        })
+        .collect()
+}
+
+fn environment_sync_target(
+    request: &SyncRequest<'_>,
+    saved_policy: Option<&str>,
+) -> ChacrabResult<SyncTarget> {
     let backend = std::env::var("CHACRAB_SYNC_BACKEND")
         .map_err(|_| ChacrabError::Config("set CHACRAB_SYNC_BACKEND for sync".to_owned()))?;
     let database_url = std::env::var("CHACRAB_SYNC_DATABASE_URL")
         .map_err(|_| ChacrabError::Config("set CHACRAB_SYNC_DATABASE_URL for sync".to_owned()))?;
+    let (url, secret) = runtime_config::split_url_secret(&database_url);
+    Ok(SyncTarget {
+        remote: SyncRemote {
+            name: "environment".to_owned(),
+            backend,
+            url,
+            conflict_policy: None,
+        },
+        secret,
+        policy: sync_policy(request.on_conflict, None, saved_policy)?,
+    })
+}
 
-    let repo = AppRepository::connect(&backend, &database_url).await?;
-    repo.init().await?;
-    Ok(repo)
+fn sync_policy(
+    requested: Option<&str>,
+    remote: Option<&str>,
+    saved: Option<&str>,
+) -> ChacrabResult<ConflictPolicy> {
+    requested
+        .or(remote)
+        .or(saved)
+        .map(str::parse)
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+fn run_remote(
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    action: &RemoteAction,
+) -> ChacrabResult<()> {
+    print_header("Sync Remotes", session_indicator, options);
+    let mut saved_config = saved_config_or(cli)?;
+
+    match action {
+        RemoteAction::Add {
+            name,
+            url,
+            backend,
+            on_conflict,
+        } => {
+            if name.trim().is_empty()
+                || name.contains(char::is_whitespace)
+                || matches!(name.as_str(), "export-bundle" | "import-bundle" | "help")
+            {
+                return Err(ChacrabError::Config("invalid sync remote name".to_owned()));
+            }
+            if saved_config.profile()?.sync_remote(name).is_some() {
+                return Err(ChacrabError::Config(
+                    "sync remote already exists".to_owned(),
+                ));
+            }
+            if let Some(policy) = on_conflict {
+                policy.parse::<ConflictPolicy>()?;
+            }
+            let backend = match backend {
+                Some(backend) => backend.clone(),
+                None => runtime_config::backend_for_url(url)
+                    .ok_or_else(|| {
+                        ChacrabError::Config("cannot infer sync remote backend".to_owned())
+                    })?
+                    .to_owned(),
+            };
+            if !matches!(backend.as_str(), "sqlite" | "postgres" | "mongo" | "http") {
+                return Err(ChacrabError::UnsupportedBackend(backend));
+            }
+
+            let (url, secret) = runtime_config::split_url_secret(url);
+            match &secret {
+                Some(secret) => keyring::store_remote_secret(name, secret)?,
+                None => keyring::clear_remote_secret(name)?,
+            }
+            saved_config.profile_mut()?.sync.remotes.push(SyncRemote {
+                name: name.clone(),
+                backend,
+                url,
+                conflict_policy: on_conflict.clone(),
+            });
+            runtime_config::save(&saved_config)?;
+
+            success("Sync remote added.", options);
+            system(&format!("Name: {name}"), options);
+            if secret.is_some() {
+                system("Credentials stored in the OS keyring.", options);
+            }
+        }
+        RemoteAction::List => {
+            let remotes = &saved_config.profile()?.sync.remotes;
+            if options.json {
+                let remotes = remotes
+                    .iter()
+                    .map(|remote| {
+                        json!({
+                            "name": remote.name,
+                            "backend": remote.backend,
+                            "url": remote.url,
+                            "conflict_policy": remote.conflict_policy,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&remotes)
+                        .map_err(|_| ChacrabError::Serialization)?
+                );
+            } else if remotes.is_empty() {
+                system("No sync remotes configured.", options);
+            } else {
+                table::print_remote_table(remotes);
+            }
+        }
+        RemoteAction::Remove { name } => {
+            let remotes = &mut saved_config.profile_mut()?.sync.remotes;
+            let before = remotes.len();
+            remotes.retain(|remote| &remote.name != name);
+            if remotes.len() == before {
+                return Err(ChacrabError::Config("unknown sync remote".to_owned()));
+            }
+            runtime_config::save(&saved_config)?;
+            keyring::clear_remote_secret(name)?;
+            success("Sync remote removed.", options);
+        }
+    }
+    Ok(())
 }
 
 async fn run_backup_export(
@@ -511,6 +2423,7 @@ async fn run_backup_import(
 ) -> ChacrabResult<()> {
     print_header("Encrypted Backup Import", session_indicator, options);
     session::enforce_timeout(cli.session_timeout_secs)?;
+    purge_expired_trash(vault, options).await;
 
     let content = fs::read_to_string(path).map_err(|_| ChacrabError::Storage)?;
     let backup_file: EncryptedBackupFile = serde_json::from_str(&content)?;
@@ -534,27 +2447,37 @@ fn run_config(
     cli: &Cli,
     options: UiOptions,
     session_indicator: SessionIndicator,
+    action: Option<&ConfigAction>,
 ) -> ChacrabResult<()> {
     print_header("Configuration", session_indicator, options);
+    if let Some(action) = action {
+        return run_config_action(cli, options, action);
+    }
+
     if options.json {
         let value = json!({
+            "profile": keyring::active_profile(),
             "backend": cli.backend,
-            "database_url": cli.database_url,
+            "database_url": runtime_config::split_url_secret(&cli.database_url).0,
             "json": cli.json,
             "quiet": cli.quiet,
             "no_color": cli.no_color,
             "session_timeout_secs": cli.session_timeout_secs,
+            "revision_retention": cli.revision_retention,
+            "trash_retention_days": cli.trash_retention_days,
         });
         println!(
             "{}",
             serde_json::to_string_pretty(&value).map_err(|_| ChacrabError::Serialization)?
         );
     } else {
+        system(&format!("Profile: {}", keyring::active_profile()), options);
         system(
             &format!("Backend: {}", backend_display(&cli.backend)),
             options,
         );
-        system(&format!("Database URL: {}", cli.database_url), options);
+        let (database_url, _) = runtime_config::split_url_secret(&cli.database_url);
+        system(&format!("Database URL: {database_url}"), options);
         system(&format!("JSON mode: {}", cli.json), options);
         system(&format!("Quiet mode: {}", cli.quiet), options);
         system(&format!("Color disabled: {}", cli.no_color), options);
@@ -562,7 +2485,269 @@ fn run_config(
             &format!("Session timeout (sec): {}", cli.session_timeout_secs),
             options,
         );
+        system(
+            &format!("Revision retention: {}", cli.revision_retention),
+            options,
+        );
+        system(
+            &format!("Trash retention (days): {}", cli.trash_retention_days),
+            options,
+        );
+    }
+    Ok(())
+}
+
+fn run_profile(
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    action: &ProfileAction,
+) -> ChacrabResult<()> {
+    print_header("Profiles", session_indicator, options);
+    let mut saved_config = saved_config_or(cli)?;
+
+    match action {
+        ProfileAction::Create {
+            name,
+            database_url,
+            backend,
+        } => {
+            if !runtime_config::is_valid_profile_name(name) {
+                return Err(ChacrabError::Config("invalid profile name".to_owned()));
+            }
+            if saved_config.profiles.contains_key(name) {
+                return Err(ChacrabError::Config("profile already exists".to_owned()));
+            }
+            let backend = match backend {
+                Some(backend) => backend.clone(),
+                None => runtime_config::backend_for_url(database_url)
+                    .ok_or_else(|| ChacrabError::Config("cannot infer profile backend".to_owned()))?
+                    .to_owned(),
+            };
+            if !matches!(backend.as_str(), "sqlite" | "postgres" | "mongo" | "http") {
+                return Err(ChacrabError::UnsupportedBackend(backend));
+            }
+
+            let (database_url, secret) = runtime_config::split_url_secret(database_url);
+            match &secret {
+                Some(secret) => keyring::store_database_secret(name, secret)?,
+                None => keyring::clear_database_secret(name)?,
+            }
+            saved_config
+                .profiles
+                .insert(name.clone(), Profile::new(backend, database_url));
+            runtime_config::save(&saved_config)?;
+            success("Profile created.", options);
+            if secret.is_some() {
+                system("Credentials stored in the OS keyring.", options);
+            }
+            system(
+                &format!("Initialize it with `chacrab --profile {name} init`."),
+                options,
+            );
+        }
+        ProfileAction::List => {
+            let active = keyring::active_profile();
+            if options.json {
+                let profiles = saved_config
+                    .profiles
+                    .iter()
+                    .map(|(name, profile)| {
+                        json!({
+                            "name": name,
+                            "backend": profile.backend,
+                            "database_url": runtime_config::split_url_secret(&profile.database_url).0,
+                            "active": name == active,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&profiles)
+                        .map_err(|_| ChacrabError::Serialization)?
+                );
+            } else {
+                table::print_profile_table(&saved_config.profiles, active);
+            }
+        }
+        ProfileAction::Use { name } => {
+            if !saved_config.profiles.contains_key(name) {
+                return Err(ChacrabError::Config("unknown profile".to_owned()));
+            }
+            saved_config.active_profile = (name != DEFAULT_PROFILE).then(|| name.clone());
+            runtime_config::save(&saved_config)?;
+            success(&format!("Active profile: {name}."), options);
+        }
+        ProfileAction::Remove { name } => {
+            if name == DEFAULT_PROFILE {
+                return Err(ChacrabError::Config(
+                    "default profile cannot be removed".to_owned(),
+                ));
+            }
+            let Some(profile) = saved_config.profiles.remove(name) else {
+                return Err(ChacrabError::Config("unknown profile".to_owned()));
+            };
+            if saved_config.active_profile.as_ref() == Some(name) {
+                saved_config.active_profile = None;
+            }
+            runtime_config::save(&saved_config)?;
+
+            let remotes = profile
+                .sync
+                .remotes
+                .into_iter()
+                .map(|remote| remote.name)
+                .collect::<Vec<_>>();
+            keyring::clear_profile_secrets(name, &remotes)?;
+            session::clear_profile_metadata(name)?;
+            success(
+                "Profile removed. Its vault database was not deleted.",
+                options,
+            );
+        }
+    }
+    Ok(())
+}
+
+fn run_config_action(cli: &Cli, options: UiOptions, action: &ConfigAction) -> ChacrabResult<()> {
+    let saved_config = match action {
+        ConfigAction::Reset { key: None } => runtime_config::load().ok().flatten(),
+        _ => runtime_config::load()?,
+    };
+    let mut saved_config = match saved_config {
+        Some(saved_config) => saved_config,
+        None => new_saved_config(cli),
+    };
+
+    match action {
+        ConfigAction::Get { key } => {
+            let value = saved_config.get(key)?;
+            if options.json {
+                let out = json!({ "key": key, "value": value });
+                println!(
+                    "{}",
+                    serde_json::to_string(&out).map_err(|_| ChacrabError::Serialization)?
+                );
+            } else {
+                println!("{value}");
+            }
+        }
+        ConfigAction::Set { key, value } if key == "database-url" => {
+            let (database_url, secret) = runtime_config::split_url_secret(value);
+            saved_config.set(key, &database_url)?;
+            match &secret {
+                Some(secret) => keyring::store_database_secret(keyring::active_profile(), secret)?,
+                None => keyring::clear_database_secret(keyring::active_profile())?,
+            }
+            runtime_config::save(&saved_config)?;
+            success(&format!("{key} set to {database_url}."), options);
+            if secret.is_some() {
+                system("Credentials stored in the OS keyring.", options);
+            }
+        }
+        ConfigAction::Set { key, value } => {
+            saved_config.set(key, value)?;
+            runtime_config::save(&saved_config)?;
+            success(
+                &format!("{key} set to {}.", saved_config.get(key)?),
+                options,
+            );
+        }
+        ConfigAction::Reset { key: Some(key) } => {
+            saved_config.reset(key)?;
+            runtime_config::save(&saved_config)?;
+            if key == "database-url" {
+                keyring::clear_database_secret(keyring::active_profile())?;
+            }
+            success(
+                &format!("{key} reset to {}.", runtime_config::default_value(key)?),
+                options,
+            );
+        }
+        ConfigAction::Reset { key: None } => {
+            saved_config.reset_all()?;
+            runtime_config::save(&saved_config)?;
+            keyring::clear_database_secret(keyring::active_profile())?;
+            success("All settings reset to defaults.", options);
+            if !saved_config.profile()?.sync.remotes.is_empty() {
+                system("Sync remotes were kept.", options);
+            }
+        }
+        ConfigAction::List => {
+            let mut rows = Vec::new();
+            for key in runtime_config::CONFIG_KEYS {
+                let value = saved_config.value(key)?;
+                let is_default = value.is_none();
+                let value = match value {
+                    Some(value) => value,
+                    None => runtime_config::default_value(key)?,
+                };
+                rows.push((key, value, is_default));
+            }
+            if options.json {
+                let out = rows
+                    .iter()
+                    .map(|(key, value, is_default)| {
+                        json!({ "key": key, "value": value, "default": is_default })
+                    })
+                    .collect::<Vec<_>>();
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&out).map_err(|_| ChacrabError::Serialization)?
+                );
+            } else {
+                table::print_config_table(&rows);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn run_privacy(
+    vault: &VaultService<AppRepository>,
+    cli: &Cli,
+    options: UiOptions,
+    session_indicator: SessionIndicator,
+    action: &PrivacyAction,
+) -> ChacrabResult<()> {
+    print_header("Metadata Privacy", session_indicator, options);
+    session::enforce_timeout(cli.session_timeout_secs)?;
+
+    let enabled = match action {
+        PrivacyAction::Status => {
+            let enabled = vault.metadata_privacy_enabled().await?;
+            system(
+                &format!(
+                    "Metadata privacy: {}",
+                    if enabled { "enabled" } else { "disabled" }
+                ),
+                options,
+            );
+            return Ok(());
+        }
+        PrivacyAction::Enable => true,
+        PrivacyAction::Disable { yes } => {
+            warning(
+                "Titles, usernames and URLs will be stored in plaintext.",
+                options,
+            );
+            confirm_destructive("Proceed?", *yes, options)?;
+            false
+        }
+    };
+
+    let mut key = login::current_session_key()?;
+    let changed = vault.set_metadata_privacy(enabled, &key).await;
+    key.zeroize();
+    let changed = changed?;
+    session::touch_session()?;
+
+    if enabled {
+        success("Metadata privacy enabled.", options);
+    } else {
+        success("Metadata privacy disabled.", options);
     }
+    system(&format!("Items updated: {changed}"), options);
     Ok(())
 }
 
@@ -571,6 +2756,7 @@ fn backend_display(backend: &str) -> &'static str {
         "sqlite" => "SQLite (local)",
         "postgres" => "PostgreSQL",
         "mongo" => "MongoDB",
+        "http" => "chacrab-server (HTTP)",
         _ => "Unsupported",
     }
 }
@@ -8,6 +8,7 @@ pub struct UiOptions {
     pub json: bool,
     pub quiet: bool,
     pub color: bool,
+    pub interactive: bool,
 }
 
 #[derive(Debug, Clone, Copy)]
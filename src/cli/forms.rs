@@ -0,0 +1,352 @@
+use secrecy::ExposeSecret;
+use zeroize::Zeroize;
+
+use crate::{
+    cli::prompts,
+    core::{
+        errors::{ChacrabError, ChacrabResult},
+        models::{
+            ApiKeyDetails, CardDetails, CustomField, CustomFieldKind, EncryptedPayload,
+            IdentityDetails, SshKeyDetails, VaultItem, VaultItemType,
+        },
+    },
+};
+
+pub struct ItemView {
+    pub details: Vec<(String, String)>,
+    pub custom_fields: Vec<(String, String)>,
+    pub plain_fields: Vec<(String, String)>,
+    pub secrets: Vec<(String, String)>,
+}
+
+impl Drop for ItemView {
+    fn drop(&mut self) {
+        for (_, value) in &mut self.secrets {
+            value.zeroize();
+        }
+    }
+}
+
+pub fn describe(item: &VaultItem, payload: &EncryptedPayload) -> ItemView {
+    let (details, secrets) = describe_typed(item, payload);
+    let mut view = ItemView {
+        details: details
+            .into_iter()
+            .map(|(label, value)| (label.to_owned(), value))
+            .collect(),
+        secrets: secrets
+            .into_iter()
+            .map(|(label, value)| (label.to_owned(), value))
+            .collect(),
+        custom_fields: Vec::new(),
+        plain_fields: Vec::new(),
+    };
+
+    for field in &payload.custom_fields {
+        if field.is_hidden() {
+            view.custom_fields
+                .push((field.name.clone(), "********".to_owned()));
+            view.secrets.push((field.name.clone(), field.value.clone()));
+        } else {
+            view.custom_fields
+                .push((field.name.clone(), field.value.clone()));
+            view.plain_fields
+                .push((field.name.clone(), field.value.clone()));
+        }
+    }
+    view
+}
+
+type Labeled = Vec<(&'static str, String)>;
+
+fn describe_typed(item: &VaultItem, payload: &EncryptedPayload) -> (Labeled, Labeled) {
+    let masked = || "********".to_owned();
+    match item.r#type {
+        VaultItemType::Password | VaultItemType::Note => (
+            vec![
+                ("Username", or_dash(&item.username)),
+                ("URL", or_dash(&item.url)),
+                ("Password", masked()),
+            ],
+            vec![("password", payload.password.clone().unwrap_or_default())],
+        ),
+        VaultItemType::Card => {
+            let mut card = payload.card.clone().unwrap_or_default();
+            let details = vec![
+                ("Cardholder", or_dash(&card.cardholder_name)),
+                ("Number", mask_card_number(&card.number)),
+                ("Expiry", or_dash(&card.expiry)),
+            ];
+            let mut secrets = vec![("card number", std::mem::take(&mut card.number))];
+            secrets.extend(card.security_code.map(|code| ("security code", code)));
+            (details, secrets)
+        }
+        VaultItemType::Identity => {
+            let identity = payload.identity.clone().unwrap_or_default();
+            (
+                vec![
+                    ("Full name", or_dash(&identity.full_name)),
+                    ("Email", or_dash(&identity.email)),
+                    ("Phone", or_dash(&identity.phone)),
+                    ("Address", or_dash(&identity.address)),
+                    ("Date of birth", or_dash(&identity.date_of_birth)),
+                    (
+                        "Document number",
+                        identity
+                            .document_number
+                            .as_ref()
+                            .map_or_else(|| "-".to_owned(), |_| masked()),
+                    ),
+                ],
+                identity
+                    .document_number
+                    .map(|number| ("document number", number))
+                    .into_iter()
+                    .collect(),
+            )
+        }
+        VaultItemType::SshKey => {
+            let ssh_key = payload.ssh_key.clone().unwrap_or_default();
+            let mut secrets = vec![("private key", ssh_key.private_key)];
+            secrets.extend(
+                ssh_key
+                    .passphrase
+                    .map(|passphrase| ("key passphrase", passphrase)),
+            );
+            (
+                vec![
+                    ("Public key", or_dash(&ssh_key.public_key)),
+                    ("Private key", masked()),
+                ],
+                secrets,
+            )
+        }
+        VaultItemType::ApiKey => {
+            let api_key = payload.api_key.clone().unwrap_or_default();
+            (
+                vec![
+                    ("Key ID", or_dash(&api_key.key_id)),
+                    ("Endpoint", or_dash(&api_key.endpoint)),
+                    ("Expires", or_dash(&api_key.expires_at)),
+                    ("Secret", masked()),
+                ],
+                vec![("secret", api_key.secret)],
+            )
+        }
+    }
+}
+
+pub fn prompt_card(current: Option<&CardDetails>) -> ChacrabResult<CardDetails> {
+    let current = current.cloned().unwrap_or_default();
+    Ok(CardDetails {
+        cardholder_name: optional("Cardholder name", &current.cardholder_name)?,
+        number: required_hidden("Card number: ", "card number", current.number)?,
+        expiry: optional("Expiry (MM/YY)", &current.expiry)?,
+        security_code: optional_hidden(
+            "Security code (optional): ",
+            "security code",
+            current.security_code,
+        )?,
+    })
+}
+
+pub fn prompt_identity(current: Option<&IdentityDetails>) -> ChacrabResult<IdentityDetails> {
+    let current = current.cloned().unwrap_or_default();
+    Ok(IdentityDetails {
+        full_name: optional("Full name", &current.full_name)?,
+        email: optional("Email", &current.email)?,
+        phone: optional("Phone", &current.phone)?,
+        address: optional("Address", &current.address)?,
+        date_of_birth: optional("Date of birth", &current.date_of_birth)?,
+        document_number: optional_hidden(
+            "Document number (optional): ",
+            "document number",
+            current.document_number,
+        )?,
+    })
+}
+
+pub fn prompt_ssh_key(current: Option<&SshKeyDetails>) -> ChacrabResult<SshKeyDetails> {
+    let current = current.cloned().unwrap_or_default();
+    let mut private_key = current.private_key;
+    if private_key.is_empty() || prompts::confirmation_prompt("Change private key?", false)? {
+        private_key.zeroize();
+        private_key = prompts::multiline("")?
+            .filter(|key| !key.trim().is_empty())
+            .ok_or_else(|| ChacrabError::Config("private key is required".to_owned()))?;
+    }
+
+    Ok(SshKeyDetails {
+        private_key,
+        public_key: optional("Public key (optional)", &current.public_key)?,
+        passphrase: optional_hidden(
+            "Key passphrase (optional): ",
+            "key passphrase",
+            current.passphrase,
+        )?,
+    })
+}
+
+pub fn prompt_api_key(current: Option<&ApiKeyDetails>) -> ChacrabResult<ApiKeyDetails> {
+    let current = current.cloned().unwrap_or_default();
+    Ok(ApiKeyDetails {
+        key_id: optional("Key ID (optional)", &current.key_id)?,
+        secret: required_hidden("Secret: ", "secret", current.secret)?,
+        endpoint: optional("Endpoint (optional)", &current.endpoint)?,
+        expires_at: optional("Expires (optional)", &current.expires_at)?,
+    })
+}
+
+pub fn prompt_custom_field() -> ChacrabResult<CustomField> {
+    let name = prompts::input("Field name")?;
+    let kinds = [
+        CustomFieldKind::Text,
+        CustomFieldKind::Hidden,
+        CustomFieldKind::Url,
+        CustomFieldKind::Boolean,
+        CustomFieldKind::Date,
+    ];
+    let kind = kinds[prompts::select("Field type", &["Text", "Hidden", "URL", "Boolean", "Date"])?];
+    let value = match kind {
+        CustomFieldKind::Hidden => prompts::secure_password_prompt("Value: ")?
+            .expose_secret()
+            .to_owned(),
+        CustomFieldKind::Boolean => prompts::confirmation_prompt("Value", false)?.to_string(),
+        CustomFieldKind::Date => prompts::input("Value (YYYY-MM-DD)")?,
+        CustomFieldKind::Text | CustomFieldKind::Url => prompts::input("Value")?,
+    };
+    CustomField::new(name, kind, value)
+}
+
+pub fn prompt_custom_fields(mut fields: Vec<CustomField>) -> ChacrabResult<Vec<CustomField>> {
+    loop {
+        let mut actions = vec!["Add field".to_owned()];
+        actions.extend(
+            fields
+                .iter()
+                .map(|field| format!("Remove {} ({})", field.name, field.kind.as_str())),
+        );
+        actions.push("Done".to_owned());
+        let labels = actions.iter().map(String::as_str).collect::<Vec<_>>();
+
+        match prompts::select("Custom fields", &labels)? {
+            0 => fields.push(prompt_custom_field()?),
+            choice if choice <= fields.len() => {
+                fields.remove(choice - 1).value.zeroize();
+            }
+            _ => return Ok(fields),
+        }
+    }
+}
+
+pub fn parse_custom_fields(args: &[String]) -> ChacrabResult<Vec<CustomField>> {
+    args.iter().map(|arg| parse_custom_field(arg)).collect()
+}
+
+fn parse_custom_field(arg: &str) -> ChacrabResult<CustomField> {
+    let (spec, value) = arg
+        .split_once('=')
+        .ok_or_else(|| ChacrabError::Config("invalid custom field argument".to_owned()))?;
+    let (kind, name) = match spec.split_once(':') {
+        Some((kind, name)) => match kind.parse::<CustomFieldKind>() {
+            Ok(kind) => (kind, name),
+            Err(_) => (CustomFieldKind::Text, spec),
+        },
+        None => (CustomFieldKind::Text, spec),
+    };
+    // Arguments are visible to other processes, so hidden values stay prompt-only.
+    if kind == CustomFieldKind::Hidden {
+        return Err(ChacrabError::Config(
+            "hidden custom field argument".to_owned(),
+        ));
+    }
+    CustomField::new(name.to_owned(), kind, value.to_owned())
+}
+
+fn optional(prompt: &str, current: &Option<String>) -> ChacrabResult<Option<String>> {
+    prompts::optional_input_with_initial(prompt, current.as_deref().unwrap_or_default())
+}
+
+fn optional_hidden(
+    prompt: &str,
+    label: &str,
+    mut current: Option<String>,
+) -> ChacrabResult<Option<String>> {
+    if current.is_some() && !prompts::confirmation_prompt(&format!("Change {label}?"), false)? {
+        return Ok(current);
+    }
+    current.zeroize();
+
+    let value = prompts::secure_password_prompt(prompt)?;
+    if value.expose_secret().is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(value.expose_secret().to_owned()))
+    }
+}
+
+fn required_hidden(prompt: &str, label: &str, mut current: String) -> ChacrabResult<String> {
+    if !current.is_empty() && !prompts::confirmation_prompt(&format!("Change {label}?"), false)? {
+        return Ok(current);
+    }
+    current.zeroize();
+
+    let value = prompts::secure_password_prompt(prompt)?;
+    if value.expose_secret().is_empty() {
+        return Err(ChacrabError::Config(format!("{label} is required")));
+    }
+    Ok(value.expose_secret().to_owned())
+}
+
+fn or_dash(value: &Option<String>) -> String {
+    value.clone().unwrap_or_else(|| "-".to_owned())
+}
+
+fn mask_card_number(number: &str) -> String {
+    let digits = number
+        .chars()
+        .filter(|ch| ch.is_ascii_digit())
+        .collect::<Vec<_>>();
+    if digits.len() < 4 {
+        return "********".to_owned();
+    }
+    let last_four = digits[digits.len() - 4..].iter().collect::<String>();
+    format!("**** {last_four}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_custom_fields;
+    use crate::core::{errors::ChacrabError, models::CustomFieldKind};
+
+    #[test]
+    fn field_arguments_parse_kind_name_and_value() {
+        let fields = parse_custom_fields(&[
+            "Recovery email=me@example.com".to_owned(),
+            "url:Portal=https://example.com".to_owned(),
+            "boolean:Shared=yes".to_owned(),
+            "Note:with colon=a=b".to_owned(),
+        ])
+        .expect("fields");
+
+        assert_eq!(fields[0].name, "Recovery email");
+        assert_eq!(fields[0].kind, CustomFieldKind::Text);
+        assert_eq!(fields[1].kind, CustomFieldKind::Url);
+        assert_eq!(fields[1].value, "https://example.com");
+        assert_eq!(fields[2].value, "true");
+        assert_eq!(fields[3].name, "Note:with colon");
+        assert_eq!(fields[3].value, "a=b");
+    }
+
+    #[test]
+    fn hidden_or_malformed_field_arguments_are_rejected() {
+        assert!(matches!(
+            parse_custom_fields(&["hidden:PIN=1234".to_owned()]),
+            Err(ChacrabError::Config(message)) if message == "hidden custom field argument"
+        ));
+        assert!(matches!(
+            parse_custom_fields(&["no value".to_owned()]),
+            Err(ChacrabError::Config(message)) if message == "invalid custom field argument"
+        ));
+    }
+}
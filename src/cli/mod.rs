@@ -1,5 +1,6 @@
 pub mod commands;
 pub mod display;
+pub mod forms;
 pub mod parser;
 pub mod prompts;
 pub mod runtime_config;
@@ -1,7 +1,13 @@
-use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+use secrecy::SecretString;
+
+use crate::core::vault::{DEFAULT_REVISION_RETENTION, DEFAULT_TRASH_RETENTION_DAYS};
 
 pub const DEFAULT_BACKEND: &str = "sqlite";
 pub const DEFAULT_DATABASE_URL: &str = "sqlite://chacrab.db?mode=rwc";
+pub const DEFAULT_SESSION_TIMEOUT_SECS: u64 = 900;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -19,6 +25,13 @@ pub struct Cli {
     #[arg(long, default_value = DEFAULT_DATABASE_URL)]
     pub database_url: String,
 
+    /// Password for `database_url` loaded from the OS keyring for the active profile.
+    #[arg(skip)]
+    pub database_secret: Option<SecretString>,
+
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
     #[arg(long, default_value_t = false, global = true)]
     pub json: bool,
 
@@ -28,22 +41,272 @@ pub struct Cli {
     #[arg(long, default_value_t = false, global = true)]
     pub no_color: bool,
 
-    #[arg(long, default_value_t = 900, global = true)]
+    #[arg(long, default_value_t = false, global = true)]
+    pub no_input: bool,
+
+    #[arg(long, default_value_t = DEFAULT_SESSION_TIMEOUT_SECS, global = true)]
     pub session_timeout_secs: u64,
+
+    #[arg(long, default_value_t = DEFAULT_REVISION_RETENTION, global = true)]
+    pub revision_retention: usize,
+
+    #[arg(long, default_value_t = DEFAULT_TRASH_RETENTION_DAYS, global = true)]
+    pub trash_retention_days: u32,
 }
 
 #[derive(Debug, Subcommand)]
+#[rustfmt::skip]
 pub enum Commands {
-    Init,
-    Login,
+    Init {
+        #[arg(long, default_value_t = false)]
+        private_metadata: bool,
+    },
+    Login {
+        #[command(flatten)]
+        secret: SecretArgs,
+    },
     Logout,
-    AddPassword,
-    AddNote,
-    List,
+    ChangeMasterPassword,
+    AddPassword {
+        #[arg(long)]
+        title: Option<String>,
+        #[arg(long)]
+        username: Option<String>,
+        #[arg(long)]
+        url: Option<String>,
+        #[command(flatten)]
+        secret: SecretArgs,
+        #[command(flatten)]
+        fields: FieldArgs,
+    },
+    AddNote {
+        #[arg(long)]
+        title: Option<String>,
+        #[command(flatten)]
+        secret: SecretArgs,
+        #[command(flatten)]
+        fields: FieldArgs,
+    },
+    AddCard { #[command(flatten)] fields: FieldArgs },
+    AddIdentity { #[command(flatten)] fields: FieldArgs },
+    AddSshKey { #[command(flatten)] fields: FieldArgs },
+    AddApiKey { #[command(flatten)] fields: FieldArgs },
+    List {
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(long)]
+        folder: Option<String>,
+        #[arg(long, default_value_t = false)]
+        favorites: bool,
+    },
+    Search {
+        #[arg(required = true)]
+        query: Vec<String>,
+        #[arg(long, default_value_t = false)]
+        deep: bool,
+    },
     Show { id: String },
-    Delete { id: String },
+    Edit {
+        id: String,
+        #[command(flatten)]
+        secret: SecretArgs,
+    },
+    History { id: String },
+    Restore {
+        id: String,
+        #[arg(long)]
+        revision: u32,
+    },
+    Totp {
+        id: String,
+        #[arg(long, default_value_t = false)]
+        copy: bool,
+    },
+    Delete {
+        id: String,
+        #[arg(long)]
+        yes: bool,
+    },
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+    Mv {
+        id: String,
+        folder: String,
+    },
+    Favorite {
+        id: String,
+        #[arg(long, default_value_t = false)]
+        remove: bool,
+    },
     BackupExport { path: String },
     BackupImport { path: String },
-    Sync,
-    Config,
+    Generate(GenerateArgs),
+    #[command(args_conflicts_with_subcommands = true)]
+    Sync {
+        #[command(subcommand)]
+        action: Option<SyncAction>,
+        name: Option<String>,
+        #[arg(long, default_value_t = false, conflicts_with = "name")]
+        all: bool,
+        #[arg(long)]
+        on_conflict: Option<String>,
+        #[arg(long)]
+        dry_run: bool,
+    },
+    Remote {
+        #[command(subcommand)]
+        action: RemoteAction,
+    },
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+    Privacy {
+        #[command(subcommand)]
+        action: PrivacyAction,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TagAction {
+    Add {
+        id: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    Remove {
+        id: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TrashAction {
+    List,
+    Restore {
+        id: String,
+    },
+    Empty {
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SyncAction {
+    ExportBundle {
+        path: String,
+        #[arg(long)]
+        full: bool,
+    },
+    ImportBundle {
+        path: String,
+        #[arg(long)]
+        on_conflict: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RemoteAction {
+    Add {
+        name: String,
+        url: String,
+        #[arg(long)]
+        backend: Option<String>,
+        #[arg(long)]
+        on_conflict: Option<String>,
+    },
+    List,
+    Remove {
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    Get { key: String },
+    Set { key: String, value: String },
+    Reset { key: Option<String> },
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProfileAction {
+    Create {
+        name: String,
+        database_url: String,
+        #[arg(long)]
+        backend: Option<String>,
+    },
+    List,
+    Use {
+        name: String,
+    },
+    Remove {
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PrivacyAction {
+    Status,
+    Enable,
+    Disable {
+        #[arg(long)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct FieldArgs {
+    #[arg(long = "field", value_name = "[KIND:]NAME=VALUE")]
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+#[group(multiple = false)]
+pub struct SecretArgs {
+    #[arg(long, default_value_t = false)]
+    pub password_stdin: bool,
+    #[arg(long, value_name = "N")]
+    pub password_fd: Option<u32>,
+    #[arg(long, value_name = "PATH")]
+    pub password_file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct GenerateArgs {
+    #[arg(long)]
+    pub length: Option<usize>,
+    #[arg(long, default_value_t = false)]
+    pub no_lowercase: bool,
+    #[arg(long, default_value_t = false)]
+    pub no_uppercase: bool,
+    #[arg(long, default_value_t = false)]
+    pub no_digits: bool,
+    #[arg(long, default_value_t = false)]
+    pub no_symbols: bool,
+    #[arg(long, default_value_t = false)]
+    pub exclude_ambiguous: bool,
+    #[arg(long, default_value_t = false)]
+    pub passphrase: bool,
+    #[arg(long)]
+    pub words: Option<usize>,
+    #[arg(long)]
+    pub separator: Option<String>,
+    #[arg(long, default_value_t = false)]
+    pub capitalize: bool,
+    #[arg(long, default_value_t = false)]
+    pub policy: bool,
 }
@@ -1,10 +1,19 @@
-use std::io::{self, Write};
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+};
 
 use dialoguer::{Confirm, Editor, Input, Select, theme::ColorfulTheme};
 use secrecy::{ExposeSecret, SecretString};
 use zeroize::Zeroize;
 
-use crate::core::errors::{ChacrabError, ChacrabResult};
+use crate::{
+    cli::parser::SecretArgs,
+    core::errors::{ChacrabError, ChacrabResult},
+};
+
+const MAX_SECRET_INPUT_BYTES: usize = 1024 * 1024;
 
 pub fn confirmation_prompt(prompt: &str, default: bool) -> ChacrabResult<bool> {
     Confirm::with_theme(&ColorfulTheme::default())
@@ -44,6 +53,56 @@ pub fn secure_password_with_confirmation(
     Ok(first)
 }
 
+pub fn piped_secret(args: &SecretArgs) -> ChacrabResult<Option<SecretString>> {
+    let secret = if args.password_stdin {
+        read_secret(io::stdin().lock())?
+    } else if let Some(fd) = args.password_fd {
+        read_secret(open_secret_file(Path::new(&format!("/dev/fd/{fd}")))?)?
+    } else if let Some(path) = &args.password_file {
+        read_secret(open_secret_file(path)?)?
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(secret))
+}
+
+fn open_secret_file(path: &Path) -> ChacrabResult<File> {
+    File::open(path).map_err(|_| ChacrabError::Config("unable to read secret input".to_owned()))
+}
+
+pub fn read_secret(reader: impl Read) -> ChacrabResult<SecretString> {
+    let mut buffer = Vec::with_capacity(MAX_SECRET_INPUT_BYTES + 1);
+    let read = reader
+        .take(MAX_SECRET_INPUT_BYTES as u64 + 1)
+        .read_to_end(&mut buffer);
+    if read.is_err() || buffer.len() > MAX_SECRET_INPUT_BYTES {
+        buffer.zeroize();
+        let message = if read.is_err() {
+            "unable to read secret input"
+        } else {
+            "secret input is too large"
+        };
+        return Err(ChacrabError::Config(message.to_owned()));
+    }
+
+    let mut value = String::from_utf8(buffer).map_err(|err| {
+        err.into_bytes().zeroize();
+        ChacrabError::Config("secret input is not valid utf-8".to_owned())
+    })?;
+    if value.ends_with('\n') {
+        value.pop();
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+    let secret = SecretString::new(Box::from(value.as_str()));
+    value.zeroize();
+    if secret.expose_secret().is_empty() {
+        return Err(ChacrabError::Config("secret input is empty".to_owned()));
+    }
+    Ok(secret)
+}
+
 pub fn input(prompt: &str) -> ChacrabResult<String> {
     Input::with_theme(&ColorfulTheme::default())
         .with_prompt(prompt)
@@ -51,6 +110,14 @@ pub fn input(prompt: &str) -> ChacrabResult<String> {
         .map_err(|_| ChacrabError::Config("unable to read input".to_owned()))
 }
 
+pub fn input_with_initial(prompt: &str, initial: &str) -> ChacrabResult<String> {
+    Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .with_initial_text(initial)
+        .interact_text()
+        .map_err(|_| ChacrabError::Config("unable to read input".to_owned()))
+}
+
 pub fn optional_input(prompt: &str) -> ChacrabResult<Option<String>> {
     let value: String = Input::with_theme(&ColorfulTheme::default())
         .with_prompt(prompt)
@@ -64,6 +131,20 @@ pub fn optional_input(prompt: &str) -> ChacrabResult<Option<String>> {
     }
 }
 
+pub fn optional_input_with_initial(prompt: &str, initial: &str) -> ChacrabResult<Option<String>> {
+    let value: String = Input::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .with_initial_text(initial)
+        .allow_empty(true)
+        .interact_text()
+        .map_err(|_| ChacrabError::Config("unable to read input".to_owned()))?;
+    if value.trim().is_empty() {
+        Ok(None)
+    } else {
+        Ok(Some(value))
+    }
+}
+
 pub fn multiline(prompt: &str) -> ChacrabResult<Option<String>> {
     Editor::new()
         .edit(prompt)
@@ -78,3 +159,33 @@ pub fn select(prompt: &str, items: &[&str]) -> ChacrabResult<usize> {
         .interact()
         .map_err(|_| ChacrabError::Config("unable to read selection".to_owned()))
 }
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use super::{MAX_SECRET_INPUT_BYTES, read_secret};
+    use crate::core::errors::ChacrabError;
+
+    #[test]
+    fn one_trailing_line_ending_is_stripped() {
+        let secret = read_secret("Secret#123\r\n".as_bytes()).expect("secret");
+        assert_eq!(secret.expose_secret(), "Secret#123");
+
+        let note = read_secret("line one\nline two\n\n".as_bytes()).expect("note");
+        assert_eq!(note.expose_secret(), "line one\nline two\n");
+    }
+
+    #[test]
+    fn empty_or_oversized_input_is_rejected() {
+        assert!(matches!(
+            read_secret("\n".as_bytes()),
+            Err(ChacrabError::Config(message)) if message == "secret input is empty"
+        ));
+        let oversized = vec![b'a'; MAX_SECRET_INPUT_BYTES + 1];
+        assert!(matches!(
+            read_secret(oversized.as_slice()),
+            Err(ChacrabError::Config(message)) if message == "secret input is too large"
+        ));
+    }
+}
@@ -1,7 +1,7 @@
 use chrono::Utc;
 
 use crate::{
-    auth::login,
+    auth::{keyring, login},
     core::errors::{ChacrabError, ChacrabResult},
 };
 
@@ -23,13 +23,20 @@ pub fn session_state() -> SessionState {
 }
 
 pub fn touch_session() -> ChacrabResult<()> {
-    let entry = ::keyring::Entry::new(SESSION_META_SERVICE, SESSION_META_USER)?;
+    let entry = keyring::entry(SESSION_META_USER)?;
     entry.set_password(&Utc::now().timestamp().to_string())?;
     Ok(())
 }
 
 pub fn clear_session_metadata() -> ChacrabResult<()> {
-    let entry = ::keyring::Entry::new(SESSION_META_SERVICE, SESSION_META_USER)?;
+    clear_profile_metadata(keyring::active_profile())
+}
+
+pub fn clear_profile_metadata(profile: &str) -> ChacrabResult<()> {
+    let entry = ::keyring::Entry::new(
+        SESSION_META_SERVICE,
+        &keyring::profile_user(SESSION_META_USER, profile),
+    )?;
     let _ = entry.delete_password();
     Ok(())
 }
@@ -37,7 +44,7 @@ pub fn clear_session_metadata() -> ChacrabResult<()> {
 pub fn enforce_timeout(timeout_secs: u64) -> ChacrabResult<()> {
     let _ = login::current_session_key()?;
 
-    let entry = ::keyring::Entry::new(SESSION_META_SERVICE, SESSION_META_USER)?;
+    let entry = keyring::entry(SESSION_META_USER)?;
     let stored = entry.get_password().ok();
 
     let Some(timestamp_str) = stored else {
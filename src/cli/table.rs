@@ -1,17 +1,113 @@
-use crate::{cli::display::short_id, core::models::VaultItem};
+use std::collections::BTreeMap;
+
+use uuid::Uuid;
+
+use crate::{
+    cli::{
+        display::short_id,
+        runtime_config::{Profile, SyncRemote, split_url_secret},
+    },
+    core::{models::VaultItem, search::SearchHit},
+};
+
+pub struct SyncPlanRow {
+    pub action: &'static str,
+    pub id: Uuid,
+    pub title: String,
+    pub local_revision: Option<u64>,
+    pub remote_revision: Option<u64>,
+}
 
 pub fn print_list_table(items: &[VaultItem]) {
-    println!("ID        TYPE       TITLE                UPDATED");
-    println!("------------------------------------------------------");
+    println!("ID        TYPE       TITLE                 FOLDER          UPDATED");
+    println!("----------------------------------------------------------------------");
     for item in items {
         let id = short_id(&item.id.to_string());
-        let kind = match item.r#type {
-            crate::core::models::VaultItemType::Password => "password",
-            crate::core::models::VaultItemType::Note => "note",
-        };
+        let kind = item.r#type.as_str();
+        let marker = if item.favorite { "*" } else { " " };
         let title = truncate(&item.title, 20);
+        let folder = truncate(item.folder.as_deref().unwrap_or("/"), 14);
         let updated = item.updated_at.format("%Y-%m-%d").to_string();
-        println!("{id:<8}  {kind:<9}  {title:<20}  {updated}");
+        println!("{id:<8}  {kind:<9}  {marker}{title:<20}  {folder:<14}  {updated}");
+    }
+}
+
+pub fn print_search_table(hits: &[SearchHit]) {
+    println!("ID        TYPE       TITLE                 MATCHED        SCORE");
+    println!("--------------------------------------------------------------------");
+    for hit in hits {
+        let id = short_id(&hit.item.id.to_string());
+        let kind = hit.item.r#type.as_str();
+        let marker = if hit.item.favorite { "*" } else { " " };
+        let title = truncate(&hit.item.title, 20);
+        let matched = hit.matched;
+        let score = hit.score;
+        println!("{id:<8}  {kind:<9}  {marker}{title:<20}  {matched:<13}  {score}");
+    }
+}
+
+pub fn print_trash_table(items: &[VaultItem]) {
+    println!("ID        TYPE       TITLE                 DELETED");
+    println!("------------------------------------------------------------");
+    for item in items {
+        let id = short_id(&item.id.to_string());
+        let kind = item.r#type.as_str();
+        let title = truncate(&item.title, 20);
+        let deleted = item
+            .deleted_at
+            .map(|deleted_at| deleted_at.format("%Y-%m-%d").to_string())
+            .unwrap_or_default();
+        println!("{id:<8}  {kind:<9}   {title:<20}  {deleted}");
+    }
+}
+
+pub fn print_sync_plan_table(rows: &[SyncPlanRow]) {
+    println!("ACTION         ID        TITLE                 LOCAL   REMOTE");
+    println!("-----------------------------------------------------------------");
+    for row in rows {
+        let action = row.action;
+        let id = short_id(&row.id.to_string());
+        let title = truncate(&row.title, 20);
+        let revision = |revision: Option<u64>| {
+            revision.map_or_else(|| "-".to_owned(), |revision| format!("r{revision}"))
+        };
+        let local = revision(row.local_revision);
+        let remote = revision(row.remote_revision);
+        println!("{action:<13}  {id:<8}  {title:<20}  {local:<6}  {remote}");
+    }
+}
+
+pub fn print_remote_table(remotes: &[SyncRemote]) {
+    println!("NAME            BACKEND   POLICY          URL");
+    println!("----------------------------------------------------------------------");
+    for remote in remotes {
+        let name = truncate(&remote.name, 14);
+        let backend = &remote.backend;
+        let policy = remote.conflict_policy.as_deref().unwrap_or("-");
+        let url = &remote.url;
+        println!("{name:<14}  {backend:<8}  {policy:<14}  {url}");
+    }
+}
+
+pub fn print_profile_table(profiles: &BTreeMap<String, Profile>, active: &str) {
+    println!("  NAME            BACKEND   DATABASE URL");
+    println!("----------------------------------------------------------------------");
+    for (name, profile) in profiles {
+        let marker = if name == active { "*" } else { " " };
+        let name = truncate(name, 14);
+        let backend = &profile.backend;
+        let (url, _) = split_url_secret(&profile.database_url);
+        println!("{marker} {name:<14}  {backend:<8}  {url}");
+    }
+}
+
+pub fn print_config_table(rows: &[(&str, String, bool)]) {
+    println!("KEY                          VALUE                     SOURCE");
+    println!("----------------------------------------------------------------------");
+    for (key, value, is_default) in rows {
+        let value = truncate(value, 24);
+        let source = if *is_default { "default" } else { "config" };
+        println!("{key:<27}  {value:<24}  {source}");
     }
 }
 
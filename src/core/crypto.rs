@@ -4,7 +4,7 @@ use argon2::{
 };
 use chacha20poly1305::{
     ChaCha20Poly1305, Key, Nonce,
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
 };
 use rand::RngCore;
 use secrecy::{ExposeSecret, SecretString};
@@ -133,13 +133,27 @@ pub fn verify_password_with_params(
 }
 
 pub fn encrypt(key_bytes: &[u8; KEY_SIZE], plaintext: &[u8]) -> ChacrabResult<CipherBlob> {
+    encrypt_with_aad(key_bytes, plaintext, &[])
+}
+
+pub fn encrypt_with_aad(
+    key_bytes: &[u8; KEY_SIZE],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> ChacrabResult<CipherBlob> {
     let mut nonce = [0u8; NONCE_SIZE];
     let mut rng = rand::rng();
     rng.fill_bytes(&mut nonce);
 
     let key = Key::from_slice(key_bytes);
     let cipher = ChaCha20Poly1305::new(key);
-    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), plaintext)?;
+    let ciphertext = cipher.encrypt(
+        Nonce::from_slice(&nonce),
+        Payload {
+            msg: plaintext,
+            aad,
+        },
+    )?;
 
     Ok(CipherBlob { ciphertext, nonce })
 }
@@ -148,13 +162,54 @@ pub fn decrypt(
     key_bytes: &[u8; KEY_SIZE],
     nonce: &[u8; NONCE_SIZE],
     ciphertext: &[u8],
+) -> ChacrabResult<Vec<u8>> {
+    decrypt_with_aad(key_bytes, nonce, ciphertext, &[])
+}
+
+pub fn decrypt_with_aad(
+    key_bytes: &[u8; KEY_SIZE],
+    nonce: &[u8; NONCE_SIZE],
+    ciphertext: &[u8],
+    aad: &[u8],
 ) -> ChacrabResult<Vec<u8>> {
     let key = Key::from_slice(key_bytes);
     let cipher = ChaCha20Poly1305::new(key);
-    let plaintext = cipher.decrypt(Nonce::from_slice(nonce), ciphertext)?;
+    let plaintext = cipher.decrypt(
+        Nonce::from_slice(nonce),
+        Payload {
+            msg: ciphertext,
+            aad,
+        },
+    )?;
     Ok(plaintext)
 }
 
+pub fn generate_vault_key() -> [u8; KEY_SIZE] {
+    let mut key = [0u8; KEY_SIZE];
+    rand::rng().fill_bytes(&mut key);
+    key
+}
+
+pub fn wrap_key(wrapping_key: &[u8; KEY_SIZE], key: &[u8; KEY_SIZE]) -> ChacrabResult<CipherBlob> {
+    encrypt(wrapping_key, key)
+}
+
+pub fn unwrap_key(
+    wrapping_key: &[u8; KEY_SIZE],
+    nonce: &[u8; NONCE_SIZE],
+    wrapped: &[u8],
+) -> ChacrabResult<[u8; KEY_SIZE]> {
+    let mut plaintext = decrypt(wrapping_key, nonce, wrapped)?;
+    if plaintext.len() != KEY_SIZE {
+        plaintext.zeroize();
+        return Err(ChacrabError::Crypto);
+    }
+    let mut key = [0u8; KEY_SIZE];
+    key.copy_from_slice(&plaintext);
+    plaintext.zeroize();
+    Ok(key)
+}
+
 pub fn zeroize_vec(buffer: &mut Vec<u8>) {
     buffer.zeroize();
 }
@@ -164,7 +219,8 @@ mod tests {
     use secrecy::SecretString;
 
     use super::{
-        KEY_SIZE, create_registration_material, decrypt, derive_key, encrypt, verify_password,
+        KEY_SIZE, create_registration_material, decrypt, decrypt_with_aad, derive_key, encrypt,
+        encrypt_with_aad, generate_vault_key, unwrap_key, verify_password, wrap_key,
     };
 
     #[test]
@@ -227,4 +283,33 @@ mod tests {
             "nonces should be randomly generated"
         );
     }
+
+    #[test]
+    fn wrapped_vault_key_roundtrip() {
+        let master_password = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
+        let salt = super::generate_salt();
+        let wrapping_key = derive_key(&master_password, &salt).expect("key derivation");
+        let vault_key = generate_vault_key();
+
+        let wrapped = wrap_key(&wrapping_key, &vault_key).expect("wrap");
+        assert_ne!(wrapped.ciphertext.as_slice(), vault_key.as_slice());
+        let unwrapped =
+            unwrap_key(&wrapping_key, &wrapped.nonce, &wrapped.ciphertext).expect("unwrap");
+        assert_eq!(unwrapped, vault_key);
+
+        let other_key = generate_vault_key();
+        assert!(unwrap_key(&other_key, &wrapped.nonce, &wrapped.ciphertext).is_err());
+    }
+
+    #[test]
+    fn decrypt_rejects_mismatched_associated_data() {
+        let key = generate_vault_key();
+        let blob = encrypt_with_aad(&key, b"payload", b"item-a").expect("encryption");
+
+        let decrypted =
+            decrypt_with_aad(&key, &blob.nonce, &blob.ciphertext, b"item-a").expect("decryption");
+        assert_eq!(decrypted, b"payload");
+        assert!(decrypt_with_aad(&key, &blob.nonce, &blob.ciphertext, b"item-b").is_err());
+        assert!(decrypt(&key, &blob.nonce, &blob.ciphertext).is_err());
+    }
 }
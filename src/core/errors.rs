@@ -16,12 +16,16 @@ pub enum ChacrabError {
     KeyringLocked,
     #[error("item not found")]
     NotFound,
+    #[error("unsupported item type: {0}")]
+    UnsupportedItemType(String),
     #[error("unsupported backend: {0}")]
     UnsupportedBackend(String),
     #[error("configuration error: {0}")]
     Config(String),
     #[error("crypto operation failed")]
     Crypto,
+    #[error("integrity check failed: {0}")]
+    Integrity(String),
     #[error("serialization failed")]
     Serialization,
     #[error("storage operation failed")]
@@ -67,6 +71,12 @@ impl From<sqlx::Error> for ChacrabError {
     }
 }
 
+impl From<reqwest::Error> for ChacrabError {
+    fn from(_: reqwest::Error) -> Self {
+        Self::Storage
+    }
+}
+
 impl From<mongodb::error::Error> for ChacrabError {
     fn from(_: mongodb::error::Error) -> Self {
         Self::Storage
@@ -0,0 +1,295 @@
+use rand::{Rng, seq::SliceRandom};
+use secrecy::SecretString;
+use zeroize::Zeroize;
+
+use crate::core::{
+    errors::{ChacrabError, ChacrabResult},
+    password_policy,
+};
+
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.?/~";
+const AMBIGUOUS: &str = "Il1O0o|`'\";:,.";
+const WORDLIST: &str = include_str!("eff_large_wordlist.txt");
+const MAX_POLICY_ATTEMPTS: usize = 100;
+
+#[derive(Debug, Clone)]
+pub struct PasswordOptions {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+    pub exclude_ambiguous: bool,
+    pub enforce_policy: bool,
+}
+
+impl Default for PasswordOptions {
+    fn default() -> Self {
+        Self {
+            length: 20,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: false,
+            enforce_policy: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PassphraseOptions {
+    pub words: usize,
+    pub separator: String,
+    pub capitalize: bool,
+    pub enforce_policy: bool,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        Self {
+            words: 6,
+            separator: "-".to_owned(),
+            capitalize: false,
+            enforce_policy: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct GeneratedSecret {
+    pub value: SecretString,
+    pub entropy_bits: f64,
+}
+
+pub fn generate_password(options: &PasswordOptions) -> ChacrabResult<GeneratedSecret> {
+    let classes = [
+        (options.lowercase, LOWERCASE),
+        (options.uppercase, UPPERCASE),
+        (options.digits, DIGITS),
+        (options.symbols, SYMBOLS),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, charset)| {
+        charset
+            .chars()
+            .filter(|ch| !options.exclude_ambiguous || !AMBIGUOUS.contains(*ch))
+            .collect::<Vec<char>>()
+    })
+    .collect::<Vec<_>>();
+
+    if classes.is_empty() {
+        return Err(ChacrabError::Config(
+            "generator needs at least one character class".to_owned(),
+        ));
+    }
+    if options.length < classes.len() {
+        return Err(ChacrabError::Config(
+            "generator length is shorter than the number of required classes".to_owned(),
+        ));
+    }
+
+    let pool = classes.concat();
+    let entropy_bits = options.length as f64 * (pool.len() as f64).log2();
+
+    with_policy(options.enforce_policy, || {
+        let mut rng = rand::rng();
+        let mut chars = classes
+            .iter()
+            .map(|class| class[rng.random_range(0..class.len())])
+            .collect::<Vec<char>>();
+        while chars.len() < options.length {
+            chars.push(pool[rng.random_range(0..pool.len())]);
+        }
+        chars.shuffle(&mut rng);
+        let value = chars.iter().collect::<String>();
+        chars.zeroize();
+        value
+    })
+    .map(|value| GeneratedSecret {
+        value,
+        entropy_bits,
+    })
+}
+
+pub fn generate_passphrase(options: &PassphraseOptions) -> ChacrabResult<GeneratedSecret> {
+    if options.words == 0 {
+        return Err(ChacrabError::Config(
+            "passphrase needs at least one word".to_owned(),
+        ));
+    }
+
+    let words = wordlist();
+    let entropy_bits = options.words as f64 * (words.len() as f64).log2();
+
+    with_policy(options.enforce_policy, || {
+        let mut rng = rand::rng();
+        (0..options.words)
+            .map(|_| {
+                let word = words[rng.random_range(0..words.len())];
+                if options.capitalize {
+                    capitalize(word)
+                } else {
+                    word.to_owned()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(&options.separator)
+    })
+    .map(|value| GeneratedSecret {
+        value,
+        entropy_bits,
+    })
+}
+
+pub fn wordlist() -> Vec<&'static str> {
+    // Hyphenated words would blur word boundaries with the default separator.
+    WORDLIST
+        .lines()
+        .filter(|line| !line.is_empty() && !line.contains('-'))
+        .collect()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn with_policy(
+    enforce_policy: bool,
+    mut generate: impl FnMut() -> String,
+) -> ChacrabResult<SecretString> {
+    for _ in 0..MAX_POLICY_ATTEMPTS {
+        let mut candidate = generate();
+        if !enforce_policy || password_policy::validate_master_password(&candidate).is_ok() {
+            return Ok(SecretString::new(candidate.into_boxed_str()));
+        }
+        candidate.zeroize();
+    }
+
+    Err(ChacrabError::Config(
+        "generator options cannot satisfy the password policy".to_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use secrecy::ExposeSecret;
+
+    use super::{
+        AMBIGUOUS, PassphraseOptions, PasswordOptions, WORDLIST, generate_passphrase,
+        generate_password, wordlist,
+    };
+    use crate::core::password_policy::validate_master_password;
+
+    #[test]
+    fn password_contains_every_required_class() {
+        let options = PasswordOptions {
+            length: 8,
+            ..PasswordOptions::default()
+        };
+        for _ in 0..50 {
+            let generated = generate_password(&options).expect("password");
+            let value = generated.value.expose_secret();
+            assert_eq!(value.chars().count(), 8);
+            assert!(value.chars().any(|ch| ch.is_ascii_lowercase()));
+            assert!(value.chars().any(|ch| ch.is_ascii_uppercase()));
+            assert!(value.chars().any(|ch| ch.is_ascii_digit()));
+            assert!(value.chars().any(|ch| !ch.is_ascii_alphanumeric()));
+        }
+    }
+
+    #[test]
+    fn password_excludes_ambiguous_characters() {
+        let options = PasswordOptions {
+            length: 64,
+            exclude_ambiguous: true,
+            ..PasswordOptions::default()
+        };
+        let generated = generate_password(&options).expect("password");
+        assert!(
+            !generated
+                .value
+                .expose_secret()
+                .chars()
+                .any(|ch| AMBIGUOUS.contains(ch))
+        );
+    }
+
+    #[test]
+    fn password_rejects_impossible_options() {
+        let no_classes = PasswordOptions {
+            lowercase: false,
+            uppercase: false,
+            digits: false,
+            symbols: false,
+            ..PasswordOptions::default()
+        };
+        assert!(generate_password(&no_classes).is_err());
+
+        let too_short_for_policy = PasswordOptions {
+            length: 8,
+            enforce_policy: true,
+            ..PasswordOptions::default()
+        };
+        assert!(generate_password(&too_short_for_policy).is_err());
+    }
+
+    #[test]
+    fn password_reports_entropy() {
+        let options = PasswordOptions {
+            length: 10,
+            uppercase: false,
+            symbols: false,
+            ..PasswordOptions::default()
+        };
+        let generated = generate_password(&options).expect("password");
+        assert!((generated.entropy_bits - 10.0 * 36f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn passphrase_uses_wordlist_and_separator() {
+        let options = PassphraseOptions {
+            words: 5,
+            separator: " ".to_owned(),
+            ..PassphraseOptions::default()
+        };
+        let generated = generate_passphrase(&options).expect("passphrase");
+        let words = wordlist();
+        let parts = generated
+            .value
+            .expose_secret()
+            .split(' ')
+            .map(str::to_owned)
+            .collect::<Vec<_>>();
+        assert_eq!(parts.len(), 5);
+        assert!(parts.iter().all(|part| words.contains(&part.as_str())));
+        assert!((generated.entropy_bits - 5.0 * 7772f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn capitalized_passphrase_passes_policy_when_asked() {
+        let options = PassphraseOptions {
+            words: 4,
+            capitalize: true,
+            enforce_policy: true,
+            ..PassphraseOptions::default()
+        };
+        let generated = generate_passphrase(&options).expect("passphrase");
+        assert!(validate_master_password(generated.value.expose_secret()).is_ok());
+    }
+
+    #[test]
+    fn embedded_wordlist_is_complete() {
+        assert_eq!(WORDLIST.lines().count(), 7776);
+        assert_eq!(wordlist().len(), 7772);
+        assert!(wordlist().iter().all(|word| !word.contains('-')));
+    }
+}
@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+
+use crate::core::models::VaultItem;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RevisionEntry {
+    pub revision: u32,
+    pub recorded_at: DateTime<Utc>,
+    pub item: VaultItem,
+    pub changes: Vec<FieldChange>,
+    pub secrets_changed: bool,
+}
+
+pub fn diff_metadata(before: &VaultItem, after: &VaultItem) -> Vec<FieldChange> {
+    let fields = [
+        (
+            "title",
+            Some(before.title.clone()),
+            Some(after.title.clone()),
+        ),
+        ("username", before.username.clone(), after.username.clone()),
+        ("url", before.url.clone(), after.url.clone()),
+        ("folder", before.folder.clone(), after.folder.clone()),
+        (
+            "tags",
+            Some(before.tags.join(", ")).filter(|tags| !tags.is_empty()),
+            Some(after.tags.join(", ")).filter(|tags| !tags.is_empty()),
+        ),
+        (
+            "favorite",
+            Some(before.favorite.to_string()),
+            Some(after.favorite.to_string()),
+        ),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, before, after)| before != after)
+        .map(|(field, before, after)| FieldChange {
+            field,
+            before: before.unwrap_or_else(|| "-".to_owned()),
+            after: after.unwrap_or_else(|| "-".to_owned()),
+        })
+        .collect()
+}
@@ -1,6 +1,10 @@
 pub mod backup;
 pub mod crypto;
 pub mod errors;
+pub mod generator;
+pub mod history;
 pub mod models;
 pub mod password_policy;
+pub mod search;
+pub mod totp;
 pub mod vault;
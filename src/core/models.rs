@@ -1,16 +1,57 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use secrecy::{ExposeSecret, SecretString};
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::{collections::BTreeMap, str::FromStr};
+use url::Url;
 use uuid::Uuid;
+use zeroize::Zeroize;
+
+use crate::core::errors::{ChacrabError, ChacrabResult};
+
+pub const LEGACY_ITEM_FORMAT_VERSION: u32 = 1;
+pub const ITEM_FORMAT_VERSION: u32 = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
 pub enum VaultItemType {
     Password,
     Note,
+    Card,
+    Identity,
+    SshKey,
+    ApiKey,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl VaultItemType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VaultItemType::Password => "password",
+            VaultItemType::Note => "note",
+            VaultItemType::Card => "card",
+            VaultItemType::Identity => "identity",
+            VaultItemType::SshKey => "ssh_key",
+            VaultItemType::ApiKey => "api_key",
+        }
+    }
+}
+
+impl FromStr for VaultItemType {
+    type Err = ChacrabError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "password" => Ok(VaultItemType::Password),
+            "note" => Ok(VaultItemType::Note),
+            "card" => Ok(VaultItemType::Card),
+            "identity" => Ok(VaultItemType::Identity),
+            "ssh_key" => Ok(VaultItemType::SshKey),
+            "api_key" => Ok(VaultItemType::ApiKey),
+            other => Err(ChacrabError::UnsupportedItemType(other.to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct VaultItem {
     pub id: Uuid,
     pub r#type: VaultItemType,
@@ -19,15 +60,322 @@ pub struct VaultItem {
     pub url: Option<String>,
     pub encrypted_data: Vec<u8>,
     pub nonce: [u8; 12],
+    #[serde(default = "legacy_item_format_version")]
+    pub format_version: u32,
+    #[serde(default)]
+    pub encrypted_metadata: Option<Vec<u8>>,
+    #[serde(default)]
+    pub metadata_nonce: Option<[u8; 12]>,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub deleted_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub revision: u64,
+    #[serde(default)]
+    pub base_revision: u64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemMetadata {
+    pub title: String,
+    pub username: Option<String>,
+    pub url: Option<String>,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub favorite: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ItemFilter {
+    pub tag: Option<String>,
+    pub folder: Option<String>,
+    pub favorites_only: bool,
+}
+
+impl ItemFilter {
+    pub fn matches(&self, item: &VaultItem) -> bool {
+        if self.favorites_only && !item.favorite {
+            return false;
+        }
+        if let Some(tag) = &self.tag
+            && !item
+                .tags
+                .iter()
+                .any(|candidate| candidate.eq_ignore_ascii_case(tag))
+        {
+            return false;
+        }
+        if let Some(folder) = self.folder.as_deref().and_then(normalize_folder) {
+            let Some(item_folder) = &item.folder else {
+                return false;
+            };
+            return item_folder == &folder || item_folder.starts_with(&format!("{folder}/"));
+        }
+        true
+    }
+}
+
+pub fn normalize_folder(path: &str) -> Option<String> {
+    let normalized = path
+        .split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    (!normalized.is_empty()).then_some(normalized)
+}
+
+pub fn normalize_tags(tags: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim();
+        if !tag.is_empty()
+            && !normalized
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(tag))
+        {
+            normalized.push(tag.to_owned());
+        }
+    }
+    normalized.sort_by_key(|tag| tag.to_lowercase());
+    normalized
+}
+
+fn legacy_item_format_version() -> u32 {
+    LEGACY_ITEM_FORMAT_VERSION
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TotpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TotpSecret {
+    pub secret: String,
+    pub algorithm: TotpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+    pub issuer: Option<String>,
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomFieldKind {
+    Text,
+    Hidden,
+    Url,
+    Boolean,
+    Date,
+}
+
+impl CustomFieldKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CustomFieldKind::Text => "text",
+            CustomFieldKind::Hidden => "hidden",
+            CustomFieldKind::Url => "url",
+            CustomFieldKind::Boolean => "boolean",
+            CustomFieldKind::Date => "date",
+        }
+    }
+}
+
+impl FromStr for CustomFieldKind {
+    type Err = ChacrabError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(CustomFieldKind::Text),
+            "hidden" => Ok(CustomFieldKind::Hidden),
+            "url" => Ok(CustomFieldKind::Url),
+            "boolean" => Ok(CustomFieldKind::Boolean),
+            "date" => Ok(CustomFieldKind::Date),
+            _ => Err(ChacrabError::Config("unknown custom field kind".to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CustomField {
+    pub name: String,
+    pub kind: CustomFieldKind,
+    pub value: String,
+}
+
+impl CustomField {
+    pub fn new(name: String, kind: CustomFieldKind, value: String) -> ChacrabResult<Self> {
+        let name = name.trim().to_owned();
+        if name.is_empty() {
+            return Err(ChacrabError::Config(
+                "custom field name is required".to_owned(),
+            ));
+        }
+
+        let value = match kind {
+            CustomFieldKind::Text | CustomFieldKind::Hidden => value,
+            CustomFieldKind::Url => {
+                Url::parse(value.trim())
+                    .map_err(|_| ChacrabError::Config("invalid custom field url".to_owned()))?;
+                value.trim().to_owned()
+            }
+            CustomFieldKind::Boolean => match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" => "true".to_owned(),
+                "false" | "no" | "n" | "0" => "false".to_owned(),
+                _ => {
+                    return Err(ChacrabError::Config(
+                        "invalid custom field boolean".to_owned(),
+                    ));
+                }
+            },
+            CustomFieldKind::Date => NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+                .map_err(|_| ChacrabError::Config("invalid custom field date".to_owned()))?
+                .format("%Y-%m-%d")
+                .to_string(),
+        };
+
+        Ok(Self { name, kind, value })
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.kind == CustomFieldKind::Hidden
+    }
+}
+
+fn deserialize_custom_fields<'de, D>(deserializer: D) -> Result<Vec<CustomField>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredCustomFields {
+        Typed(Vec<CustomField>),
+        Legacy(serde_json::Map<String, serde_json::Value>),
+    }
+
+    Ok(match StoredCustomFields::deserialize(deserializer)? {
+        StoredCustomFields::Typed(fields) => fields,
+        StoredCustomFields::Legacy(map) => map
+            .into_iter()
+            .map(|(name, value)| CustomField {
+                name,
+                kind: CustomFieldKind::Text,
+                value: match value {
+                    serde_json::Value::String(text) => text,
+                    other => other.to_string(),
+                },
+            })
+            .collect(),
+    })
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CardDetails {
+    pub cardholder_name: Option<String>,
+    pub number: String,
+    pub expiry: Option<String>,
+    pub security_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IdentityDetails {
+    pub full_name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub date_of_birth: Option<String>,
+    pub document_number: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SshKeyDetails {
+    pub private_key: String,
+    pub public_key: Option<String>,
+    pub passphrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ApiKeyDetails {
+    pub key_id: Option<String>,
+    pub secret: String,
+    pub endpoint: Option<String>,
+    pub expires_at: Option<String>,
+}
+
+impl Zeroize for CardDetails {
+    fn zeroize(&mut self) {
+        self.number.zeroize();
+        self.security_code.zeroize();
+    }
+}
+
+impl Zeroize for IdentityDetails {
+    fn zeroize(&mut self) {
+        self.document_number.zeroize();
+        self.date_of_birth.zeroize();
+    }
+}
+
+impl Zeroize for SshKeyDetails {
+    fn zeroize(&mut self) {
+        self.private_key.zeroize();
+        self.passphrase.zeroize();
+    }
+}
+
+impl Zeroize for ApiKeyDetails {
+    fn zeroize(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct EncryptedPayload {
     pub password: Option<String>,
     pub notes: Option<String>,
-    pub custom_fields: serde_json::Map<String, serde_json::Value>,
+    #[serde(default, deserialize_with = "deserialize_custom_fields")]
+    pub custom_fields: Vec<CustomField>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub totp: Option<TotpSecret>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub card: Option<CardDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<IdentityDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssh_key: Option<SshKeyDetails>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<ApiKeyDetails>,
+}
+
+impl Zeroize for EncryptedPayload {
+    fn zeroize(&mut self) {
+        self.password.zeroize();
+        self.notes.zeroize();
+        for field in &mut self.custom_fields {
+            field.value.zeroize();
+        }
+        if let Some(totp) = self.totp.as_mut() {
+            totp.secret.zeroize();
+        }
+        self.card.zeroize();
+        self.identity.zeroize();
+        self.ssh_key.zeroize();
+        self.api_key.zeroize();
+    }
 }
 
 impl EncryptedPayload {
@@ -35,15 +383,46 @@ impl EncryptedPayload {
         Self {
             password: Some(password.expose_secret().to_owned()),
             notes,
-            custom_fields: serde_json::Map::new(),
+            ..Self::default()
         }
     }
 
     pub fn for_note(notes: SecretString) -> Self {
         Self {
-            password: None,
             notes: Some(notes.expose_secret().to_owned()),
-            custom_fields: serde_json::Map::new(),
+            ..Self::default()
+        }
+    }
+
+    pub fn for_card(card: CardDetails, notes: Option<String>) -> Self {
+        Self {
+            card: Some(card),
+            notes,
+            ..Self::default()
+        }
+    }
+
+    pub fn for_identity(identity: IdentityDetails, notes: Option<String>) -> Self {
+        Self {
+            identity: Some(identity),
+            notes,
+            ..Self::default()
+        }
+    }
+
+    pub fn for_ssh_key(ssh_key: SshKeyDetails, notes: Option<String>) -> Self {
+        Self {
+            ssh_key: Some(ssh_key),
+            notes,
+            ..Self::default()
+        }
+    }
+
+    pub fn for_api_key(api_key: ApiKeyDetails, notes: Option<String>) -> Self {
+        Self {
+            api_key: Some(api_key),
+            notes,
+            ..Self::default()
         }
     }
 }
@@ -55,6 +434,75 @@ pub struct AuthRecord {
     pub argon2_m_cost: u32,
     pub argon2_t_cost: u32,
     pub argon2_p_cost: u32,
+    pub wrapped_vault_key: Option<Vec<u8>>,
+    pub wrapped_vault_key_nonce: Option<[u8; 12]>,
+    pub metadata_privacy: bool,
+    /// Bumped each time the vault key is wrapped under a new master password.
+    #[serde(default)]
+    pub revision: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemRevision {
+    pub item_id: Uuid,
+    pub revision: u32,
+    pub encrypted_snapshot: Vec<u8>,
+    pub nonce: [u8; 12],
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Tombstone {
+    pub id: Uuid,
+    pub deleted_at: DateTime<Utc>,
+    /// Revision of the item when it was deleted; 0 for tombstones written before schema version 14.
+    #[serde(default)]
+    pub revision: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChangeSet {
+    pub items: Vec<VaultItem>,
+    pub tombstones: Vec<Tombstone>,
+    pub cursor: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncState {
+    pub remote: String,
+    pub local_cursor: u64,
+    pub remote_cursor: u64,
+    pub manifest: Option<VaultManifest>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub revision: u64,
+    pub digest: String,
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VaultManifest {
+    pub generation: u64,
+    pub entries: BTreeMap<Uuid, ManifestEntry>,
+    pub mac: String,
+}
+
+#[derive(Debug, Default)]
+pub struct ItemChanges {
+    pub title: Option<String>,
+    pub username: Option<Option<String>>,
+    pub url: Option<Option<String>>,
+    pub password: Option<SecretString>,
+    pub notes: Option<Option<SecretString>>,
+    pub totp: Option<Option<TotpSecret>>,
+    pub card: Option<CardDetails>,
+    pub identity: Option<IdentityDetails>,
+    pub ssh_key: Option<SshKeyDetails>,
+    pub api_key: Option<ApiKeyDetails>,
+    pub custom_fields: Option<Vec<CustomField>>,
 }
 
 #[derive(Debug, Clone)]
@@ -65,3 +513,54 @@ pub struct NewVaultItem {
     pub url: Option<String>,
     pub payload: EncryptedPayload,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CustomField, CustomFieldKind, EncryptedPayload};
+
+    #[test]
+    fn legacy_custom_field_map_loads_as_text_fields() {
+        let payload: EncryptedPayload = serde_json::from_str(
+            r#"{"password":"pw","notes":null,"custom_fields":{"pin":"1234","retries":3}}"#,
+        )
+        .expect("legacy payload");
+        assert_eq!(payload.custom_fields.len(), 2);
+        assert!(
+            payload
+                .custom_fields
+                .iter()
+                .all(|field| field.kind == CustomFieldKind::Text)
+        );
+        assert!(
+            payload
+                .custom_fields
+                .iter()
+                .any(|field| field.name == "retries" && field.value == "3")
+        );
+    }
+
+    #[test]
+    fn custom_field_values_are_validated_per_kind() {
+        let boolean =
+            CustomField::new("2FA".to_owned(), CustomFieldKind::Boolean, "yes".to_owned())
+                .expect("boolean");
+        assert_eq!(boolean.value, "true");
+        assert!(
+            CustomField::new(
+                "Site".to_owned(),
+                CustomFieldKind::Url,
+                "not a url".to_owned()
+            )
+            .is_err()
+        );
+        assert!(
+            CustomField::new(
+                "Renewal".to_owned(),
+                CustomFieldKind::Date,
+                "2026-13-01".to_owned()
+            )
+            .is_err()
+        );
+        assert!(CustomField::new(" ".to_owned(), CustomFieldKind::Text, "x".to_owned()).is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use url::Url;
+
+use crate::core::models::{EncryptedPayload, VaultItem};
+
+const EXACT: u32 = 1000;
+const PREFIX: u32 = 800;
+const WORD_PREFIX: u32 = 700;
+const SUBSTRING: u32 = 600;
+const SUBSEQUENCE: u32 = 300;
+
+const TITLE_WEIGHT: u32 = 4;
+const USERNAME_WEIGHT: u32 = 3;
+const HOST_WEIGHT: u32 = 3;
+const TAG_WEIGHT: u32 = 2;
+const DEEP_WEIGHT: u32 = 1;
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub item: VaultItem,
+    pub score: u32,
+    pub matched: &'static str,
+}
+
+pub fn query_terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(str::to_lowercase).collect()
+}
+
+pub fn fuzzy_score(term: &str, candidate: &str) -> Option<u32> {
+    let candidate = candidate.to_lowercase();
+    if term.is_empty() || candidate.is_empty() {
+        return None;
+    }
+    if candidate == term {
+        return Some(EXACT);
+    }
+    if candidate.starts_with(term) {
+        return Some(PREFIX);
+    }
+    if let Some(position) = candidate.find(term) {
+        let at_word_start = candidate[..position]
+            .chars()
+            .next_back()
+            .is_some_and(|ch| !ch.is_alphanumeric());
+        return Some(if at_word_start {
+            WORD_PREFIX
+        } else {
+            SUBSTRING
+        });
+    }
+    subsequence_score(term, &candidate)
+}
+
+pub fn score_item(
+    terms: &[String],
+    item: &VaultItem,
+    payload: Option<&EncryptedPayload>,
+) -> Option<(u32, &'static str)> {
+    let host = item.url.as_deref().and_then(url_host);
+    let mut fields = vec![(TITLE_WEIGHT, "title", item.title.as_str())];
+    fields.extend(
+        item.username
+            .as_deref()
+            .map(|username| (USERNAME_WEIGHT, "username", username)),
+    );
+    fields.extend(host.as_deref().map(|host| (HOST_WEIGHT, "url", host)));
+    fields.extend(
+        item.tags
+            .iter()
+            .map(|tag| (TAG_WEIGHT, "tag", tag.as_str())),
+    );
+
+    if let Some(payload) = payload {
+        fields.extend(
+            payload
+                .notes
+                .as_deref()
+                .map(|notes| (DEEP_WEIGHT, "notes", notes)),
+        );
+        for field in &payload.custom_fields {
+            fields.push((DEEP_WEIGHT, "custom field", field.name.as_str()));
+            if !field.is_hidden() {
+                fields.push((DEEP_WEIGHT, "custom field", field.value.as_str()));
+            }
+        }
+    }
+    score_fields(terms, &fields)
+}
+
+pub fn url_host(url: &str) -> Option<String> {
+    let parsed = Url::parse(url)
+        .ok()
+        .or_else(|| Url::parse(&format!("https://{url}")).ok())?;
+    parsed
+        .host_str()
+        .map(|host| host.trim_start_matches("www.").to_owned())
+}
+
+fn score_fields(
+    terms: &[String],
+    fields: &[(u32, &'static str, &str)],
+) -> Option<(u32, &'static str)> {
+    let mut total = 0;
+    let mut best_field = None;
+    let mut best_score = 0;
+    for term in terms {
+        let (score, field) = fields
+            .iter()
+            .filter_map(|(weight, field, value)| {
+                fuzzy_score(term, value).map(|score| (score * weight, *field))
+            })
+            .max_by_key(|(score, _)| *score)?;
+        total += score;
+        if score > best_score {
+            best_score = score;
+            best_field = Some(field);
+        }
+    }
+    best_field.map(|field| (total, field))
+}
+
+fn subsequence_score(term: &str, candidate: &str) -> Option<u32> {
+    let mut chars = candidate.char_indices();
+    let mut start = None;
+    let mut end = 0;
+    for wanted in term.chars() {
+        let (index, ch) = chars.find(|(_, ch)| *ch == wanted)?;
+        start.get_or_insert(index);
+        end = index + ch.len_utf8();
+    }
+
+    let span = end - start.unwrap_or_default();
+    let gaps = u32::try_from(span.saturating_sub(term.len())).unwrap_or(u32::MAX);
+    SUBSEQUENCE
+        .checked_sub(gaps.saturating_mul(10))
+        .filter(|score| *score > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_score, query_terms, url_host};
+
+    #[test]
+    fn ranks_exact_over_prefix_over_substring_over_subsequence() {
+        let exact = fuzzy_score("github", "GitHub").expect("exact");
+        let prefix = fuzzy_score("git", "GitHub").expect("prefix");
+        let word = fuzzy_score("hub", "git hub").expect("word");
+        let substring = fuzzy_score("hub", "GitHub").expect("substring");
+        let subsequence = fuzzy_score("gthb", "GitHub").expect("subsequence");
+        assert!(exact > prefix);
+        assert!(prefix > word);
+        assert!(word > substring);
+        assert!(substring > subsequence);
+    }
+
+    #[test]
+    fn rejects_unrelated_and_scattered_matches() {
+        assert!(fuzzy_score("bank", "GitHub").is_none());
+        assert!(fuzzy_score("ae", &format!("a{}e", "z".repeat(40))).is_none());
+    }
+
+    #[test]
+    fn extracts_url_hosts() {
+        assert_eq!(
+            url_host("https://www.github.com/login").as_deref(),
+            Some("github.com")
+        );
+        assert_eq!(
+            url_host("mail.example.com").as_deref(),
+            Some("mail.example.com")
+        );
+        assert_eq!(query_terms("  GitHub  Work "), vec!["github", "work"]);
+    }
+}
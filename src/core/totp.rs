@@ -0,0 +1,318 @@
+use hmac::{Hmac, Mac, digest::KeyInit};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use url::Url;
+use zeroize::Zeroize;
+
+use crate::core::{
+    errors::{ChacrabError, ChacrabResult},
+    models::{TotpAlgorithm, TotpSecret},
+};
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_PERIOD: u64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TotpCode {
+    pub code: String,
+    pub remaining_secs: u64,
+}
+
+pub fn parse_otpauth_uri(uri: &str) -> ChacrabResult<TotpSecret> {
+    let invalid = || ChacrabError::Config("invalid otpauth uri".to_owned());
+    let parsed = Url::parse(uri).map_err(|_| invalid())?;
+    if parsed.scheme() != "otpauth" || parsed.host_str() != Some("totp") {
+        return Err(invalid());
+    }
+
+    let label = percent_decode(parsed.path().trim_start_matches('/'));
+    let (label_issuer, account) = match label.split_once(':') {
+        Some((issuer, account)) => (Some(issuer.trim().to_owned()), account.trim().to_owned()),
+        None => (None, label.trim().to_owned()),
+    };
+
+    let mut secret = None;
+    let mut issuer = label_issuer;
+    let mut algorithm = TotpAlgorithm::Sha1;
+    let mut digits = DEFAULT_DIGITS;
+    let mut period = DEFAULT_PERIOD;
+    for (key, value) in parsed.query_pairs() {
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => secret = Some(value.into_owned()),
+            "issuer" => issuer = Some(value.into_owned()),
+            "algorithm" => {
+                algorithm = match value.to_ascii_uppercase().as_str() {
+                    "SHA1" => TotpAlgorithm::Sha1,
+                    "SHA256" => TotpAlgorithm::Sha256,
+                    "SHA512" => TotpAlgorithm::Sha512,
+                    _ => return Err(invalid()),
+                }
+            }
+            "digits" => digits = value.parse().map_err(|_| invalid())?,
+            "period" => period = value.parse().map_err(|_| invalid())?,
+            _ => {}
+        }
+    }
+
+    build_secret(
+        &secret.ok_or_else(invalid)?,
+        algorithm,
+        digits,
+        period,
+        issuer.filter(|issuer| !issuer.is_empty()),
+        Some(account).filter(|account| !account.is_empty()),
+    )
+}
+
+pub fn parse_totp_input(input: &str) -> ChacrabResult<TotpSecret> {
+    let trimmed = input.trim();
+    if trimmed.starts_with("otpauth://") {
+        parse_otpauth_uri(trimmed)
+    } else {
+        build_secret(
+            trimmed,
+            TotpAlgorithm::Sha1,
+            DEFAULT_DIGITS,
+            DEFAULT_PERIOD,
+            None,
+            None,
+        )
+    }
+}
+
+pub fn hotp(
+    key: &[u8],
+    counter: u64,
+    digits: u32,
+    algorithm: TotpAlgorithm,
+) -> ChacrabResult<String> {
+    if !(6..=8).contains(&digits) {
+        return Err(ChacrabError::Config(
+            "unsupported otp digit count".to_owned(),
+        ));
+    }
+
+    let message = counter.to_be_bytes();
+    let mut digest = match algorithm {
+        TotpAlgorithm::Sha1 => mac::<Hmac<Sha1>>(key, &message)?,
+        TotpAlgorithm::Sha256 => mac::<Hmac<Sha256>>(key, &message)?,
+        TotpAlgorithm::Sha512 => mac::<Hmac<Sha512>>(key, &message)?,
+    };
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    digest.zeroize();
+
+    let code = binary % 10u32.pow(digits);
+    Ok(format!("{code:0width$}", width = digits as usize))
+}
+
+pub fn totp_at(secret: &TotpSecret, unix_time: u64) -> ChacrabResult<TotpCode> {
+    if secret.period == 0 {
+        return Err(ChacrabError::Config("invalid totp period".to_owned()));
+    }
+
+    let mut key = decode_base32(&secret.secret)?;
+    let code = hotp(
+        &key,
+        unix_time / secret.period,
+        secret.digits,
+        secret.algorithm,
+    );
+    key.zeroize();
+
+    Ok(TotpCode {
+        code: code?,
+        remaining_secs: secret.period - unix_time % secret.period,
+    })
+}
+
+pub fn decode_base32(input: &str) -> ChacrabResult<Vec<u8>> {
+    let invalid = || ChacrabError::Config("invalid base32 secret".to_owned());
+    let mut output = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer = 0u64;
+    let mut bits = 0u32;
+
+    for ch in input.chars() {
+        if ch == '=' || ch == ' ' || ch == '-' {
+            continue;
+        }
+        let upper = ch.to_ascii_uppercase() as u8;
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|candidate| *candidate == upper)
+            .ok_or_else(invalid)?;
+        buffer = (buffer << 5) | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    buffer.zeroize();
+
+    if output.is_empty() {
+        return Err(invalid());
+    }
+    Ok(output)
+}
+
+fn build_secret(
+    secret: &str,
+    algorithm: TotpAlgorithm,
+    digits: u32,
+    period: u64,
+    issuer: Option<String>,
+    account: Option<String>,
+) -> ChacrabResult<TotpSecret> {
+    let normalized = secret
+        .chars()
+        .filter(|ch| !matches!(ch, ' ' | '-' | '='))
+        .map(|ch| ch.to_ascii_uppercase())
+        .collect::<String>();
+    decode_base32(&normalized)?.zeroize();
+    if !(6..=8).contains(&digits) || period == 0 {
+        return Err(ChacrabError::Config("invalid otpauth uri".to_owned()));
+    }
+
+    Ok(TotpSecret {
+        secret: normalized,
+        algorithm,
+        digits,
+        period,
+        issuer,
+        account,
+    })
+}
+
+fn mac<M: Mac + KeyInit>(key: &[u8], message: &[u8]) -> ChacrabResult<Vec<u8>> {
+    let mut mac = <M as Mac>::new_from_slice(key).map_err(|_| ChacrabError::Crypto)?;
+    mac.update(message);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn percent_decode(input: &str) -> String {
+    url::form_urlencoded::parse(format!("label={input}").as_bytes())
+        .next()
+        .map(|(_, value)| value.into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_base32, hotp, parse_otpauth_uri, parse_totp_input, totp_at};
+    use crate::core::models::{TotpAlgorithm, TotpSecret};
+
+    const RFC_SHA1_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+    const RFC_SHA256_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZA";
+    const RFC_SHA512_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQGEZDGNA";
+
+    fn rfc_secret(secret: &str, algorithm: TotpAlgorithm) -> TotpSecret {
+        TotpSecret {
+            secret: secret.to_owned(),
+            algorithm,
+            digits: 8,
+            period: 30,
+            issuer: None,
+            account: None,
+        }
+    }
+
+    #[test]
+    fn hotp_matches_rfc4226_vectors() {
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            let generated = hotp(
+                b"12345678901234567890",
+                counter as u64,
+                6,
+                TotpAlgorithm::Sha1,
+            )
+            .expect("hotp");
+            assert_eq!(&generated, code);
+        }
+    }
+
+    #[test]
+    fn totp_matches_rfc6238_vectors() {
+        let vectors = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        let sha1 = rfc_secret(RFC_SHA1_SECRET, TotpAlgorithm::Sha1);
+        let sha256 = rfc_secret(RFC_SHA256_SECRET, TotpAlgorithm::Sha256);
+        let sha512 = rfc_secret(RFC_SHA512_SECRET, TotpAlgorithm::Sha512);
+
+        for (time, sha1_code, sha256_code, sha512_code) in vectors {
+            assert_eq!(totp_at(&sha1, time).expect("sha1").code, sha1_code);
+            assert_eq!(totp_at(&sha256, time).expect("sha256").code, sha256_code);
+            assert_eq!(totp_at(&sha512, time).expect("sha512").code, sha512_code);
+        }
+    }
+
+    #[test]
+    fn totp_reports_remaining_seconds() {
+        let secret = rfc_secret(RFC_SHA1_SECRET, TotpAlgorithm::Sha1);
+        assert_eq!(totp_at(&secret, 59).expect("totp").remaining_secs, 1);
+        assert_eq!(totp_at(&secret, 60).expect("totp").remaining_secs, 30);
+    }
+
+    #[test]
+    fn parses_otpauth_uri_parameters() {
+        let secret = parse_otpauth_uri(
+            "otpauth://totp/Example%20Co:alice@example.com?secret=jbswy3dpehpk3pxp&issuer=Example&algorithm=SHA256&digits=8&period=60",
+        )
+        .expect("uri");
+        assert_eq!(secret.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(secret.algorithm, TotpAlgorithm::Sha256);
+        assert_eq!(secret.digits, 8);
+        assert_eq!(secret.period, 60);
+        assert_eq!(secret.issuer.as_deref(), Some("Example"));
+        assert_eq!(secret.account.as_deref(), Some("alice@example.com"));
+    }
+
+    #[test]
+    fn raw_secret_uses_defaults() {
+        let secret = parse_totp_input("jbsw y3dp ehpk 3pxp").expect("secret");
+        assert_eq!(secret.secret, "JBSWY3DPEHPK3PXP");
+        assert_eq!(secret.algorithm, TotpAlgorithm::Sha1);
+        assert_eq!(secret.digits, 6);
+        assert_eq!(secret.period, 30);
+    }
+
+    #[test]
+    fn rejects_invalid_otpauth_input() {
+        assert!(parse_otpauth_uri("otpauth://hotp/Example?secret=JBSWY3DPEHPK3PXP").is_err());
+        assert!(parse_otpauth_uri("otpauth://totp/Example").is_err());
+        assert!(
+            parse_otpauth_uri("otpauth://totp/Example?secret=JBSWY3DPEHPK3PXP&digits=12").is_err()
+        );
+        assert!(
+            parse_otpauth_uri("otpauth://totp/Example?secret=JBSWY3DPEHPK3PXP&algorithm=MD5")
+                .is_err()
+        );
+        assert!(parse_totp_input("not base32!").is_err());
+    }
+
+    #[test]
+    fn decodes_base32() {
+        assert_eq!(
+            decode_base32("MZXW6YTBOI======").expect("base32"),
+            b"foobar"
+        );
+    }
+}
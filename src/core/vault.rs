@@ -1,5 +1,5 @@
-use chrono::Utc;
-use secrecy::SecretString;
+use chrono::{DateTime, Duration, Utc};
+use secrecy::{ExposeSecret, SecretString};
 use serde_json::Value;
 use uuid::Uuid;
 use zeroize::Zeroize;
@@ -7,19 +7,45 @@ use zeroize::Zeroize;
 use crate::{
     core::{
         crypto,
-        errors::ChacrabResult,
-        models::{EncryptedPayload, NewVaultItem, VaultItem, VaultItemType},
+        errors::{ChacrabError, ChacrabResult},
+        history::{self, RevisionEntry},
+        models::{
+            AuthRecord, EncryptedPayload, ITEM_FORMAT_VERSION, ItemChanges, ItemMetadata,
+            ItemRevision, LEGACY_ITEM_FORMAT_VERSION, NewVaultItem, VaultItem, VaultItemType,
+            normalize_folder, normalize_tags,
+        },
+        search::{self, SearchHit},
+        totp::{self, TotpCode},
     },
     storage::r#trait::VaultRepository,
 };
 
+pub const DEFAULT_REVISION_RETENTION: usize = 10;
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
 pub struct VaultService<R: VaultRepository> {
     repository: R,
+    revision_retention: usize,
+    trash_retention_days: u32,
 }
 
 impl<R: VaultRepository> VaultService<R> {
     pub fn new(repository: R) -> Self {
-        Self { repository }
+        Self {
+            repository,
+            revision_retention: DEFAULT_REVISION_RETENTION,
+            trash_retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+        }
+    }
+
+    pub fn with_revision_retention(mut self, revision_retention: usize) -> Self {
+        self.revision_retention = revision_retention;
+        self
+    }
+
+    pub fn with_trash_retention_days(mut self, trash_retention_days: u32) -> Self {
+        self.trash_retention_days = trash_retention_days;
+        self
     }
 
     pub async fn add_password(
@@ -65,27 +91,41 @@ impl<R: VaultRepository> VaultService<R> {
         .await
     }
 
-    async fn add_item(
+    pub async fn add_item(
         &self,
         new_item: NewVaultItem,
         key: &[u8; crypto::KEY_SIZE],
     ) -> ChacrabResult<VaultItem> {
+        let id = Uuid::new_v4();
         let mut serialized = serde_json::to_vec(&new_item.payload)?;
-        let encrypted = crypto::encrypt(key, &serialized)?;
+        let encrypted = encrypt_item_payload(key, id, &new_item.r#type, &serialized);
         crypto::zeroize_vec(&mut serialized);
+        let encrypted = encrypted?;
 
         let now = Utc::now();
-        let item = VaultItem {
-            id: Uuid::new_v4(),
+        let mut item = VaultItem {
+            id,
             r#type: new_item.r#type,
             title: new_item.title,
             username: new_item.username,
             url: new_item.url,
             encrypted_data: encrypted.ciphertext,
             nonce: encrypted.nonce,
+            format_version: ITEM_FORMAT_VERSION,
+            encrypted_metadata: None,
+            metadata_nonce: None,
+            folder: None,
+            tags: Vec::new(),
+            favorite: false,
+            deleted_at: None,
+            revision: 1,
+            base_revision: 0,
             created_at: now,
             updated_at: now,
         };
+        if self.metadata_privacy_enabled().await? {
+            seal_metadata(&mut item, key)?;
+        }
         self.repository.upsert_item(&item).await?;
         Ok(item)
     }
@@ -94,18 +134,497 @@ impl<R: VaultRepository> VaultService<R> {
         self.repository.list_items().await
     }
 
+    pub async fn list_revealed(
+        &self,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<Vec<VaultItem>> {
+        let mut items = self.repository.list_items().await?;
+        items.retain(|item| item.deleted_at.is_none());
+        for item in &mut items {
+            reveal_metadata(item, key)?;
+        }
+        Ok(items)
+    }
+
+    pub async fn list_trash(&self, key: &[u8; crypto::KEY_SIZE]) -> ChacrabResult<Vec<VaultItem>> {
+        let mut items = self.repository.list_items().await?;
+        items.retain(|item| item.deleted_at.is_some());
+        for item in &mut items {
+            reveal_metadata(item, key)?;
+        }
+        items.sort_by_key(|item| std::cmp::Reverse(item.deleted_at));
+        Ok(items)
+    }
+
+    pub async fn move_to_trash(&self, id: Uuid) -> ChacrabResult<()> {
+        let mut item = self.repository.get_item(id).await?;
+        if item.deleted_at.is_some() {
+            return Err(ChacrabError::NotFound);
+        }
+        let now = Utc::now();
+        item.deleted_at = Some(now);
+        item.updated_at = now;
+        item.revision += 1;
+        self.repository.upsert_item(&item).await
+    }
+
+    pub async fn restore_from_trash(
+        &self,
+        id: Uuid,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<VaultItem> {
+        let mut item = self.repository.get_item(id).await?;
+        if item.deleted_at.is_none() {
+            return Err(ChacrabError::Config("item is not in trash".to_owned()));
+        }
+        item.deleted_at = None;
+        item.updated_at = Utc::now();
+        item.revision += 1;
+        self.repository.upsert_item(&item).await?;
+        reveal_metadata(&mut item, key)?;
+        Ok(item)
+    }
+
+    pub async fn empty_trash(&self) -> ChacrabResult<usize> {
+        self.purge_trashed_before(Utc::now()).await
+    }
+
+    pub async fn purge_expired_trash(&self) -> ChacrabResult<usize> {
+        if self.trash_retention_days == 0 {
+            return Ok(0);
+        }
+        self.purge_trashed_before(Utc::now() - Duration::days(i64::from(self.trash_retention_days)))
+            .await
+    }
+
+    async fn purge_trashed_before(&self, cutoff: DateTime<Utc>) -> ChacrabResult<usize> {
+        let mut purged = 0;
+        for item in self.repository.list_items().await? {
+            if item
+                .deleted_at
+                .is_some_and(|deleted_at| deleted_at <= cutoff)
+            {
+                self.repository.delete_item(item.id).await?;
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    pub async fn search(
+        &self,
+        query: &str,
+        deep: bool,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<Vec<SearchHit>> {
+        let terms = search::query_terms(query);
+        if terms.is_empty() {
+            return Err(ChacrabError::Config("search query is empty".to_owned()));
+        }
+
+        let mut hits = Vec::new();
+        for item in self.list_revealed(key).await? {
+            let best = if deep {
+                let mut plaintext = decrypt_item_payload(key, &item)?;
+                let payload = serde_json::from_slice::<EncryptedPayload>(&plaintext);
+                plaintext.zeroize();
+                let mut payload = payload?;
+                let best = search::score_item(&terms, &item, Some(&payload));
+                payload.notes.zeroize();
+                for field in &mut payload.custom_fields {
+                    field.value.zeroize();
+                }
+                best
+            } else {
+                search::score_item(&terms, &item, None)
+            };
+            if let Some((score, matched)) = best {
+                hits.push(SearchHit {
+                    item,
+                    score,
+                    matched,
+                });
+            }
+        }
+
+        hits.sort_by(|left, right| {
+            right.score.cmp(&left.score).then_with(|| {
+                left.item
+                    .title
+                    .to_lowercase()
+                    .cmp(&right.item.title.to_lowercase())
+            })
+        });
+        Ok(hits)
+    }
+
+    pub async fn metadata_privacy_enabled(&self) -> ChacrabResult<bool> {
+        Ok(self
+            .repository
+            .get_auth_record()
+            .await?
+            .is_some_and(|auth| auth.metadata_privacy))
+    }
+
+    pub async fn set_metadata_privacy(
+        &self,
+        enabled: bool,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<usize> {
+        let mut auth =
+            self.repository.get_auth_record().await?.ok_or_else(|| {
+                ChacrabError::Config("vault not initialized; run init".to_owned())
+            })?;
+
+        let mut changed = Vec::new();
+        for mut item in self.repository.list_items().await? {
+            match (enabled, item.encrypted_metadata.is_some()) {
+                (true, false) => seal_metadata(&mut item, key)?,
+                (true, true) if item.favorite => {
+                    reveal_metadata(&mut item, key)?;
+                    seal_metadata(&mut item, key)?;
+                }
+                (false, true) => {
+                    reveal_metadata(&mut item, key)?;
+                    item.encrypted_metadata = None;
+                    item.metadata_nonce = None;
+                }
+                _ => continue,
+            }
+            item.revision += 1;
+            changed.push(item);
+        }
+
+        auth.metadata_privacy = enabled;
+        self.repository
+            .replace_auth_and_items(&auth, &changed)
+            .await?;
+        Ok(changed.len())
+    }
+
     pub async fn show_decrypted(
         &self,
         id: Uuid,
         key: &[u8; crypto::KEY_SIZE],
     ) -> ChacrabResult<(VaultItem, Value)> {
-        let item = self.repository.get_item(id).await?;
-        let mut plaintext = crypto::decrypt(key, &item.nonce, &item.encrypted_data)?;
+        let mut item = self.repository.get_item(id).await?;
+        reveal_metadata(&mut item, key)?;
+        let mut plaintext = decrypt_item_payload(key, &item)?;
         let payload: Value = serde_json::from_slice(&plaintext)?;
         plaintext.zeroize();
         Ok((item, payload))
     }
 
+    pub async fn update_item(
+        &self,
+        id: Uuid,
+        changes: ItemChanges,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<VaultItem> {
+        let (mut item, mut payload) = self.show_payload(id, key).await?;
+        let previous = item.clone();
+
+        if let Some(title) = changes.title {
+            item.title = title;
+        }
+        if let Some(username) = changes.username {
+            item.username = username;
+        }
+        if let Some(url) = changes.url {
+            item.url = url;
+        }
+        if let Some(password) = changes.password {
+            if let Some(previous) = payload.password.as_mut() {
+                previous.zeroize();
+            }
+            payload.password = Some(password.expose_secret().to_owned());
+        }
+        if let Some(notes) = changes.notes {
+            if let Some(previous) = payload.notes.as_mut() {
+                previous.zeroize();
+            }
+            payload.notes = notes.map(|notes| notes.expose_secret().to_owned());
+        }
+        if let Some(card) = changes.card {
+            payload.card = Some(card);
+        }
+        if let Some(identity) = changes.identity {
+            payload.identity = Some(identity);
+        }
+        if let Some(ssh_key) = changes.ssh_key {
+            payload.ssh_key = Some(ssh_key);
+        }
+        if let Some(api_key) = changes.api_key {
+            payload.api_key = Some(api_key);
+        }
+        if let Some(custom_fields) = changes.custom_fields {
+            for field in &mut payload.custom_fields {
+                field.value.zeroize();
+            }
+            payload.custom_fields = custom_fields;
+        }
+        if let Some(totp) = changes.totp {
+            if let Some(previous) = payload.totp.as_mut() {
+                previous.secret.zeroize();
+            }
+            payload.totp = totp;
+        }
+
+        let mut serialized = serde_json::to_vec(&payload)?;
+        payload.zeroize();
+        let encrypted = encrypt_item_payload(key, item.id, &item.r#type, &serialized);
+        crypto::zeroize_vec(&mut serialized);
+        let encrypted = encrypted?;
+
+        item.encrypted_data = encrypted.ciphertext;
+        item.nonce = encrypted.nonce;
+        item.format_version = ITEM_FORMAT_VERSION;
+        item.encrypted_metadata = None;
+        item.metadata_nonce = None;
+        item.updated_at = Utc::now();
+        item.revision += 1;
+        if self.metadata_privacy_enabled().await? {
+            seal_metadata(&mut item, key)?;
+        }
+        self.record_revision(&previous, key).await?;
+        self.repository.upsert_item(&item).await?;
+        Ok(item)
+    }
+
+    pub async fn add_tags(
+        &self,
+        id: Uuid,
+        tags: Vec<String>,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<VaultItem> {
+        self.organize(id, key, |item| {
+            item.tags = normalize_tags(std::mem::take(&mut item.tags).into_iter().chain(tags));
+        })
+        .await
+    }
+
+    pub async fn remove_tags(
+        &self,
+        id: Uuid,
+        tags: &[String],
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<VaultItem> {
+        self.organize(id, key, |item| {
+            item.tags.retain(|existing| {
+                !tags
+                    .iter()
+                    .any(|tag| tag.trim().eq_ignore_ascii_case(existing))
+            });
+        })
+        .await
+    }
+
+    pub async fn move_to_folder(
+        &self,
+        id: Uuid,
+        folder: &str,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<VaultItem> {
+        self.organize(id, key, |item| item.folder = normalize_folder(folder))
+            .await
+    }
+
+    pub async fn set_favorite(
+        &self,
+        id: Uuid,
+        favorite: bool,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<VaultItem> {
+        self.organize(id, key, |item| item.favorite = favorite)
+            .await
+    }
+
+    async fn organize(
+        &self,
+        id: Uuid,
+        key: &[u8; crypto::KEY_SIZE],
+        apply: impl FnOnce(&mut VaultItem),
+    ) -> ChacrabResult<VaultItem> {
+        let mut item = self.repository.get_item(id).await?;
+        reveal_metadata(&mut item, key)?;
+        let previous = item.clone();
+        apply(&mut item);
+        item.encrypted_metadata = None;
+        item.metadata_nonce = None;
+        item.updated_at = Utc::now();
+        item.revision += 1;
+
+        let revealed = item.clone();
+        if self.metadata_privacy_enabled().await? {
+            seal_metadata(&mut item, key)?;
+        }
+        self.record_revision(&previous, key).await?;
+        self.repository.upsert_item(&item).await?;
+        Ok(revealed)
+    }
+
+    pub async fn history(
+        &self,
+        id: Uuid,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<Vec<RevisionEntry>> {
+        let mut current = self.repository.get_item(id).await?;
+        reveal_metadata(&mut current, key)?;
+
+        let revisions = self.repository.list_revisions(id).await?;
+        let snapshots = revisions
+            .iter()
+            .map(|revision| open_revision(key, revision))
+            .collect::<ChacrabResult<Vec<_>>>()?;
+
+        let mut entries = Vec::with_capacity(revisions.len());
+        for (index, revision) in revisions.iter().enumerate() {
+            let snapshot = &snapshots[index];
+            let next = snapshots.get(index + 1).unwrap_or(&current);
+            entries.push(RevisionEntry {
+                revision: revision.revision,
+                recorded_at: revision.created_at,
+                item: snapshot.clone(),
+                changes: history::diff_metadata(snapshot, next),
+                secrets_changed: payloads_differ(key, snapshot, next)?,
+            });
+        }
+        Ok(entries)
+    }
+
+    pub async fn restore_revision(
+        &self,
+        id: Uuid,
+        revision: u32,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<VaultItem> {
+        let stored = self
+            .repository
+            .list_revisions(id)
+            .await?
+            .into_iter()
+            .find(|candidate| candidate.revision == revision)
+            .ok_or_else(|| ChacrabError::Config("revision not found".to_owned()))?;
+        let mut restored = open_revision(key, &stored)?;
+
+        let mut current = self.repository.get_item(id).await?;
+        reveal_metadata(&mut current, key)?;
+        restored.created_at = current.created_at;
+        restored.updated_at = Utc::now();
+        restored.revision = current.revision + 1;
+        restored.base_revision = current.base_revision;
+        restored.deleted_at = current.deleted_at;
+
+        let revealed = restored.clone();
+        if self.metadata_privacy_enabled().await? {
+            seal_metadata(&mut restored, key)?;
+        }
+        self.record_revision(&current, key).await?;
+        self.repository.upsert_item(&restored).await?;
+        Ok(revealed)
+    }
+
+    async fn record_revision(
+        &self,
+        previous: &VaultItem,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<()> {
+        if self.revision_retention == 0 {
+            return Ok(());
+        }
+
+        let revision = self
+            .repository
+            .list_revisions(previous.id)
+            .await?
+            .last()
+            .map_or(1, |latest| latest.revision + 1);
+        let mut snapshot = previous.clone();
+        snapshot.encrypted_metadata = None;
+        snapshot.metadata_nonce = None;
+
+        let mut serialized = serde_json::to_vec(&snapshot)?;
+        let encrypted =
+            crypto::encrypt_with_aad(key, &serialized, &revision_aad(previous.id, revision));
+        crypto::zeroize_vec(&mut serialized);
+        let encrypted = encrypted?;
+
+        self.repository
+            .add_revision(&ItemRevision {
+                item_id: previous.id,
+                revision,
+                encrypted_snapshot: encrypted.ciphertext,
+                nonce: encrypted.nonce,
+                created_at: Utc::now(),
+            })
+            .await?;
+        self.repository
+            .prune_revisions(previous.id, self.revision_retention)
+            .await
+    }
+
+    pub async fn totp_code(
+        &self,
+        id: Uuid,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<(VaultItem, TotpCode)> {
+        let (item, mut payload) = self.show_payload(id, key).await?;
+
+        let Some(mut secret) = payload.totp.take() else {
+            return Err(ChacrabError::Config("item has no totp secret".to_owned()));
+        };
+        let now = u64::try_from(Utc::now().timestamp()).map_err(|_| ChacrabError::Crypto)?;
+        let code = totp::totp_at(&secret, now);
+        secret.secret.zeroize();
+        Ok((item, code?))
+    }
+
+    pub async fn change_master_password(
+        &self,
+        current_password: &SecretString,
+        new_password: &SecretString,
+    ) -> ChacrabResult<()> {
+        let auth =
+            self.repository.get_auth_record().await?.ok_or_else(|| {
+                ChacrabError::Config("vault not initialized; run init".to_owned())
+            })?;
+
+        let mut old_master_key = crypto::verify_password_with_params(
+            current_password,
+            &auth.salt,
+            &auth.verifier,
+            auth.argon2_m_cost,
+            auth.argon2_t_cost,
+            auth.argon2_p_cost,
+        )?;
+        let vault_key = unlock_vault_key(&self.repository, &auth, &old_master_key).await;
+        old_master_key.zeroize();
+        let mut vault_key = vault_key?;
+
+        let new_auth = wrap_vault_key(new_password, &vault_key);
+        vault_key.zeroize();
+        self.repository
+            .set_auth_record(&AuthRecord {
+                metadata_privacy: auth.metadata_privacy,
+                revision: auth.revision + 1,
+                ..new_auth?
+            })
+            .await
+    }
+
+    pub async fn show_payload(
+        &self,
+        id: Uuid,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<(VaultItem, EncryptedPayload)> {
+        let mut item = self.repository.get_item(id).await?;
+        reveal_metadata(&mut item, key)?;
+        let mut plaintext = decrypt_item_payload(key, &item)?;
+        let payload = serde_json::from_slice(&plaintext);
+        plaintext.zeroize();
+        Ok((item, payload?))
+    }
+
     pub async fn delete(&self, id: Uuid) -> ChacrabResult<()> {
         self.repository.delete_item(id).await
     }
@@ -114,3 +633,261 @@ impl<R: VaultRepository> VaultService<R> {
         &self.repository
     }
 }
+
+pub fn wrap_vault_key(
+    master_password: &SecretString,
+    vault_key: &[u8; crypto::KEY_SIZE],
+) -> ChacrabResult<AuthRecord> {
+    let (material, mut master_key) = crypto::create_registration_material(master_password)?;
+    let wrapped = crypto::wrap_key(&master_key, vault_key);
+    master_key.zeroize();
+    let wrapped = wrapped?;
+
+    Ok(AuthRecord {
+        salt: material.salt_b64,
+        verifier: material.verifier,
+        argon2_m_cost: crypto::ARGON2_M_COST,
+        argon2_t_cost: crypto::ARGON2_T_COST,
+        argon2_p_cost: crypto::ARGON2_P_COST,
+        wrapped_vault_key: Some(wrapped.ciphertext),
+        wrapped_vault_key_nonce: Some(wrapped.nonce),
+        metadata_privacy: false,
+        revision: 0,
+    })
+}
+
+fn item_aad(id: Uuid, item_type: &VaultItemType, format_version: u32) -> Vec<u8> {
+    format!("chacrab:item:v{format_version}:{id}:{}", item_type.as_str()).into_bytes()
+}
+
+fn metadata_aad(id: Uuid, item_type: &VaultItemType) -> Vec<u8> {
+    format!(
+        "chacrab:item-metadata:v{ITEM_FORMAT_VERSION}:{id}:{}",
+        item_type.as_str()
+    )
+    .into_bytes()
+}
+
+fn revision_aad(id: Uuid, revision: u32) -> Vec<u8> {
+    format!("chacrab:item-revision:v1:{id}:{revision}").into_bytes()
+}
+
+fn open_revision(
+    key: &[u8; crypto::KEY_SIZE],
+    revision: &ItemRevision,
+) -> ChacrabResult<VaultItem> {
+    let mut plaintext = crypto::decrypt_with_aad(
+        key,
+        &revision.nonce,
+        &revision.encrypted_snapshot,
+        &revision_aad(revision.item_id, revision.revision),
+    )?;
+    let snapshot: ChacrabResult<VaultItem> = serde_json::from_slice(&plaintext).map_err(Into::into);
+    plaintext.zeroize();
+    let snapshot = snapshot?;
+    if snapshot.id != revision.item_id {
+        return Err(ChacrabError::Crypto);
+    }
+    Ok(snapshot)
+}
+
+fn payloads_differ(
+    key: &[u8; crypto::KEY_SIZE],
+    before: &VaultItem,
+    after: &VaultItem,
+) -> ChacrabResult<bool> {
+    if before.encrypted_data == after.encrypted_data {
+        return Ok(false);
+    }
+    let mut before = payload_value(key, before)?;
+    let mut after = match payload_value(key, after) {
+        Ok(after) => after,
+        Err(error) => {
+            zeroize_value(&mut before);
+            return Err(error);
+        }
+    };
+    let differ = before != after;
+    zeroize_value(&mut before);
+    zeroize_value(&mut after);
+    Ok(differ)
+}
+
+fn payload_value(key: &[u8; crypto::KEY_SIZE], item: &VaultItem) -> ChacrabResult<Value> {
+    let mut plaintext = decrypt_item_payload(key, item)?;
+    let value = serde_json::from_slice(&plaintext);
+    plaintext.zeroize();
+    Ok(value?)
+}
+
+fn zeroize_value(value: &mut Value) {
+    match value {
+        Value::String(text) => text.zeroize(),
+        Value::Array(values) => values.iter_mut().for_each(zeroize_value),
+        Value::Object(map) => map.values_mut().for_each(zeroize_value),
+        _ => {}
+    }
+}
+
+fn seal_metadata(item: &mut VaultItem, key: &[u8; crypto::KEY_SIZE]) -> ChacrabResult<()> {
+    let metadata = ItemMetadata {
+        title: std::mem::take(&mut item.title),
+        username: item.username.take(),
+        url: item.url.take(),
+        folder: item.folder.take(),
+        tags: std::mem::take(&mut item.tags),
+        favorite: std::mem::take(&mut item.favorite),
+    };
+    let mut serialized = serde_json::to_vec(&metadata)?;
+    let encrypted =
+        crypto::encrypt_with_aad(key, &serialized, &metadata_aad(item.id, &item.r#type));
+    crypto::zeroize_vec(&mut serialized);
+    let encrypted = encrypted?;
+    item.encrypted_metadata = Some(encrypted.ciphertext);
+    item.metadata_nonce = Some(encrypted.nonce);
+    Ok(())
+}
+
+pub fn reveal_metadata(item: &mut VaultItem, key: &[u8; crypto::KEY_SIZE]) -> ChacrabResult<()> {
+    let (Some(ciphertext), Some(nonce)) = (&item.encrypted_metadata, &item.metadata_nonce) else {
+        return Ok(());
+    };
+    let mut plaintext =
+        crypto::decrypt_with_aad(key, nonce, ciphertext, &metadata_aad(item.id, &item.r#type))?;
+    let metadata: ChacrabResult<ItemMetadata> =
+        serde_json::from_slice(&plaintext).map_err(Into::into);
+    plaintext.zeroize();
+    let metadata = metadata?;
+    item.title = metadata.title;
+    item.username = metadata.username;
+    item.url = metadata.url;
+    item.folder = metadata.folder;
+    item.tags = metadata.tags;
+    // Items sealed before favorites were private keep the flag in the plaintext column.
+    item.favorite |= metadata.favorite;
+    Ok(())
+}
+
+pub fn duplicate_item(item: &VaultItem, key: &[u8; crypto::KEY_SIZE]) -> ChacrabResult<VaultItem> {
+    let mut copy = item.clone();
+    reveal_metadata(&mut copy, key)?;
+    let sealed = copy.encrypted_metadata.take().is_some();
+    copy.metadata_nonce = None;
+
+    copy.id = Uuid::new_v4();
+    let mut plaintext = decrypt_item_payload(key, item)?;
+    let encrypted = encrypt_item_payload(key, copy.id, &copy.r#type, &plaintext);
+    plaintext.zeroize();
+    let encrypted = encrypted?;
+    copy.encrypted_data = encrypted.ciphertext;
+    copy.nonce = encrypted.nonce;
+    copy.format_version = ITEM_FORMAT_VERSION;
+    copy.title = format!("{} (conflict copy)", copy.title);
+    copy.revision = 1;
+    copy.base_revision = 0;
+    copy.updated_at = Utc::now();
+    if sealed {
+        seal_metadata(&mut copy, key)?;
+    }
+    Ok(copy)
+}
+
+fn encrypt_item_payload(
+    key: &[u8; crypto::KEY_SIZE],
+    id: Uuid,
+    item_type: &VaultItemType,
+    plaintext: &[u8],
+) -> ChacrabResult<crypto::CipherBlob> {
+    crypto::encrypt_with_aad(
+        key,
+        plaintext,
+        &item_aad(id, item_type, ITEM_FORMAT_VERSION),
+    )
+}
+
+pub fn decrypt_item_payload(
+    key: &[u8; crypto::KEY_SIZE],
+    item: &VaultItem,
+) -> ChacrabResult<Vec<u8>> {
+    match item.format_version {
+        LEGACY_ITEM_FORMAT_VERSION => crypto::decrypt(key, &item.nonce, &item.encrypted_data),
+        ITEM_FORMAT_VERSION => crypto::decrypt_with_aad(
+            key,
+            &item.nonce,
+            &item.encrypted_data,
+            &item_aad(item.id, &item.r#type, item.format_version),
+        ),
+        _ => Err(ChacrabError::Config(
+            "unsupported item format version".to_owned(),
+        )),
+    }
+}
+
+pub async fn unlock_vault_key<R: VaultRepository>(
+    repository: &R,
+    auth: &AuthRecord,
+    master_key: &[u8; crypto::KEY_SIZE],
+) -> ChacrabResult<[u8; crypto::KEY_SIZE]> {
+    if let (Some(wrapped), Some(nonce)) = (&auth.wrapped_vault_key, &auth.wrapped_vault_key_nonce) {
+        let mut vault_key = crypto::unwrap_key(master_key, nonce, wrapped)?;
+        if let Err(err) = upgrade_item_format(repository, auth, &vault_key).await {
+            vault_key.zeroize();
+            return Err(err);
+        }
+        return Ok(vault_key);
+    }
+
+    let mut vault_key = crypto::generate_vault_key();
+    let wrapped = crypto::wrap_key(master_key, &vault_key)?;
+    let upgraded = AuthRecord {
+        wrapped_vault_key: Some(wrapped.ciphertext),
+        wrapped_vault_key_nonce: Some(wrapped.nonce),
+        ..auth.clone()
+    };
+    let items = repository.list_items().await?;
+    if let Err(err) = reencrypt_items(repository, &upgraded, items, master_key, &vault_key).await {
+        vault_key.zeroize();
+        return Err(err);
+    }
+    Ok(vault_key)
+}
+
+async fn upgrade_item_format<R: VaultRepository>(
+    repository: &R,
+    auth: &AuthRecord,
+    vault_key: &[u8; crypto::KEY_SIZE],
+) -> ChacrabResult<()> {
+    if !repository.has_legacy_items().await? {
+        return Ok(());
+    }
+    let outdated = repository
+        .list_items()
+        .await?
+        .into_iter()
+        .filter(|item| item.format_version < ITEM_FORMAT_VERSION)
+        .collect::<Vec<_>>();
+    if !outdated.is_empty() {
+        reencrypt_items(repository, auth, outdated, vault_key, vault_key).await?;
+    }
+    repository.clear_legacy_items().await
+}
+
+async fn reencrypt_items<R: VaultRepository>(
+    repository: &R,
+    auth: &AuthRecord,
+    mut items: Vec<VaultItem>,
+    old_key: &[u8; crypto::KEY_SIZE],
+    new_key: &[u8; crypto::KEY_SIZE],
+) -> ChacrabResult<()> {
+    for item in &mut items {
+        let mut plaintext = decrypt_item_payload(old_key, item)?;
+        let encrypted = encrypt_item_payload(new_key, item.id, &item.r#type, &plaintext);
+        plaintext.zeroize();
+        let encrypted = encrypted?;
+        item.encrypted_data = encrypted.ciphertext;
+        item.nonce = encrypted.nonce;
+        item.format_version = ITEM_FORMAT_VERSION;
+        item.revision += 1;
+    }
+    repository.replace_auth_and_items(auth, &items).await
+}
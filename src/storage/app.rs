@@ -4,11 +4,13 @@ use uuid::Uuid;
 use crate::{
     core::{
         errors::{ChacrabError, ChacrabResult},
-        models::{AuthRecord, VaultItem},
+        models::{
+            AuthRecord, ChangeSet, ItemRevision, SyncState, Tombstone, VaultItem, VaultManifest,
+        },
     },
     storage::{
-        mongo::MongoRepository, postgres::PostgresRepository, sqlite::SqliteRepository,
-        r#trait::VaultRepository,
+        bundle::BundleRepository, mongo::MongoRepository, postgres::PostgresRepository,
+        remote::RemoteRepository, sqlite::SqliteRepository, r#trait::VaultRepository,
     },
 };
 
@@ -17,6 +19,8 @@ pub enum AppRepository {
     Sqlite(SqliteRepository),
     Postgres(PostgresRepository),
     Mongo(MongoRepository),
+    Remote(RemoteRepository),
+    Bundle(BundleRepository),
 }
 
 impl AppRepository {
@@ -27,9 +31,25 @@ impl AppRepository {
                 PostgresRepository::connect(database_url).await?,
             )),
             "mongo" => Ok(Self::Mongo(MongoRepository::connect(database_url).await?)),
+            "http" => Ok(Self::Remote(RemoteRepository::connect(database_url)?)),
             other => Err(ChacrabError::UnsupportedBackend(other.to_owned())),
         }
     }
+
+    pub async fn open_existing(backend: &str, database_url: &str) -> ChacrabResult<Self> {
+        let repo = match backend {
+            "sqlite" => SqliteRepository::connect_read_only(database_url)
+                .await?
+                .map(Self::Sqlite),
+            _ => Some(Self::connect(backend, database_url).await?),
+        };
+        match repo {
+            Some(repo) if repo.is_initialized().await? => Ok(repo),
+            _ => Err(ChacrabError::Config(
+                "sync remote is not initialised".to_owned(),
+            )),
+        }
+    }
 }
 
 #[async_trait]
@@ -39,6 +59,38 @@ impl VaultRepository for AppRepository {
             AppRepository::Sqlite(repo) => repo.init().await,
             AppRepository::Postgres(repo) => repo.init().await,
             AppRepository::Mongo(repo) => repo.init().await,
+            AppRepository::Remote(repo) => repo.init().await,
+            AppRepository::Bundle(repo) => repo.init().await,
+        }
+    }
+
+    async fn is_initialized(&self) -> ChacrabResult<bool> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.is_initialized().await,
+            AppRepository::Postgres(repo) => repo.is_initialized().await,
+            AppRepository::Mongo(repo) => repo.is_initialized().await,
+            AppRepository::Remote(repo) => repo.is_initialized().await,
+            AppRepository::Bundle(repo) => repo.is_initialized().await,
+        }
+    }
+
+    async fn has_legacy_items(&self) -> ChacrabResult<bool> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.has_legacy_items().await,
+            AppRepository::Postgres(repo) => repo.has_legacy_items().await,
+            AppRepository::Mongo(repo) => repo.has_legacy_items().await,
+            AppRepository::Remote(repo) => repo.has_legacy_items().await,
+            AppRepository::Bundle(repo) => repo.has_legacy_items().await,
+        }
+    }
+
+    async fn clear_legacy_items(&self) -> ChacrabResult<()> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.clear_legacy_items().await,
+            AppRepository::Postgres(repo) => repo.clear_legacy_items().await,
+            AppRepository::Mongo(repo) => repo.clear_legacy_items().await,
+            AppRepository::Remote(repo) => repo.clear_legacy_items().await,
+            AppRepository::Bundle(repo) => repo.clear_legacy_items().await,
         }
     }
 
@@ -47,6 +99,8 @@ impl VaultRepository for AppRepository {
             AppRepository::Sqlite(repo) => repo.upsert_item(item).await,
             AppRepository::Postgres(repo) => repo.upsert_item(item).await,
             AppRepository::Mongo(repo) => repo.upsert_item(item).await,
+            AppRepository::Remote(repo) => repo.upsert_item(item).await,
+            AppRepository::Bundle(repo) => repo.upsert_item(item).await,
         }
     }
 
@@ -55,6 +109,8 @@ impl VaultRepository for AppRepository {
             AppRepository::Sqlite(repo) => repo.list_items().await,
             AppRepository::Postgres(repo) => repo.list_items().await,
             AppRepository::Mongo(repo) => repo.list_items().await,
+            AppRepository::Remote(repo) => repo.list_items().await,
+            AppRepository::Bundle(repo) => repo.list_items().await,
         }
     }
 
@@ -63,6 +119,8 @@ impl VaultRepository for AppRepository {
             AppRepository::Sqlite(repo) => repo.get_item(id).await,
             AppRepository::Postgres(repo) => repo.get_item(id).await,
             AppRepository::Mongo(repo) => repo.get_item(id).await,
+            AppRepository::Remote(repo) => repo.get_item(id).await,
+            AppRepository::Bundle(repo) => repo.get_item(id).await,
         }
     }
 
@@ -71,6 +129,108 @@ impl VaultRepository for AppRepository {
             AppRepository::Sqlite(repo) => repo.delete_item(id).await,
             AppRepository::Postgres(repo) => repo.delete_item(id).await,
             AppRepository::Mongo(repo) => repo.delete_item(id).await,
+            AppRepository::Remote(repo) => repo.delete_item(id).await,
+            AppRepository::Bundle(repo) => repo.delete_item(id).await,
+        }
+    }
+
+    async fn add_revision(&self, revision: &ItemRevision) -> ChacrabResult<()> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.add_revision(revision).await,
+            AppRepository::Postgres(repo) => repo.add_revision(revision).await,
+            AppRepository::Mongo(repo) => repo.add_revision(revision).await,
+            AppRepository::Remote(repo) => repo.add_revision(revision).await,
+            AppRepository::Bundle(repo) => repo.add_revision(revision).await,
+        }
+    }
+
+    async fn list_revisions(&self, item_id: Uuid) -> ChacrabResult<Vec<ItemRevision>> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.list_revisions(item_id).await,
+            AppRepository::Postgres(repo) => repo.list_revisions(item_id).await,
+            AppRepository::Mongo(repo) => repo.list_revisions(item_id).await,
+            AppRepository::Remote(repo) => repo.list_revisions(item_id).await,
+            AppRepository::Bundle(repo) => repo.list_revisions(item_id).await,
+        }
+    }
+
+    async fn prune_revisions(&self, item_id: Uuid, keep: usize) -> ChacrabResult<()> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.prune_revisions(item_id, keep).await,
+            AppRepository::Postgres(repo) => repo.prune_revisions(item_id, keep).await,
+            AppRepository::Mongo(repo) => repo.prune_revisions(item_id, keep).await,
+            AppRepository::Remote(repo) => repo.prune_revisions(item_id, keep).await,
+            AppRepository::Bundle(repo) => repo.prune_revisions(item_id, keep).await,
+        }
+    }
+
+    async fn list_tombstones(&self) -> ChacrabResult<Vec<Tombstone>> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.list_tombstones().await,
+            AppRepository::Postgres(repo) => repo.list_tombstones().await,
+            AppRepository::Mongo(repo) => repo.list_tombstones().await,
+            AppRepository::Remote(repo) => repo.list_tombstones().await,
+            AppRepository::Bundle(repo) => repo.list_tombstones().await,
+        }
+    }
+
+    async fn record_tombstone(&self, tombstone: &Tombstone) -> ChacrabResult<()> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.record_tombstone(tombstone).await,
+            AppRepository::Postgres(repo) => repo.record_tombstone(tombstone).await,
+            AppRepository::Mongo(repo) => repo.record_tombstone(tombstone).await,
+            AppRepository::Remote(repo) => repo.record_tombstone(tombstone).await,
+            AppRepository::Bundle(repo) => repo.record_tombstone(tombstone).await,
+        }
+    }
+
+    async fn changes_since(&self, cursor: u64) -> ChacrabResult<ChangeSet> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.changes_since(cursor).await,
+            AppRepository::Postgres(repo) => repo.changes_since(cursor).await,
+            AppRepository::Mongo(repo) => repo.changes_since(cursor).await,
+            AppRepository::Remote(repo) => repo.changes_since(cursor).await,
+            AppRepository::Bundle(repo) => repo.changes_since(cursor).await,
+        }
+    }
+
+    async fn get_manifest(&self) -> ChacrabResult<Option<VaultManifest>> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.get_manifest().await,
+            AppRepository::Postgres(repo) => repo.get_manifest().await,
+            AppRepository::Mongo(repo) => repo.get_manifest().await,
+            AppRepository::Remote(repo) => repo.get_manifest().await,
+            AppRepository::Bundle(repo) => repo.get_manifest().await,
+        }
+    }
+
+    async fn set_manifest(&self, manifest: &VaultManifest) -> ChacrabResult<()> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.set_manifest(manifest).await,
+            AppRepository::Postgres(repo) => repo.set_manifest(manifest).await,
+            AppRepository::Mongo(repo) => repo.set_manifest(manifest).await,
+            AppRepository::Remote(repo) => repo.set_manifest(manifest).await,
+            AppRepository::Bundle(repo) => repo.set_manifest(manifest).await,
+        }
+    }
+
+    async fn get_sync_state(&self, remote: &str) -> ChacrabResult<Option<SyncState>> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.get_sync_state(remote).await,
+            AppRepository::Postgres(repo) => repo.get_sync_state(remote).await,
+            AppRepository::Mongo(repo) => repo.get_sync_state(remote).await,
+            AppRepository::Remote(repo) => repo.get_sync_state(remote).await,
+            AppRepository::Bundle(repo) => repo.get_sync_state(remote).await,
+        }
+    }
+
+    async fn set_sync_state(&self, state: &SyncState) -> ChacrabResult<()> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.set_sync_state(state).await,
+            AppRepository::Postgres(repo) => repo.set_sync_state(state).await,
+            AppRepository::Mongo(repo) => repo.set_sync_state(state).await,
+            AppRepository::Remote(repo) => repo.set_sync_state(state).await,
+            AppRepository::Bundle(repo) => repo.set_sync_state(state).await,
         }
     }
 
@@ -79,6 +239,8 @@ impl VaultRepository for AppRepository {
             AppRepository::Sqlite(repo) => repo.get_auth_record().await,
             AppRepository::Postgres(repo) => repo.get_auth_record().await,
             AppRepository::Mongo(repo) => repo.get_auth_record().await,
+            AppRepository::Remote(repo) => repo.get_auth_record().await,
+            AppRepository::Bundle(repo) => repo.get_auth_record().await,
         }
     }
 
@@ -87,6 +249,22 @@ impl VaultRepository for AppRepository {
             AppRepository::Sqlite(repo) => repo.set_auth_record(auth).await,
             AppRepository::Postgres(repo) => repo.set_auth_record(auth).await,
             AppRepository::Mongo(repo) => repo.set_auth_record(auth).await,
+            AppRepository::Remote(repo) => repo.set_auth_record(auth).await,
+            AppRepository::Bundle(repo) => repo.set_auth_record(auth).await,
+        }
+    }
+
+    async fn replace_auth_and_items(
+        &self,
+        auth: &AuthRecord,
+        items: &[VaultItem],
+    ) -> ChacrabResult<()> {
+        match self {
+            AppRepository::Sqlite(repo) => repo.replace_auth_and_items(auth, items).await,
+            AppRepository::Postgres(repo) => repo.replace_auth_and_items(auth, items).await,
+            AppRepository::Mongo(repo) => repo.replace_auth_and_items(auth, items).await,
+            AppRepository::Remote(repo) => repo.replace_auth_and_items(auth, items).await,
+            AppRepository::Bundle(repo) => repo.replace_auth_and_items(auth, items).await,
         }
     }
 }
@@ -0,0 +1,197 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::{
+    core::{
+        errors::{ChacrabError, ChacrabResult},
+        models::{
+            AuthRecord, ChangeSet, ItemRevision, SyncState, Tombstone, VaultItem, VaultManifest,
+        },
+    },
+    storage::r#trait::VaultRepository,
+    sync::bundle::ChangeBundle,
+};
+
+#[derive(Clone)]
+pub struct BundleRepository {
+    contents: Arc<Mutex<BundleContents>>,
+}
+
+struct BundleContents {
+    sequence: u64,
+    auth: Option<AuthRecord>,
+    manifest: Option<VaultManifest>,
+    items: BTreeMap<Uuid, VaultItem>,
+    tombstones: BTreeMap<Uuid, Tombstone>,
+    revisions: Vec<ItemRevision>,
+}
+
+impl BundleRepository {
+    pub fn new(bundle: ChangeBundle) -> Self {
+        Self {
+            contents: Arc::new(Mutex::new(BundleContents {
+                sequence: bundle.sequence,
+                auth: Some(bundle.auth),
+                manifest: Some(bundle.manifest),
+                items: bundle
+                    .items
+                    .into_iter()
+                    .map(|item| (item.id, item))
+                    .collect(),
+                tombstones: bundle
+                    .tombstones
+                    .into_iter()
+                    .map(|tombstone| (tombstone.id, tombstone))
+                    .collect(),
+                revisions: Vec::new(),
+            })),
+        }
+    }
+
+    fn contents(&self) -> ChacrabResult<MutexGuard<'_, BundleContents>> {
+        self.contents.lock().map_err(|_| ChacrabError::Storage)
+    }
+}
+
+#[async_trait]
+impl VaultRepository for BundleRepository {
+    async fn init(&self) -> ChacrabResult<()> {
+        Ok(())
+    }
+
+    async fn is_initialized(&self) -> ChacrabResult<bool> {
+        Ok(true)
+    }
+
+    async fn has_legacy_items(&self) -> ChacrabResult<bool> {
+        Ok(true)
+    }
+
+    async fn clear_legacy_items(&self) -> ChacrabResult<()> {
+        Ok(())
+    }
+
+    async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()> {
+        self.contents()?.items.insert(item.id, item.clone());
+        Ok(())
+    }
+
+    async fn list_items(&self) -> ChacrabResult<Vec<VaultItem>> {
+        Ok(self.contents()?.items.values().cloned().collect())
+    }
+
+    async fn get_item(&self, id: Uuid) -> ChacrabResult<VaultItem> {
+        self.contents()?
+            .items
+            .get(&id)
+            .cloned()
+            .ok_or(ChacrabError::NotFound)
+    }
+
+    async fn delete_item(&self, id: Uuid) -> ChacrabResult<()> {
+        self.contents()?
+            .items
+            .remove(&id)
+            .map(|_| ())
+            .ok_or(ChacrabError::NotFound)
+    }
+
+    async fn add_revision(&self, revision: &ItemRevision) -> ChacrabResult<()> {
+        self.contents()?.revisions.push(revision.clone());
+        Ok(())
+    }
+
+    async fn list_revisions(&self, item_id: Uuid) -> ChacrabResult<Vec<ItemRevision>> {
+        Ok(self
+            .contents()?
+            .revisions
+            .iter()
+            .filter(|revision| revision.item_id == item_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn prune_revisions(&self, item_id: Uuid, keep: usize) -> ChacrabResult<()> {
+        let mut contents = self.contents()?;
+        let stored = contents
+            .revisions
+            .iter()
+            .filter(|revision| revision.item_id == item_id)
+            .count();
+        let mut excess = stored.saturating_sub(keep);
+        contents.revisions.retain(|revision| {
+            let prune = excess > 0 && revision.item_id == item_id;
+            excess -= usize::from(prune);
+            !prune
+        });
+        Ok(())
+    }
+
+    async fn list_tombstones(&self) -> ChacrabResult<Vec<Tombstone>> {
+        Ok(self.contents()?.tombstones.values().copied().collect())
+    }
+
+    async fn record_tombstone(&self, tombstone: &Tombstone) -> ChacrabResult<()> {
+        let mut contents = self.contents()?;
+        contents.items.remove(&tombstone.id);
+        contents.tombstones.insert(tombstone.id, *tombstone);
+        Ok(())
+    }
+
+    async fn changes_since(&self, cursor: u64) -> ChacrabResult<ChangeSet> {
+        let contents = self.contents()?;
+        if cursor >= contents.sequence {
+            return Ok(ChangeSet {
+                cursor: contents.sequence,
+                ..ChangeSet::default()
+            });
+        }
+        Ok(ChangeSet {
+            items: contents.items.values().cloned().collect(),
+            tombstones: contents.tombstones.values().copied().collect(),
+            cursor: contents.sequence,
+        })
+    }
+
+    async fn get_manifest(&self) -> ChacrabResult<Option<VaultManifest>> {
+        Ok(self.contents()?.manifest.clone())
+    }
+
+    async fn set_manifest(&self, manifest: &VaultManifest) -> ChacrabResult<()> {
+        self.contents()?.manifest = Some(manifest.clone());
+        Ok(())
+    }
+
+    async fn get_sync_state(&self, _: &str) -> ChacrabResult<Option<SyncState>> {
+        Err(ChacrabError::UnsupportedBackend("bundle".to_owned()))
+    }
+
+    async fn set_sync_state(&self, _: &SyncState) -> ChacrabResult<()> {
+        Err(ChacrabError::UnsupportedBackend("bundle".to_owned()))
+    }
+
+    async fn get_auth_record(&self) -> ChacrabResult<Option<AuthRecord>> {
+        Ok(self.contents()?.auth.clone())
+    }
+
+    async fn set_auth_record(&self, auth: &AuthRecord) -> ChacrabResult<()> {
+        self.contents()?.auth = Some(auth.clone());
+        Ok(())
+    }
+
+    async fn replace_auth_and_items(
+        &self,
+        auth: &AuthRecord,
+        items: &[VaultItem],
+    ) -> ChacrabResult<()> {
+        let mut contents = self.contents()?;
+        contents.auth = Some(auth.clone());
+        contents.items = items.iter().map(|item| (item.id, item.clone())).collect();
+        Ok(())
+    }
+}
@@ -1,5 +1,28 @@
 pub mod app;
+pub mod bundle;
 pub mod mongo;
 pub mod postgres;
+pub mod remote;
 pub mod sqlite;
 pub mod r#trait;
+
+use crate::core::{
+    errors::{ChacrabError, ChacrabResult},
+    models::VaultItem,
+};
+
+pub(crate) fn readable_items(
+    items: impl IntoIterator<Item = ChacrabResult<VaultItem>>,
+) -> ChacrabResult<Vec<VaultItem>> {
+    let mut readable = Vec::new();
+    for item in items {
+        match item {
+            Ok(item) => readable.push(item),
+            Err(ChacrabError::UnsupportedItemType(item_type)) => {
+                tracing::warn!(%item_type, "skipping vault item of an unsupported type");
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(readable)
+}
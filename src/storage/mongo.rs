@@ -4,23 +4,32 @@ use futures_util::TryStreamExt;
 use mongodb::{
     Client, Collection, IndexModel,
     bson::{self, Binary, Bson, DateTime as BsonDateTime, Document, doc},
-    options::{ClientOptions, IndexOptions},
+    options::{ClientOptions, IndexOptions, ReturnDocument},
 };
 use uuid::Uuid;
 
 use crate::core::{
     errors::{ChacrabError, ChacrabResult},
-    models::{AuthRecord, VaultItem, VaultItemType},
+    models::{
+        AuthRecord, ChangeSet, ITEM_FORMAT_VERSION, ItemRevision, LEGACY_ITEM_FORMAT_VERSION,
+        SyncState, Tombstone, VaultItem, VaultItemType, VaultManifest,
+    },
 };
-use crate::storage::r#trait::VaultRepository;
+use crate::storage::{self, r#trait::VaultRepository};
 
-const SCHEMA_VERSION: i64 = 1;
+const SCHEMA_VERSION: i64 = 15;
+const PENDING_REKEY_ID: &str = "pending_rekey";
+const SYNC_MANIFEST_ID: &str = "sync_manifest";
+const CHANGE_SEQUENCE_ID: &str = "change_sequence";
 
 #[derive(Clone)]
 pub struct MongoRepository {
     vault_items: Collection<Document>,
+    item_revisions: Collection<Document>,
+    tombstones: Collection<Document>,
     auth: Collection<Document>,
     metadata: Collection<Document>,
+    sync_state: Collection<Document>,
 }
 
 impl MongoRepository {
@@ -37,24 +46,28 @@ impl MongoRepository {
 
         Ok(Self {
             vault_items: database.collection("vault_items"),
+            item_revisions: database.collection("item_revisions"),
+            tombstones: database.collection("tombstones"),
             auth: database.collection("auth"),
             metadata: database.collection("metadata"),
+            sync_state: database.collection("sync_state"),
         })
     }
 
     fn parse_item_type(value: &str) -> ChacrabResult<VaultItemType> {
-        match value {
-            "password" => Ok(VaultItemType::Password),
-            "note" => Ok(VaultItemType::Note),
-            _ => Err(ChacrabError::Storage),
-        }
+        value.parse()
     }
 
     fn item_type_to_str(item_type: &VaultItemType) -> &'static str {
-        match item_type {
-            VaultItemType::Password => "password",
-            VaultItemType::Note => "note",
-        }
+        item_type.as_str()
+    }
+
+    fn optional_nonce(document: &Document, key: &str) -> ChacrabResult<Option<[u8; 12]>> {
+        document
+            .get_binary_generic(key)
+            .ok()
+            .map(|bytes| <[u8; 12]>::try_from(bytes.as_slice()).map_err(|_| ChacrabError::Storage))
+            .transpose()
     }
 
     fn to_document(item: &VaultItem) -> Document {
@@ -66,6 +79,21 @@ impl MongoRepository {
             "url": item.url.clone(),
             "encrypted_data": Bson::Binary(Binary { subtype: bson::spec::BinarySubtype::Generic, bytes: item.encrypted_data.clone() }),
             "nonce": Bson::Binary(Binary { subtype: bson::spec::BinarySubtype::Generic, bytes: item.nonce.to_vec() }),
+            "format_version": item.format_version as i32,
+            "encrypted_metadata": item.encrypted_metadata.clone().map(|bytes| {
+                Bson::Binary(Binary { subtype: bson::spec::BinarySubtype::Generic, bytes })
+            }),
+            "metadata_nonce": item.metadata_nonce.map(|nonce| {
+                Bson::Binary(Binary { subtype: bson::spec::BinarySubtype::Generic, bytes: nonce.to_vec() })
+            }),
+            "folder": item.folder.clone(),
+            "tags": item.tags.clone(),
+            "favorite": item.favorite,
+            "deleted_at": item.deleted_at.map(|deleted_at| {
+                Bson::DateTime(BsonDateTime::from_millis(deleted_at.timestamp_millis()))
+            }),
+            "revision": item.revision as i64,
+            "base_revision": item.base_revision as i64,
             "created_at": Bson::DateTime(BsonDateTime::from_millis(item.created_at.timestamp_millis())),
             "updated_at": Bson::DateTime(BsonDateTime::from_millis(item.updated_at.timestamp_millis())),
         }
@@ -111,6 +139,36 @@ impl MongoRepository {
             url: document.get_str("url").ok().map(str::to_owned),
             encrypted_data,
             nonce,
+            format_version: document
+                .get_i32("format_version")
+                .map(|version| version as u32)
+                .unwrap_or(LEGACY_ITEM_FORMAT_VERSION),
+            encrypted_metadata: document
+                .get_binary_generic("encrypted_metadata")
+                .ok()
+                .cloned(),
+            metadata_nonce: Self::optional_nonce(&document, "metadata_nonce")?,
+            folder: document.get_str("folder").ok().map(str::to_owned),
+            tags: document
+                .get_array("tags")
+                .map(|tags| {
+                    tags.iter()
+                        .filter_map(|tag| tag.as_str().map(str::to_owned))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            favorite: document.get_bool("favorite").unwrap_or(false),
+            deleted_at: document
+                .get_datetime("deleted_at")
+                .ok()
+                .map(|deleted_at| {
+                    Utc.timestamp_millis_opt(deleted_at.timestamp_millis())
+                        .single()
+                        .ok_or(ChacrabError::Storage)
+                })
+                .transpose()?,
+            revision: document.get_i64("revision").unwrap_or_default() as u64,
+            base_revision: document.get_i64("base_revision").unwrap_or_default() as u64,
             created_at: Utc
                 .timestamp_millis_opt(created_at)
                 .single()
@@ -121,6 +179,177 @@ impl MongoRepository {
                 .ok_or(ChacrabError::Storage)?,
         })
     }
+
+    fn tombstone_from_document(document: &Document) -> ChacrabResult<Tombstone> {
+        let id_text = document.get_str("id").map_err(|_| ChacrabError::Storage)?;
+        let deleted_at = document
+            .get_datetime("deleted_at")
+            .map_err(|_| ChacrabError::Storage)?
+            .timestamp_millis();
+        Ok(Tombstone {
+            id: Uuid::parse_str(id_text).map_err(|_| ChacrabError::Storage)?,
+            deleted_at: Utc
+                .timestamp_millis_opt(deleted_at)
+                .single()
+                .ok_or(ChacrabError::Storage)?,
+            revision: document.get_i64("revision").unwrap_or_default() as u64,
+        })
+    }
+
+    fn revision_document(revision: &ItemRevision) -> Document {
+        doc! {
+            "item_id": revision.item_id.to_string(),
+            "revision": revision.revision as i64,
+            "encrypted_snapshot": Bson::Binary(Binary { subtype: bson::spec::BinarySubtype::Generic, bytes: revision.encrypted_snapshot.clone() }),
+            "nonce": Bson::Binary(Binary { subtype: bson::spec::BinarySubtype::Generic, bytes: revision.nonce.to_vec() }),
+            "created_at": Bson::DateTime(BsonDateTime::from_millis(revision.created_at.timestamp_millis())),
+        }
+    }
+
+    fn revision_from_document(document: Document) -> ChacrabResult<ItemRevision> {
+        let item_id_text = document
+            .get_str("item_id")
+            .map_err(|_| ChacrabError::Storage)?;
+        let created_at = document
+            .get_datetime("created_at")
+            .map_err(|_| ChacrabError::Storage)?
+            .timestamp_millis();
+
+        Ok(ItemRevision {
+            item_id: Uuid::parse_str(item_id_text).map_err(|_| ChacrabError::Storage)?,
+            revision: u32::try_from(
+                document
+                    .get_i64("revision")
+                    .map_err(|_| ChacrabError::Storage)?,
+            )
+            .map_err(|_| ChacrabError::Storage)?,
+            encrypted_snapshot: document
+                .get_binary_generic("encrypted_snapshot")
+                .map_err(|_| ChacrabError::Storage)?
+                .to_vec(),
+            nonce: Self::optional_nonce(&document, "nonce")?.ok_or(ChacrabError::Storage)?,
+            created_at: Utc
+                .timestamp_millis_opt(created_at)
+                .single()
+                .ok_or(ChacrabError::Storage)?,
+        })
+    }
+
+    async fn next_change_seq(&self) -> ChacrabResult<i64> {
+        self.metadata
+            .find_one_and_update(
+                doc! { "_id": CHANGE_SEQUENCE_ID },
+                doc! { "$inc": { "value": 1_i64 } },
+            )
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .await?
+            .ok_or(ChacrabError::Storage)?
+            .get_i64("value")
+            .map_err(|_| ChacrabError::Storage)
+    }
+
+    fn changed_between(cursor: u64, upper: i64) -> Document {
+        if cursor == 0 {
+            doc! { "$or": [
+                { "change_seq": { "$lte": upper } },
+                { "change_seq": { "$exists": false } },
+            ] }
+        } else {
+            let lower = i64::try_from(cursor).unwrap_or(i64::MAX);
+            doc! { "change_seq": { "$gt": lower, "$lte": upper } }
+        }
+    }
+
+    async fn write_tombstone(&self, tombstone: &Tombstone) -> ChacrabResult<()> {
+        self.tombstones
+            .replace_one(
+                doc! { "id": tombstone.id.to_string() },
+                doc! {
+                    "id": tombstone.id.to_string(),
+                    "deleted_at": Bson::DateTime(BsonDateTime::from_millis(tombstone.deleted_at.timestamp_millis())),
+                    "revision": tombstone.revision as i64,
+                    "change_seq": self.next_change_seq().await?,
+                },
+            )
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    fn auth_document(auth: &AuthRecord) -> Document {
+        doc! {
+            "id": 1,
+            "salt": &auth.salt,
+            "verifier": &auth.verifier,
+            "argon2_m_cost": auth.argon2_m_cost as i32,
+            "argon2_t_cost": auth.argon2_t_cost as i32,
+            "argon2_p_cost": auth.argon2_p_cost as i32,
+            "wrapped_vault_key": auth.wrapped_vault_key.clone().map(|bytes| {
+                Bson::Binary(Binary { subtype: bson::spec::BinarySubtype::Generic, bytes })
+            }),
+            "wrapped_vault_key_nonce": auth.wrapped_vault_key_nonce.map(|nonce| {
+                Bson::Binary(Binary { subtype: bson::spec::BinarySubtype::Generic, bytes: nonce.to_vec() })
+            }),
+            "metadata_privacy": auth.metadata_privacy,
+            "revision": auth.revision as i64,
+        }
+    }
+
+    async fn apply_rekey(&self, auth: Document, items: Vec<Document>) -> ChacrabResult<()> {
+        for mut item in items {
+            item.insert("change_seq", self.next_change_seq().await?);
+            let id = item
+                .get_str("id")
+                .map_err(|_| ChacrabError::Storage)?
+                .to_owned();
+            self.vault_items
+                .replace_one(doc! { "id": id }, item)
+                .upsert(true)
+                .await?;
+        }
+        self.auth
+            .update_one(doc! { "id": 1 }, doc! { "$set": auth })
+            .upsert(true)
+            .await?;
+        self.metadata
+            .delete_one(doc! { "_id": PENDING_REKEY_ID })
+            .await?;
+        Ok(())
+    }
+
+    async fn set_legacy_items(&self, legacy_items: bool) -> ChacrabResult<()> {
+        self.metadata
+            .update_one(
+                doc! { "_id": "schema" },
+                doc! { "$set": { "legacy_items": legacy_items } },
+            )
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    async fn resume_pending_rekey(&self) -> ChacrabResult<()> {
+        let Some(pending) = self
+            .metadata
+            .find_one(doc! { "_id": PENDING_REKEY_ID })
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let auth = pending
+            .get_document("auth")
+            .map_err(|_| ChacrabError::Storage)?
+            .clone();
+        let items = pending
+            .get_array("items")
+            .map_err(|_| ChacrabError::Storage)?
+            .iter()
+            .map(|value| value.as_document().cloned().ok_or(ChacrabError::Storage))
+            .collect::<ChacrabResult<Vec<_>>>()?;
+        self.apply_rekey(auth, items).await
+    }
 }
 
 #[async_trait]
@@ -131,6 +360,26 @@ impl VaultRepository for MongoRepository {
             .options(IndexOptions::builder().unique(true).build())
             .build();
         self.vault_items.create_index(unique_index).await?;
+        let revision_index = IndexModel::builder()
+            .keys(doc! { "item_id": 1, "revision": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.item_revisions.create_index(revision_index).await?;
+        let tombstone_index = IndexModel::builder()
+            .keys(doc! { "id": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.tombstones.create_index(tombstone_index).await?;
+        for collection in [&self.vault_items, &self.tombstones] {
+            collection
+                .create_index(IndexModel::builder().keys(doc! { "change_seq": 1 }).build())
+                .await?;
+        }
+        let sync_state_index = IndexModel::builder()
+            .keys(doc! { "remote": 1 })
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+        self.sync_state.create_index(sync_state_index).await?;
 
         self.metadata
             .update_one(
@@ -140,14 +389,46 @@ impl VaultRepository for MongoRepository {
             .upsert(true)
             .await?;
 
+        self.resume_pending_rekey().await?;
+
         Ok(())
     }
 
+    async fn is_initialized(&self) -> ChacrabResult<bool> {
+        let version = self
+            .metadata
+            .find_one(doc! { "_id": "schema" })
+            .await?
+            .and_then(|schema| schema.get_i64("version").ok());
+        Ok(version == Some(SCHEMA_VERSION))
+    }
+
+    async fn has_legacy_items(&self) -> ChacrabResult<bool> {
+        let flag = self
+            .metadata
+            .find_one(doc! { "_id": "schema" })
+            .await?
+            .and_then(|schema| schema.get_bool("legacy_items").ok());
+        Ok(flag.unwrap_or(true))
+    }
+
+    async fn clear_legacy_items(&self) -> ChacrabResult<()> {
+        self.set_legacy_items(false).await
+    }
+
     async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()> {
+        let mut document = Self::to_document(item);
+        document.insert("change_seq", self.next_change_seq().await?);
         self.vault_items
-            .replace_one(doc! { "id": item.id.to_string() }, Self::to_document(item))
+            .replace_one(doc! { "id": item.id.to_string() }, document)
             .upsert(true)
             .await?;
+        if item.format_version < ITEM_FORMAT_VERSION {
+            self.set_legacy_items(true).await?;
+        }
+        self.tombstones
+            .delete_one(doc! { "id": item.id.to_string() })
+            .await?;
         Ok(())
     }
 
@@ -158,11 +439,11 @@ impl VaultRepository for MongoRepository {
             .sort(doc! { "updated_at": -1 })
             .await?;
 
-        let mut out = Vec::new();
+        let mut documents = Vec::new();
         while let Some(document) = cursor.try_next().await? {
-            out.push(Self::from_document(document)?);
+            documents.push(Self::from_document(document));
         }
-        Ok(out)
+        storage::readable_items(documents)
     }
 
     async fn get_item(&self, id: Uuid) -> ChacrabResult<VaultItem> {
@@ -175,14 +456,205 @@ impl VaultRepository for MongoRepository {
     }
 
     async fn delete_item(&self, id: Uuid) -> ChacrabResult<()> {
-        let result = self
+        let deleted = self
+            .vault_items
+            .find_one_and_delete(doc! { "id": id.to_string() })
+            .await?
+            .ok_or(ChacrabError::NotFound)?;
+        self.item_revisions
+            .delete_many(doc! { "item_id": id.to_string() })
+            .await?;
+        self.write_tombstone(&Tombstone {
+            id,
+            deleted_at: Utc::now(),
+            revision: deleted.get_i64("revision").unwrap_or_default() as u64,
+        })
+        .await
+    }
+
+    async fn add_revision(&self, revision: &ItemRevision) -> ChacrabResult<()> {
+        self.item_revisions
+            .insert_one(Self::revision_document(revision))
+            .await?;
+        Ok(())
+    }
+
+    async fn list_revisions(&self, item_id: Uuid) -> ChacrabResult<Vec<ItemRevision>> {
+        let mut cursor = self
+            .item_revisions
+            .find(doc! { "item_id": item_id.to_string() })
+            .sort(doc! { "revision": 1 })
+            .await?;
+
+        let mut out = Vec::new();
+        while let Some(document) = cursor.try_next().await? {
+            out.push(Self::revision_from_document(document)?);
+        }
+        Ok(out)
+    }
+
+    async fn prune_revisions(&self, item_id: Uuid, keep: usize) -> ChacrabResult<()> {
+        let Some(newest_dropped) = self
+            .item_revisions
+            .find_one(doc! { "item_id": item_id.to_string() })
+            .sort(doc! { "revision": -1 })
+            .skip(keep as u64)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        let revision = newest_dropped
+            .get_i64("revision")
+            .map_err(|_| ChacrabError::Storage)?;
+        self.item_revisions
+            .delete_many(doc! {
+                "item_id": item_id.to_string(),
+                "revision": { "$lte": revision },
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn list_tombstones(&self) -> ChacrabResult<Vec<Tombstone>> {
+        let mut cursor = self
+            .tombstones
+            .find(doc! {})
+            .sort(doc! { "deleted_at": 1 })
+            .await?;
+
+        let mut out = Vec::new();
+        while let Some(document) = cursor.try_next().await? {
+            out.push(Self::tombstone_from_document(&document)?);
+        }
+        Ok(out)
+    }
+
+    async fn record_tombstone(&self, tombstone: &Tombstone) -> ChacrabResult<()> {
+        let id = tombstone.id.to_string();
+        self.vault_items.delete_one(doc! { "id": &id }).await?;
+        self.item_revisions
+            .delete_many(doc! { "item_id": &id })
+            .await?;
+        self.write_tombstone(tombstone).await
+    }
+
+    async fn changes_since(&self, cursor: u64) -> ChacrabResult<ChangeSet> {
+        // Both queries stop at the counter read here, so the returned cursor never
+        // moves past a change that was written after it and left out of this set.
+        let current = self
+            .metadata
+            .find_one(doc! { "_id": CHANGE_SEQUENCE_ID })
+            .await?
+            .map(|counter| counter.get_i64("value").map_err(|_| ChacrabError::Storage))
+            .transpose()?
+            .unwrap_or_default();
+
+        let mut items = Vec::new();
+        let mut item_cursor = self
             .vault_items
-            .delete_one(doc! { "id": id.to_string() })
+            .find(Self::changed_between(cursor, current))
+            .sort(doc! { "change_seq": 1 })
+            .await?;
+        while let Some(document) = item_cursor.try_next().await? {
+            items.push(Self::from_document(document)?);
+        }
+
+        let mut tombstones = Vec::new();
+        let mut tombstone_cursor = self
+            .tombstones
+            .find(Self::changed_between(cursor, current))
+            .sort(doc! { "change_seq": 1 })
+            .await?;
+        while let Some(document) = tombstone_cursor.try_next().await? {
+            tombstones.push(Self::tombstone_from_document(&document)?);
+        }
+
+        Ok(ChangeSet {
+            items,
+            tombstones,
+            cursor: current as u64,
+        })
+    }
+
+    async fn get_manifest(&self) -> ChacrabResult<Option<VaultManifest>> {
+        let document = self
+            .metadata
+            .find_one(doc! { "_id": SYNC_MANIFEST_ID })
+            .await?;
+        document
+            .map(|doc| {
+                Ok(VaultManifest {
+                    generation: doc
+                        .get_i64("generation")
+                        .map_err(|_| ChacrabError::Storage)? as u64,
+                    entries: serde_json::from_str(
+                        doc.get_str("entries").map_err(|_| ChacrabError::Storage)?,
+                    )
+                    .map_err(|_| ChacrabError::Storage)?,
+                    mac: doc
+                        .get_str("mac")
+                        .map_err(|_| ChacrabError::Storage)?
+                        .to_owned(),
+                })
+            })
+            .transpose()
+    }
+
+    async fn set_manifest(&self, manifest: &VaultManifest) -> ChacrabResult<()> {
+        self.metadata
+            .replace_one(
+                doc! { "_id": SYNC_MANIFEST_ID },
+                doc! {
+                    "_id": SYNC_MANIFEST_ID,
+                    "generation": manifest.generation as i64,
+                    "entries": serde_json::to_string(&manifest.entries)?,
+                    "mac": &manifest.mac,
+                },
+            )
+            .upsert(true)
             .await?;
+        Ok(())
+    }
+
+    async fn get_sync_state(&self, remote: &str) -> ChacrabResult<Option<SyncState>> {
+        let document = self.sync_state.find_one(doc! { "remote": remote }).await?;
+        document
+            .map(|doc| {
+                Ok(SyncState {
+                    remote: remote.to_owned(),
+                    local_cursor: doc
+                        .get_i64("local_cursor")
+                        .map_err(|_| ChacrabError::Storage)?
+                        as u64,
+                    remote_cursor: doc
+                        .get_i64("remote_cursor")
+                        .map_err(|_| ChacrabError::Storage)?
+                        as u64,
+                    manifest: doc
+                        .get_str("manifest")
+                        .ok()
+                        .map(serde_json::from_str)
+                        .transpose()
+                        .map_err(|_| ChacrabError::Storage)?,
+                })
+            })
+            .transpose()
+    }
 
-        if result.deleted_count == 0 {
-            return Err(ChacrabError::NotFound);
+    async fn set_sync_state(&self, state: &SyncState) -> ChacrabResult<()> {
+        let mut document = doc! {
+            "remote": &state.remote,
+            "local_cursor": state.local_cursor as i64,
+            "remote_cursor": state.remote_cursor as i64,
+        };
+        if let Some(manifest) = &state.manifest {
+            document.insert("manifest", serde_json::to_string(manifest)?);
         }
+        self.sync_state
+            .replace_one(doc! { "remote": &state.remote }, document)
+            .upsert(true)
+            .await?;
         Ok(())
     }
 
@@ -211,6 +683,10 @@ impl VaultRepository for MongoRepository {
                         .get_i32("argon2_p_cost")
                         .map_err(|_| ChacrabError::Storage)?
                         as u32,
+                    wrapped_vault_key: doc.get_binary_generic("wrapped_vault_key").ok().cloned(),
+                    wrapped_vault_key_nonce: Self::optional_nonce(&doc, "wrapped_vault_key_nonce")?,
+                    metadata_privacy: doc.get_bool("metadata_privacy").unwrap_or(false),
+                    revision: doc.get_i64("revision").unwrap_or_default() as u64,
                 })
             })
             .transpose()
@@ -218,21 +694,32 @@ impl VaultRepository for MongoRepository {
 
     async fn set_auth_record(&self, auth: &AuthRecord) -> ChacrabResult<()> {
         self.auth
-            .update_one(
-                doc! { "id": 1 },
+            .update_one(doc! { "id": 1 }, doc! { "$set": Self::auth_document(auth) })
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    async fn replace_auth_and_items(
+        &self,
+        auth: &AuthRecord,
+        items: &[VaultItem],
+    ) -> ChacrabResult<()> {
+        // Journal first: standalone MongoDB has no multi-document transactions.
+        let auth_document = Self::auth_document(auth);
+        let item_documents = items.iter().map(Self::to_document).collect::<Vec<_>>();
+        self.metadata
+            .replace_one(
+                doc! { "_id": PENDING_REKEY_ID },
                 doc! {
-                    "$set": {
-                        "id": 1,
-                        "salt": &auth.salt,
-                        "verifier": &auth.verifier,
-                        "argon2_m_cost": auth.argon2_m_cost as i32,
-                        "argon2_t_cost": auth.argon2_t_cost as i32,
-                        "argon2_p_cost": auth.argon2_p_cost as i32,
-                    }
+                    "_id": PENDING_REKEY_ID,
+                    "auth": auth_document.clone(),
+                    "items": item_documents.clone(),
                 },
             )
             .upsert(true)
             .await?;
-        Ok(())
+
+        self.apply_rekey(auth_document, item_documents).await
     }
 }
@@ -1,15 +1,19 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::{PgPool, Row};
+use sqlx::{Executor, PgConnection, PgPool, Postgres, Row, postgres::PgRow};
 use uuid::Uuid;
 
 use crate::core::{
     errors::{ChacrabError, ChacrabResult},
-    models::{AuthRecord, VaultItem, VaultItemType},
+    models::{
+        AuthRecord, ChangeSet, ITEM_FORMAT_VERSION, ItemRevision, SyncState, Tombstone, VaultItem,
+        VaultItemType, VaultManifest,
+    },
 };
-use crate::storage::r#trait::VaultRepository;
+use crate::storage::{self, r#trait::VaultRepository};
 
-const SCHEMA_VERSION: i64 = 1;
+const SCHEMA_VERSION: i64 = 15;
+const ITEM_COLUMNS: &str = "id, item_type, title, username, url, encrypted_data, nonce, format_version, encrypted_metadata, metadata_nonce, folder, tags, favorite, deleted_at, revision, base_revision, created_at, updated_at";
 
 #[derive(Clone)]
 pub struct PostgresRepository {
@@ -23,18 +27,301 @@ impl PostgresRepository {
     }
 
     fn parse_item_type(value: &str) -> ChacrabResult<VaultItemType> {
-        match value {
-            "password" => Ok(VaultItemType::Password),
-            "note" => Ok(VaultItemType::Note),
-            _ => Err(ChacrabError::Storage),
-        }
+        value.parse()
     }
 
     fn item_type_to_str(item_type: &VaultItemType) -> &'static str {
-        match item_type {
-            VaultItemType::Password => "password",
-            VaultItemType::Note => "note",
+        item_type.as_str()
+    }
+
+    fn nonce_from_blob(blob: Option<Vec<u8>>) -> ChacrabResult<Option<[u8; 12]>> {
+        blob.map(|bytes| <[u8; 12]>::try_from(bytes.as_slice()).map_err(|_| ChacrabError::Storage))
+            .transpose()
+    }
+
+    fn item_from_row(row: &PgRow) -> ChacrabResult<VaultItem> {
+        let nonce_blob: Vec<u8> = row.try_get("nonce")?;
+        if nonce_blob.len() != 12 {
+            return Err(ChacrabError::Storage);
+        }
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&nonce_blob);
+
+        let id_text: String = row.try_get("id")?;
+        let item_type_text: String = row.try_get("item_type")?;
+        let created_at_text: String = row.try_get("created_at")?;
+        let updated_at_text: String = row.try_get("updated_at")?;
+        let deleted_at_text: Option<String> = row.try_get("deleted_at")?;
+
+        let created_at = DateTime::parse_from_rfc3339(&created_at_text)
+            .map_err(|_| ChacrabError::Storage)?
+            .with_timezone(&Utc);
+        let updated_at = DateTime::parse_from_rfc3339(&updated_at_text)
+            .map_err(|_| ChacrabError::Storage)?
+            .with_timezone(&Utc);
+
+        Ok(VaultItem {
+            id: Uuid::parse_str(&id_text).map_err(|_| ChacrabError::Storage)?,
+            r#type: Self::parse_item_type(&item_type_text)?,
+            title: row.try_get("title")?,
+            username: row.try_get("username")?,
+            url: row.try_get("url")?,
+            encrypted_data: row.try_get("encrypted_data")?,
+            nonce,
+            encrypted_metadata: row.try_get("encrypted_metadata")?,
+            metadata_nonce: Self::nonce_from_blob(row.try_get("metadata_nonce")?)?,
+            format_version: row.try_get::<i32, _>("format_version")? as u32,
+            folder: row.try_get("folder")?,
+            tags: row.try_get("tags")?,
+            favorite: row.try_get("favorite")?,
+            deleted_at: deleted_at_text
+                .map(|text| {
+                    DateTime::parse_from_rfc3339(&text)
+                        .map(|deleted_at| deleted_at.with_timezone(&Utc))
+                        .map_err(|_| ChacrabError::Storage)
+                })
+                .transpose()?,
+            revision: row.try_get::<i64, _>("revision")? as u64,
+            base_revision: row.try_get::<i64, _>("base_revision")? as u64,
+            created_at,
+            updated_at,
+        })
+    }
+
+    fn tombstone_from_row(row: &PgRow) -> ChacrabResult<Tombstone> {
+        let id_text: String = row.try_get("id")?;
+        let deleted_at_text: String = row.try_get("deleted_at")?;
+        Ok(Tombstone {
+            id: Uuid::parse_str(&id_text).map_err(|_| ChacrabError::Storage)?,
+            deleted_at: DateTime::parse_from_rfc3339(&deleted_at_text)
+                .map_err(|_| ChacrabError::Storage)?
+                .with_timezone(&Utc),
+            revision: row.try_get::<i64, _>("revision")? as u64,
+        })
+    }
+
+    fn revision_from_row(row: &PgRow) -> ChacrabResult<ItemRevision> {
+        let item_id_text: String = row.try_get("item_id")?;
+        let nonce_blob: Vec<u8> = row.try_get("nonce")?;
+        let created_at_text: String = row.try_get("created_at")?;
+
+        Ok(ItemRevision {
+            item_id: Uuid::parse_str(&item_id_text).map_err(|_| ChacrabError::Storage)?,
+            revision: row.try_get::<i32, _>("revision")? as u32,
+            encrypted_snapshot: row.try_get("encrypted_snapshot")?,
+            nonce: Self::nonce_from_blob(Some(nonce_blob))?.ok_or(ChacrabError::Storage)?,
+            created_at: DateTime::parse_from_rfc3339(&created_at_text)
+                .map_err(|_| ChacrabError::Storage)?
+                .with_timezone(&Utc),
+        })
+    }
+
+    async fn migrate(&self, from_version: i64) -> ChacrabResult<()> {
+        if from_version < 2 {
+            sqlx::query("ALTER TABLE auth ADD COLUMN IF NOT EXISTS wrapped_vault_key BYTEA NULL")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query(
+                "ALTER TABLE auth ADD COLUMN IF NOT EXISTS wrapped_vault_key_nonce BYTEA NULL",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        if from_version < 3 {
+            sqlx::query(
+                "ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS format_version INTEGER NOT NULL DEFAULT 1",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        if from_version < 4 {
+            sqlx::query("ALTER TABLE auth ADD COLUMN IF NOT EXISTS metadata_privacy BOOLEAN NOT NULL DEFAULT FALSE")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query(
+                "ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS encrypted_metadata BYTEA NULL",
+            )
+            .execute(&self.pool)
+            .await?;
+            sqlx::query(
+                "ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS metadata_nonce BYTEA NULL",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        if from_version < 5 {
+            for statement in [
+                "ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS folder TEXT NULL",
+                "ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}'",
+                "ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS favorite BOOLEAN NOT NULL DEFAULT FALSE",
+            ] {
+                sqlx::query(statement).execute(&self.pool).await?;
+            }
+        }
+        if from_version < 7 {
+            sqlx::query("ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS deleted_at TEXT NULL")
+                .execute(&self.pool)
+                .await?;
+        }
+        if from_version < 9 {
+            for statement in [
+                "ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 0",
+                "ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS base_revision BIGINT NOT NULL DEFAULT 0",
+            ] {
+                sqlx::query(statement).execute(&self.pool).await?;
+            }
+        }
+        if from_version < 11 {
+            for statement in [
+                "ALTER TABLE vault_items ADD COLUMN IF NOT EXISTS change_seq BIGINT NOT NULL DEFAULT 1",
+                "ALTER TABLE tombstones ADD COLUMN IF NOT EXISTS change_seq BIGINT NOT NULL DEFAULT 1",
+            ] {
+                sqlx::query(statement).execute(&self.pool).await?;
+            }
+        }
+        if from_version < 12 {
+            sqlx::query("ALTER TABLE sync_state ADD COLUMN IF NOT EXISTS manifest TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        if from_version < 13 {
+            sqlx::query(
+                "ALTER TABLE schema_meta ADD COLUMN IF NOT EXISTS legacy_items BOOLEAN NOT NULL DEFAULT TRUE",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        if from_version < 14 {
+            sqlx::query(
+                "ALTER TABLE tombstones ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 0",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        if from_version < 15 {
+            sqlx::query(
+                "ALTER TABLE auth ADD COLUMN IF NOT EXISTS revision BIGINT NOT NULL DEFAULT 0",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn next_change_seq(conn: &mut PgConnection) -> ChacrabResult<i64> {
+        Ok(sqlx::query_scalar(
+            "UPDATE change_sequence SET value = value + 1 WHERE id = 1 RETURNING value",
+        )
+        .fetch_one(conn)
+        .await?)
+    }
+
+    async fn write_item(conn: &mut PgConnection, item: &VaultItem) -> ChacrabResult<()> {
+        let change_seq = Self::next_change_seq(&mut *conn).await?;
+        sqlx::query(
+            "INSERT INTO vault_items (id, item_type, title, username, url, encrypted_data, nonce, format_version, encrypted_metadata, metadata_nonce, folder, tags, favorite, deleted_at, revision, base_revision, created_at, updated_at, change_seq)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+             ON CONFLICT(id) DO UPDATE SET
+                item_type = EXCLUDED.item_type,
+                title = EXCLUDED.title,
+                username = EXCLUDED.username,
+                url = EXCLUDED.url,
+                encrypted_data = EXCLUDED.encrypted_data,
+                nonce = EXCLUDED.nonce,
+                format_version = EXCLUDED.format_version,
+                encrypted_metadata = EXCLUDED.encrypted_metadata,
+                metadata_nonce = EXCLUDED.metadata_nonce,
+                folder = EXCLUDED.folder,
+                tags = EXCLUDED.tags,
+                favorite = EXCLUDED.favorite,
+                deleted_at = EXCLUDED.deleted_at,
+                revision = EXCLUDED.revision,
+                base_revision = EXCLUDED.base_revision,
+                created_at = EXCLUDED.created_at,
+                updated_at = EXCLUDED.updated_at,
+                change_seq = EXCLUDED.change_seq",
+        )
+        .bind(item.id.to_string())
+        .bind(Self::item_type_to_str(&item.r#type))
+        .bind(&item.title)
+        .bind(&item.username)
+        .bind(&item.url)
+        .bind(&item.encrypted_data)
+        .bind(item.nonce.to_vec())
+        .bind(item.format_version as i32)
+        .bind(&item.encrypted_metadata)
+        .bind(item.metadata_nonce.map(|nonce| nonce.to_vec()))
+        .bind(&item.folder)
+        .bind(&item.tags)
+        .bind(item.favorite)
+        .bind(item.deleted_at.map(|deleted_at| deleted_at.to_rfc3339()))
+        .bind(item.revision as i64)
+        .bind(item.base_revision as i64)
+        .bind(item.created_at.to_rfc3339())
+        .bind(item.updated_at.to_rfc3339())
+        .bind(change_seq)
+        .execute(&mut *conn)
+        .await?;
+
+        if item.format_version < ITEM_FORMAT_VERSION {
+            sqlx::query("UPDATE schema_meta SET legacy_items = TRUE WHERE id = 1")
+                .execute(conn)
+                .await?;
         }
+        Ok(())
+    }
+
+    async fn write_tombstone(conn: &mut PgConnection, tombstone: &Tombstone) -> ChacrabResult<()> {
+        let change_seq = Self::next_change_seq(&mut *conn).await?;
+        sqlx::query(
+            "INSERT INTO tombstones (id, deleted_at, revision, change_seq)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT(id) DO UPDATE SET
+                deleted_at = EXCLUDED.deleted_at,
+                revision = EXCLUDED.revision,
+                change_seq = EXCLUDED.change_seq",
+        )
+        .bind(tombstone.id.to_string())
+        .bind(tombstone.deleted_at.to_rfc3339())
+        .bind(tombstone.revision as i64)
+        .bind(change_seq)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn write_auth<'e, E>(executor: E, auth: &AuthRecord) -> ChacrabResult<()>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        sqlx::query(
+            "INSERT INTO auth (id, salt, verifier, argon2_m_cost, argon2_t_cost, argon2_p_cost, wrapped_vault_key, wrapped_vault_key_nonce, metadata_privacy, revision)
+             VALUES (1, $1, $2, $3, $4, $5, $6, $7, $8, $9)
+             ON CONFLICT(id) DO UPDATE SET
+               salt = EXCLUDED.salt,
+               verifier = EXCLUDED.verifier,
+               argon2_m_cost = EXCLUDED.argon2_m_cost,
+               argon2_t_cost = EXCLUDED.argon2_t_cost,
+               argon2_p_cost = EXCLUDED.argon2_p_cost,
+               wrapped_vault_key = EXCLUDED.wrapped_vault_key,
+               wrapped_vault_key_nonce = EXCLUDED.wrapped_vault_key_nonce,
+               metadata_privacy = EXCLUDED.metadata_privacy,
+               revision = EXCLUDED.revision",
+        )
+        .bind(&auth.salt)
+        .bind(&auth.verifier)
+        .bind(auth.argon2_m_cost as i32)
+        .bind(auth.argon2_t_cost as i32)
+        .bind(auth.argon2_p_cost as i32)
+        .bind(&auth.wrapped_vault_key)
+        .bind(auth.wrapped_vault_key_nonce.map(|nonce| nonce.to_vec()))
+        .bind(auth.metadata_privacy)
+        .bind(auth.revision as i64)
+        .execute(executor)
+        .await?;
+
+        Ok(())
     }
 }
 
@@ -44,20 +331,17 @@ impl VaultRepository for PostgresRepository {
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS schema_meta (
                 id INTEGER PRIMARY KEY,
-                schema_version BIGINT NOT NULL
+                schema_version BIGINT NOT NULL,
+                legacy_items BOOLEAN NOT NULL DEFAULT TRUE
             )",
         )
         .execute(&self.pool)
         .await?;
 
-        sqlx::query(
-            "INSERT INTO schema_meta (id, schema_version)
-             VALUES (1, $1)
-             ON CONFLICT(id) DO UPDATE SET schema_version = EXCLUDED.schema_version",
-        )
-        .bind(SCHEMA_VERSION)
-        .execute(&self.pool)
-        .await?;
+        let stored_version =
+            sqlx::query_scalar::<_, i64>("SELECT schema_version FROM schema_meta WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await?;
 
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS auth (
@@ -66,7 +350,11 @@ impl VaultRepository for PostgresRepository {
                 verifier TEXT NOT NULL,
                 argon2_m_cost INTEGER NOT NULL,
                 argon2_t_cost INTEGER NOT NULL,
-                argon2_p_cost INTEGER NOT NULL
+                argon2_p_cost INTEGER NOT NULL,
+                wrapped_vault_key BYTEA NULL,
+                wrapped_vault_key_nonce BYTEA NULL,
+                metadata_privacy BOOLEAN NOT NULL DEFAULT FALSE,
+                revision BIGINT NOT NULL DEFAULT 0
             )",
         )
         .execute(&self.pool)
@@ -81,142 +369,376 @@ impl VaultRepository for PostgresRepository {
                 url TEXT NULL,
                 encrypted_data BYTEA NOT NULL,
                 nonce BYTEA NOT NULL,
+                format_version INTEGER NOT NULL DEFAULT 1,
+                encrypted_metadata BYTEA NULL,
+                metadata_nonce BYTEA NULL,
+                folder TEXT NULL,
+                tags TEXT[] NOT NULL DEFAULT '{}',
+                favorite BOOLEAN NOT NULL DEFAULT FALSE,
+                deleted_at TEXT NULL,
+                revision BIGINT NOT NULL DEFAULT 0,
+                base_revision BIGINT NOT NULL DEFAULT 0,
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                change_seq BIGINT NOT NULL DEFAULT 1
             )",
         )
         .execute(&self.pool)
         .await?;
 
-        Ok(())
-    }
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS item_revisions (
+                item_id TEXT NOT NULL,
+                revision INTEGER NOT NULL,
+                encrypted_snapshot BYTEA NOT NULL,
+                nonce BYTEA NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (item_id, revision)
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
 
-    async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()> {
         sqlx::query(
-            "INSERT INTO vault_items (id, item_type, title, username, url, encrypted_data, nonce, created_at, updated_at)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-             ON CONFLICT(id) DO UPDATE SET
-                item_type = EXCLUDED.item_type,
-                title = EXCLUDED.title,
-                username = EXCLUDED.username,
-                url = EXCLUDED.url,
-                encrypted_data = EXCLUDED.encrypted_data,
-                nonce = EXCLUDED.nonce,
-                created_at = EXCLUDED.created_at,
-                updated_at = EXCLUDED.updated_at",
+            "CREATE TABLE IF NOT EXISTS tombstones (
+                id TEXT PRIMARY KEY,
+                deleted_at TEXT NOT NULL,
+                revision BIGINT NOT NULL DEFAULT 0,
+                change_seq BIGINT NOT NULL DEFAULT 1
+            )",
         )
-        .bind(item.id.to_string())
-        .bind(Self::item_type_to_str(&item.r#type))
-        .bind(&item.title)
-        .bind(&item.username)
-        .bind(&item.url)
-        .bind(&item.encrypted_data)
-        .bind(item.nonce.to_vec())
-        .bind(item.created_at.to_rfc3339())
-        .bind(item.updated_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS sync_manifest (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                generation BIGINT NOT NULL,
+                entries TEXT NOT NULL,
+                mac TEXT NOT NULL
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        for statement in [
+            "CREATE TABLE IF NOT EXISTS change_sequence (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                value BIGINT NOT NULL
+            )",
+            "INSERT INTO change_sequence (id, value) VALUES (1, 1) ON CONFLICT(id) DO NOTHING",
+            "CREATE TABLE IF NOT EXISTS sync_state (
+                remote TEXT PRIMARY KEY,
+                local_cursor BIGINT NOT NULL,
+                remote_cursor BIGINT NOT NULL,
+                manifest TEXT
+            )",
+        ] {
+            sqlx::query(statement).execute(&self.pool).await?;
+        }
+
+        if let Some(version) = stored_version {
+            self.migrate(version).await?;
+        }
+
+        for statement in [
+            "CREATE INDEX IF NOT EXISTS vault_items_change_seq ON vault_items (change_seq)",
+            "CREATE INDEX IF NOT EXISTS tombstones_change_seq ON tombstones (change_seq)",
+        ] {
+            sqlx::query(statement).execute(&self.pool).await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO schema_meta (id, schema_version)
+             VALUES (1, $1)
+             ON CONFLICT(id) DO UPDATE SET schema_version = EXCLUDED.schema_version",
+        )
+        .bind(SCHEMA_VERSION)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn is_initialized(&self) -> ChacrabResult<bool> {
+        let exists = sqlx::query_scalar::<_, bool>("SELECT to_regclass('schema_meta') IS NOT NULL")
+            .fetch_one(&self.pool)
+            .await?;
+        if !exists {
+            return Ok(false);
+        }
+        let version =
+            sqlx::query_scalar::<_, i64>("SELECT schema_version FROM schema_meta WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(version == Some(SCHEMA_VERSION))
+    }
+
+    async fn has_legacy_items(&self) -> ChacrabResult<bool> {
+        let flag =
+            sqlx::query_scalar::<_, bool>("SELECT legacy_items FROM schema_meta WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(flag.unwrap_or(true))
+    }
+
+    async fn clear_legacy_items(&self) -> ChacrabResult<()> {
+        sqlx::query("UPDATE schema_meta SET legacy_items = FALSE WHERE id = 1")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::write_item(&mut tx, item).await?;
+        sqlx::query("DELETE FROM tombstones WHERE id = $1")
+            .bind(item.id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
         Ok(())
     }
 
     async fn list_items(&self) -> ChacrabResult<Vec<VaultItem>> {
-        let rows = sqlx::query(
-            "SELECT id, item_type, title, username, url, encrypted_data, nonce, created_at, updated_at
-             FROM vault_items ORDER BY updated_at DESC",
-        )
+        let rows = sqlx::query(&format!(
+            "SELECT {ITEM_COLUMNS} FROM vault_items ORDER BY updated_at DESC"
+        ))
         .fetch_all(&self.pool)
         .await?;
 
-        rows.into_iter()
-            .map(|row| {
-                let nonce_blob: Vec<u8> = row.try_get("nonce")?;
-                if nonce_blob.len() != 12 {
-                    return Err(ChacrabError::Storage);
-                }
-                let mut nonce = [0u8; 12];
-                nonce.copy_from_slice(&nonce_blob);
-
-                let id_text: String = row.try_get("id")?;
-                let item_type_text: String = row.try_get("item_type")?;
-                let created_at_text: String = row.try_get("created_at")?;
-                let updated_at_text: String = row.try_get("updated_at")?;
-                let created_at = DateTime::parse_from_rfc3339(&created_at_text)
-                    .map_err(|_| ChacrabError::Storage)?
-                    .with_timezone(&Utc);
-                let updated_at = DateTime::parse_from_rfc3339(&updated_at_text)
-                    .map_err(|_| ChacrabError::Storage)?
-                    .with_timezone(&Utc);
-
-                Ok(VaultItem {
-                    id: Uuid::parse_str(&id_text).map_err(|_| ChacrabError::Storage)?,
-                    r#type: Self::parse_item_type(&item_type_text)?,
-                    title: row.try_get("title")?,
-                    username: row.try_get("username")?,
-                    url: row.try_get("url")?,
-                    encrypted_data: row.try_get("encrypted_data")?,
-                    nonce,
-                    created_at,
-                    updated_at,
-                })
-            })
-            .collect::<Result<Vec<_>, ChacrabError>>()
+        storage::readable_items(rows.iter().map(Self::item_from_row))
     }
 
     async fn get_item(&self, id: Uuid) -> ChacrabResult<VaultItem> {
-        let row = sqlx::query(
-            "SELECT id, item_type, title, username, url, encrypted_data, nonce, created_at, updated_at
-             FROM vault_items WHERE id = $1",
-        )
+        let row = sqlx::query(&format!(
+            "SELECT {ITEM_COLUMNS} FROM vault_items WHERE id = $1"
+        ))
         .bind(id.to_string())
         .fetch_optional(&self.pool)
         .await?
         .ok_or(ChacrabError::NotFound)?;
 
-        let nonce_blob: Vec<u8> = row.try_get("nonce")?;
-        if nonce_blob.len() != 12 {
-            return Err(ChacrabError::Storage);
+        Self::item_from_row(&row)
+    }
+
+    async fn delete_item(&self, id: Uuid) -> ChacrabResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let revision: i64 =
+            sqlx::query_scalar("DELETE FROM vault_items WHERE id = $1 RETURNING revision")
+                .bind(id.to_string())
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(ChacrabError::NotFound)?;
+        sqlx::query("DELETE FROM item_revisions WHERE item_id = $1")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        Self::write_tombstone(
+            &mut tx,
+            &Tombstone {
+                id,
+                deleted_at: Utc::now(),
+                revision: revision as u64,
+            },
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn add_revision(&self, revision: &ItemRevision) -> ChacrabResult<()> {
+        sqlx::query(
+            "INSERT INTO item_revisions (item_id, revision, encrypted_snapshot, nonce, created_at)
+             VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(revision.item_id.to_string())
+        .bind(revision.revision as i32)
+        .bind(&revision.encrypted_snapshot)
+        .bind(revision.nonce.to_vec())
+        .bind(revision.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_revisions(&self, item_id: Uuid) -> ChacrabResult<Vec<ItemRevision>> {
+        let rows = sqlx::query(
+            "SELECT item_id, revision, encrypted_snapshot, nonce, created_at
+             FROM item_revisions WHERE item_id = $1 ORDER BY revision ASC",
+        )
+        .bind(item_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::revision_from_row).collect()
+    }
+
+    async fn prune_revisions(&self, item_id: Uuid, keep: usize) -> ChacrabResult<()> {
+        sqlx::query(
+            "DELETE FROM item_revisions
+             WHERE item_id = $1 AND revision NOT IN (
+               SELECT revision FROM item_revisions WHERE item_id = $1
+               ORDER BY revision DESC LIMIT $2
+             )",
+        )
+        .bind(item_id.to_string())
+        .bind(i64::try_from(keep).unwrap_or(i64::MAX))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_tombstones(&self) -> ChacrabResult<Vec<Tombstone>> {
+        let rows =
+            sqlx::query("SELECT id, deleted_at, revision FROM tombstones ORDER BY deleted_at ASC")
+                .fetch_all(&self.pool)
+                .await?;
+
+        rows.iter().map(Self::tombstone_from_row).collect()
+    }
+
+    async fn record_tombstone(&self, tombstone: &Tombstone) -> ChacrabResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for statement in [
+            "DELETE FROM vault_items WHERE id = $1",
+            "DELETE FROM item_revisions WHERE item_id = $1",
+        ] {
+            sqlx::query(statement)
+                .bind(tombstone.id.to_string())
+                .execute(&mut *tx)
+                .await?;
         }
-        let mut nonce = [0u8; 12];
-        nonce.copy_from_slice(&nonce_blob);
+        Self::write_tombstone(&mut tx, tombstone).await?;
+        tx.commit().await?;
+        Ok(())
+    }
 
-        let item_type_text: String = row.try_get("item_type")?;
-        let created_at_text: String = row.try_get("created_at")?;
-        let updated_at_text: String = row.try_get("updated_at")?;
-        let created_at = DateTime::parse_from_rfc3339(&created_at_text)
-            .map_err(|_| ChacrabError::Storage)?
-            .with_timezone(&Utc);
-        let updated_at = DateTime::parse_from_rfc3339(&updated_at_text)
-            .map_err(|_| ChacrabError::Storage)?
-            .with_timezone(&Utc);
+    async fn changes_since(&self, cursor: u64) -> ChacrabResult<ChangeSet> {
+        let since = i64::try_from(cursor).unwrap_or(i64::MAX);
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+            .execute(&mut *tx)
+            .await?;
+        let current: i64 = sqlx::query_scalar("SELECT value FROM change_sequence WHERE id = 1")
+            .fetch_one(&mut *tx)
+            .await?;
+        let item_rows = sqlx::query(&format!(
+            "SELECT {ITEM_COLUMNS} FROM vault_items WHERE change_seq > $1 ORDER BY change_seq ASC"
+        ))
+        .bind(since)
+        .fetch_all(&mut *tx)
+        .await?;
+        let tombstone_rows = sqlx::query(
+            "SELECT id, deleted_at, revision FROM tombstones WHERE change_seq > $1 ORDER BY change_seq ASC",
+        )
+        .bind(since)
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
 
-        Ok(VaultItem {
-            id,
-            r#type: Self::parse_item_type(&item_type_text)?,
-            title: row.try_get("title")?,
-            username: row.try_get("username")?,
-            url: row.try_get("url")?,
-            encrypted_data: row.try_get("encrypted_data")?,
-            nonce,
-            created_at,
-            updated_at,
+        Ok(ChangeSet {
+            items: item_rows
+                .iter()
+                .map(Self::item_from_row)
+                .collect::<ChacrabResult<_>>()?,
+            tombstones: tombstone_rows
+                .iter()
+                .map(Self::tombstone_from_row)
+                .collect::<ChacrabResult<_>>()?,
+            cursor: current as u64,
         })
     }
 
-    async fn delete_item(&self, id: Uuid) -> ChacrabResult<()> {
-        let result = sqlx::query("DELETE FROM vault_items WHERE id = $1")
-            .bind(id.to_string())
-            .execute(&self.pool)
+    async fn get_manifest(&self) -> ChacrabResult<Option<VaultManifest>> {
+        let row = sqlx::query("SELECT generation, entries, mac FROM sync_manifest WHERE id = 1")
+            .fetch_optional(&self.pool)
             .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(ChacrabError::NotFound);
-        }
+        row.map(|r| {
+            let entries_text: String = r.try_get("entries")?;
+            Ok(VaultManifest {
+                generation: r.try_get::<i64, _>("generation")? as u64,
+                entries: serde_json::from_str(&entries_text).map_err(|_| ChacrabError::Storage)?,
+                mac: r.try_get("mac")?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn set_manifest(&self, manifest: &VaultManifest) -> ChacrabResult<()> {
+        sqlx::query(
+            "INSERT INTO sync_manifest (id, generation, entries, mac)
+             VALUES (1, $1, $2, $3)
+             ON CONFLICT(id) DO UPDATE SET
+               generation=EXCLUDED.generation,
+               entries=EXCLUDED.entries,
+               mac=EXCLUDED.mac",
+        )
+        .bind(manifest.generation as i64)
+        .bind(serde_json::to_string(&manifest.entries)?)
+        .bind(&manifest.mac)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_sync_state(&self, remote: &str) -> ChacrabResult<Option<SyncState>> {
+        let row = sqlx::query(
+            "SELECT remote, local_cursor, remote_cursor, manifest FROM sync_state WHERE remote = $1",
+        )
+        .bind(remote)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| {
+            Ok(SyncState {
+                remote: r.try_get("remote")?,
+                local_cursor: r.try_get::<i64, _>("local_cursor")? as u64,
+                remote_cursor: r.try_get::<i64, _>("remote_cursor")? as u64,
+                manifest: r
+                    .try_get::<Option<String>, _>("manifest")?
+                    .map(|manifest| serde_json::from_str(&manifest))
+                    .transpose()
+                    .map_err(|_| ChacrabError::Storage)?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn set_sync_state(&self, state: &SyncState) -> ChacrabResult<()> {
+        sqlx::query(
+            "INSERT INTO sync_state (remote, local_cursor, remote_cursor, manifest)
+             VALUES ($1, $2, $3, $4)
+             ON CONFLICT(remote) DO UPDATE SET
+               local_cursor = EXCLUDED.local_cursor,
+               remote_cursor = EXCLUDED.remote_cursor,
+               manifest = EXCLUDED.manifest",
+        )
+        .bind(&state.remote)
+        .bind(state.local_cursor as i64)
+        .bind(state.remote_cursor as i64)
+        .bind(
+            state
+                .manifest
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+        )
+        .execute(&self.pool)
+        .await?;
+
         Ok(())
     }
 
     async fn get_auth_record(&self) -> ChacrabResult<Option<AuthRecord>> {
         let row = sqlx::query(
-            "SELECT salt, verifier, argon2_m_cost, argon2_t_cost, argon2_p_cost FROM auth WHERE id = 1",
+            "SELECT salt, verifier, argon2_m_cost, argon2_t_cost, argon2_p_cost, wrapped_vault_key, wrapped_vault_key_nonce,
+                    metadata_privacy, revision
+             FROM auth WHERE id = 1",
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -228,30 +750,32 @@ impl VaultRepository for PostgresRepository {
                 argon2_m_cost: r.try_get::<i32, _>("argon2_m_cost")? as u32,
                 argon2_t_cost: r.try_get::<i32, _>("argon2_t_cost")? as u32,
                 argon2_p_cost: r.try_get::<i32, _>("argon2_p_cost")? as u32,
+                wrapped_vault_key: r.try_get("wrapped_vault_key")?,
+                wrapped_vault_key_nonce: Self::nonce_from_blob(
+                    r.try_get("wrapped_vault_key_nonce")?,
+                )?,
+                metadata_privacy: r.try_get("metadata_privacy")?,
+                revision: r.try_get::<i64, _>("revision")? as u64,
             })
         })
         .transpose()
     }
 
     async fn set_auth_record(&self, auth: &AuthRecord) -> ChacrabResult<()> {
-        sqlx::query(
-            "INSERT INTO auth (id, salt, verifier, argon2_m_cost, argon2_t_cost, argon2_p_cost)
-             VALUES (1, $1, $2, $3, $4, $5)
-             ON CONFLICT(id) DO UPDATE SET
-               salt = EXCLUDED.salt,
-               verifier = EXCLUDED.verifier,
-               argon2_m_cost = EXCLUDED.argon2_m_cost,
-               argon2_t_cost = EXCLUDED.argon2_t_cost,
-               argon2_p_cost = EXCLUDED.argon2_p_cost",
-        )
-        .bind(&auth.salt)
-        .bind(&auth.verifier)
-        .bind(auth.argon2_m_cost as i32)
-        .bind(auth.argon2_t_cost as i32)
-        .bind(auth.argon2_p_cost as i32)
-        .execute(&self.pool)
-        .await?;
+        Self::write_auth(&self.pool, auth).await
+    }
 
+    async fn replace_auth_and_items(
+        &self,
+        auth: &AuthRecord,
+        items: &[VaultItem],
+    ) -> ChacrabResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for item in items {
+            Self::write_item(&mut tx, item).await?;
+        }
+        Self::write_auth(&mut *tx, auth).await?;
+        tx.commit().await?;
         Ok(())
     }
 }
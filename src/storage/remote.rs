@@ -0,0 +1,251 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Serialize, de::DeserializeOwned};
+use url::{Host, Url};
+use uuid::Uuid;
+
+use crate::{
+    core::{
+        errors::{ChacrabError, ChacrabResult},
+        models::{
+            AuthRecord, ChangeSet, ItemRevision, SyncState, Tombstone, VaultItem, VaultManifest,
+        },
+    },
+    storage::r#trait::VaultRepository,
+    sync::protocol::{
+        API_PREFIX, API_VERSION, ErrorResponse, PruneRequest, ReplaceVaultRequest, StatusResponse,
+    },
+};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+pub struct RemoteRepository {
+    client: Client,
+    base_url: Url,
+    token: Option<Arc<SecretString>>,
+}
+
+impl RemoteRepository {
+    pub fn connect(server_url: &str) -> ChacrabResult<Self> {
+        let invalid = || ChacrabError::Config("invalid sync server url".to_owned());
+        let mut url = Url::parse(server_url).map_err(|_| invalid())?;
+        match url.scheme() {
+            "https" => {}
+            "http" if Self::is_loopback(&url) => {}
+            "http" => {
+                return Err(ChacrabError::Config(
+                    "sync server must use https".to_owned(),
+                ));
+            }
+            _ => return Err(invalid()),
+        }
+
+        let token = url
+            .password()
+            .filter(|token| !token.is_empty())
+            .map(|token| Arc::new(SecretString::new(token.to_owned().into_boxed_str())));
+        url.set_username("").map_err(|_| invalid())?;
+        url.set_password(None).map_err(|_| invalid())?;
+        let path = format!("{}{API_PREFIX}/", url.path().trim_end_matches('/'));
+        url.set_path(&path);
+
+        let client = Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .user_agent(concat!("chacrab/", env!("CARGO_PKG_VERSION")))
+            .build()
+            .map_err(|_| ChacrabError::Storage)?;
+
+        Ok(Self {
+            client,
+            base_url: url,
+            token,
+        })
+    }
+
+    fn is_loopback(url: &Url) -> bool {
+        match url.host() {
+            Some(Host::Domain(domain)) => domain == "localhost",
+            Some(Host::Ipv4(address)) => address.is_loopback(),
+            Some(Host::Ipv6(address)) => address.is_loopback(),
+            None => false,
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> ChacrabResult<RequestBuilder> {
+        let url = self
+            .base_url
+            .join(path)
+            .map_err(|_| ChacrabError::Config("invalid sync server url".to_owned()))?;
+        let request = self.client.request(method, url);
+        Ok(match &self.token {
+            Some(token) => request.bearer_auth(token.expose_secret()),
+            None => request,
+        })
+    }
+
+    async fn send(request: RequestBuilder) -> ChacrabResult<Response> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        Err(match status {
+            StatusCode::UNAUTHORIZED => {
+                ChacrabError::Config("sync server rejected the access token".to_owned())
+            }
+            StatusCode::NOT_FOUND => ChacrabError::NotFound,
+            status if status.is_client_error() => ChacrabError::Config(
+                response
+                    .json::<ErrorResponse>()
+                    .await
+                    .map(|body| body.error)
+                    .unwrap_or_else(|_| "sync server rejected the request".to_owned()),
+            ),
+            _ => ChacrabError::Storage,
+        })
+    }
+
+    async fn fetch<T: DeserializeOwned>(&self, path: &str) -> ChacrabResult<T> {
+        Ok(Self::send(self.request(Method::GET, path)?)
+            .await?
+            .json()
+            .await?)
+    }
+
+    async fn submit<T: Serialize + ?Sized>(
+        &self,
+        method: Method,
+        path: &str,
+        body: &T,
+    ) -> ChacrabResult<()> {
+        Self::send(self.request(method, path)?.json(body)).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl VaultRepository for RemoteRepository {
+    async fn init(&self) -> ChacrabResult<()> {
+        let status: StatusResponse = self.fetch("status").await?;
+        if status.api_version != API_VERSION {
+            return Err(ChacrabError::Config(
+                "unsupported sync server api version".to_owned(),
+            ));
+        }
+        Ok(())
+    }
+
+    async fn is_initialized(&self) -> ChacrabResult<bool> {
+        self.init().await.map(|()| true)
+    }
+
+    async fn has_legacy_items(&self) -> ChacrabResult<bool> {
+        Ok(true)
+    }
+
+    async fn clear_legacy_items(&self) -> ChacrabResult<()> {
+        Ok(())
+    }
+
+    async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()> {
+        self.submit(Method::PUT, &format!("items/{}", item.id), item)
+            .await
+    }
+
+    async fn list_items(&self) -> ChacrabResult<Vec<VaultItem>> {
+        self.fetch("items").await
+    }
+
+    async fn get_item(&self, id: Uuid) -> ChacrabResult<VaultItem> {
+        self.fetch(&format!("items/{id}")).await
+    }
+
+    async fn delete_item(&self, id: Uuid) -> ChacrabResult<()> {
+        Self::send(self.request(Method::DELETE, &format!("items/{id}"))?).await?;
+        Ok(())
+    }
+
+    async fn add_revision(&self, revision: &ItemRevision) -> ChacrabResult<()> {
+        self.submit(
+            Method::POST,
+            &format!("items/{}/revisions", revision.item_id),
+            revision,
+        )
+        .await
+    }
+
+    async fn list_revisions(&self, item_id: Uuid) -> ChacrabResult<Vec<ItemRevision>> {
+        self.fetch(&format!("items/{item_id}/revisions")).await
+    }
+
+    async fn prune_revisions(&self, item_id: Uuid, keep: usize) -> ChacrabResult<()> {
+        self.submit(
+            Method::POST,
+            &format!("items/{item_id}/revisions/prune"),
+            &PruneRequest { keep },
+        )
+        .await
+    }
+
+    async fn list_tombstones(&self) -> ChacrabResult<Vec<Tombstone>> {
+        self.fetch("tombstones").await
+    }
+
+    async fn record_tombstone(&self, tombstone: &Tombstone) -> ChacrabResult<()> {
+        self.submit(
+            Method::PUT,
+            &format!("tombstones/{}", tombstone.id),
+            tombstone,
+        )
+        .await
+    }
+
+    async fn changes_since(&self, cursor: u64) -> ChacrabResult<ChangeSet> {
+        self.fetch(&format!("changes?since={cursor}")).await
+    }
+
+    async fn get_manifest(&self) -> ChacrabResult<Option<VaultManifest>> {
+        self.fetch("manifest").await
+    }
+
+    async fn set_manifest(&self, manifest: &VaultManifest) -> ChacrabResult<()> {
+        self.submit(Method::PUT, "manifest", manifest).await
+    }
+
+    async fn get_sync_state(&self, _: &str) -> ChacrabResult<Option<SyncState>> {
+        Err(ChacrabError::UnsupportedBackend("http".to_owned()))
+    }
+
+    async fn set_sync_state(&self, _: &SyncState) -> ChacrabResult<()> {
+        Err(ChacrabError::UnsupportedBackend("http".to_owned()))
+    }
+
+    async fn get_auth_record(&self) -> ChacrabResult<Option<AuthRecord>> {
+        self.fetch("auth").await
+    }
+
+    async fn set_auth_record(&self, auth: &AuthRecord) -> ChacrabResult<()> {
+        self.submit(Method::PUT, "auth", auth).await
+    }
+
+    async fn replace_auth_and_items(
+        &self,
+        auth: &AuthRecord,
+        items: &[VaultItem],
+    ) -> ChacrabResult<()> {
+        self.submit(
+            Method::PUT,
+            "vault",
+            &ReplaceVaultRequest {
+                auth: auth.clone(),
+                items: items.to_vec(),
+            },
+        )
+        .await
+    }
+}
@@ -1,15 +1,24 @@
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use sqlx::{Row, SqlitePool};
+use std::str::FromStr;
+
+use sqlx::{
+    Executor, Row, Sqlite, SqliteConnection, SqlitePool,
+    sqlite::{SqliteConnectOptions, SqliteRow},
+};
 use uuid::Uuid;
 
 use crate::core::{
     errors::{ChacrabError, ChacrabResult},
-    models::{AuthRecord, VaultItem, VaultItemType},
+    models::{
+        AuthRecord, ChangeSet, ITEM_FORMAT_VERSION, ItemRevision, SyncState, Tombstone, VaultItem,
+        VaultItemType, VaultManifest,
+    },
 };
-use crate::storage::r#trait::VaultRepository;
+use crate::storage::{self, r#trait::VaultRepository};
 
-const SCHEMA_VERSION: i64 = 1;
+const SCHEMA_VERSION: i64 = 15;
+const ITEM_COLUMNS: &str = "id, item_type, title, username, url, encrypted_data, nonce, format_version, encrypted_metadata, metadata_nonce, folder, tags, favorite, deleted_at, revision, base_revision, created_at, updated_at";
 
 #[derive(Clone)]
 pub struct SqliteRepository {
@@ -23,6 +32,17 @@ impl SqliteRepository {
         Ok(Self { pool })
     }
 
+    pub async fn connect_read_only(database_url: &str) -> ChacrabResult<Option<Self>> {
+        let options = SqliteConnectOptions::from_str(database_url)?
+            .read_only(true)
+            .create_if_missing(false);
+        if !options.get_filename().exists() {
+            return Ok(None);
+        }
+        let pool = SqlitePool::connect_with(options).await?;
+        Ok(Some(Self { pool }))
+    }
+
     fn normalize_sqlite_url(database_url: &str) -> String {
         if !database_url.starts_with("sqlite://") {
             return database_url.to_owned();
@@ -40,18 +60,299 @@ impl SqliteRepository {
     }
 
     fn parse_item_type(value: &str) -> ChacrabResult<VaultItemType> {
-        match value {
-            "password" => Ok(VaultItemType::Password),
-            "note" => Ok(VaultItemType::Note),
-            _ => Err(ChacrabError::Storage),
-        }
+        value.parse()
     }
 
     fn item_type_to_str(item_type: &VaultItemType) -> &'static str {
-        match item_type {
-            VaultItemType::Password => "password",
-            VaultItemType::Note => "note",
+        item_type.as_str()
+    }
+
+    fn nonce_from_blob(blob: Option<Vec<u8>>) -> ChacrabResult<Option<[u8; 12]>> {
+        blob.map(|bytes| <[u8; 12]>::try_from(bytes.as_slice()).map_err(|_| ChacrabError::Storage))
+            .transpose()
+    }
+
+    fn item_from_row(row: &SqliteRow) -> ChacrabResult<VaultItem> {
+        let nonce_blob: Vec<u8> = row.try_get("nonce")?;
+        if nonce_blob.len() != 12 {
+            return Err(ChacrabError::Storage);
+        }
+        let mut nonce = [0u8; 12];
+        nonce.copy_from_slice(&nonce_blob);
+
+        let id_text: String = row.try_get("id")?;
+        let item_type_text: String = row.try_get("item_type")?;
+        let tags_text: String = row.try_get("tags")?;
+        let created_at_text: String = row.try_get("created_at")?;
+        let updated_at_text: String = row.try_get("updated_at")?;
+        let deleted_at_text: Option<String> = row.try_get("deleted_at")?;
+
+        let created_at = DateTime::parse_from_rfc3339(&created_at_text)
+            .map_err(|_| ChacrabError::Storage)?
+            .with_timezone(&Utc);
+        let updated_at = DateTime::parse_from_rfc3339(&updated_at_text)
+            .map_err(|_| ChacrabError::Storage)?
+            .with_timezone(&Utc);
+
+        Ok(VaultItem {
+            id: Uuid::parse_str(&id_text).map_err(|_| ChacrabError::Storage)?,
+            r#type: Self::parse_item_type(&item_type_text)?,
+            title: row.try_get("title")?,
+            username: row.try_get("username")?,
+            url: row.try_get("url")?,
+            encrypted_data: row.try_get("encrypted_data")?,
+            nonce,
+            encrypted_metadata: row.try_get("encrypted_metadata")?,
+            metadata_nonce: Self::nonce_from_blob(row.try_get("metadata_nonce")?)?,
+            format_version: row.try_get("format_version")?,
+            folder: row.try_get("folder")?,
+            tags: serde_json::from_str(&tags_text).map_err(|_| ChacrabError::Storage)?,
+            favorite: row.try_get("favorite")?,
+            deleted_at: deleted_at_text
+                .map(|text| {
+                    DateTime::parse_from_rfc3339(&text)
+                        .map(|deleted_at| deleted_at.with_timezone(&Utc))
+                        .map_err(|_| ChacrabError::Storage)
+                })
+                .transpose()?,
+            revision: row.try_get::<i64, _>("revision")? as u64,
+            base_revision: row.try_get::<i64, _>("base_revision")? as u64,
+            created_at,
+            updated_at,
+        })
+    }
+
+    fn tombstone_from_row(row: &SqliteRow) -> ChacrabResult<Tombstone> {
+        let id_text: String = row.try_get("id")?;
+        let deleted_at_text: String = row.try_get("deleted_at")?;
+        Ok(Tombstone {
+            id: Uuid::parse_str(&id_text).map_err(|_| ChacrabError::Storage)?,
+            deleted_at: DateTime::parse_from_rfc3339(&deleted_at_text)
+                .map_err(|_| ChacrabError::Storage)?
+                .with_timezone(&Utc),
+            revision: row.try_get::<i64, _>("revision")? as u64,
+        })
+    }
+
+    fn revision_from_row(row: &SqliteRow) -> ChacrabResult<ItemRevision> {
+        let item_id_text: String = row.try_get("item_id")?;
+        let nonce_blob: Vec<u8> = row.try_get("nonce")?;
+        let created_at_text: String = row.try_get("created_at")?;
+
+        Ok(ItemRevision {
+            item_id: Uuid::parse_str(&item_id_text).map_err(|_| ChacrabError::Storage)?,
+            revision: row.try_get("revision")?,
+            encrypted_snapshot: row.try_get("encrypted_snapshot")?,
+            nonce: Self::nonce_from_blob(Some(nonce_blob))?.ok_or(ChacrabError::Storage)?,
+            created_at: DateTime::parse_from_rfc3339(&created_at_text)
+                .map_err(|_| ChacrabError::Storage)?
+                .with_timezone(&Utc),
+        })
+    }
+
+    async fn migrate(&self, from_version: i64) -> ChacrabResult<()> {
+        if from_version < 2 {
+            sqlx::query("ALTER TABLE auth ADD COLUMN wrapped_vault_key BLOB")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("ALTER TABLE auth ADD COLUMN wrapped_vault_key_nonce BLOB")
+                .execute(&self.pool)
+                .await?;
+        }
+        if from_version < 3 {
+            sqlx::query(
+                "ALTER TABLE vault_items ADD COLUMN format_version INTEGER NOT NULL DEFAULT 1",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        if from_version < 4 {
+            sqlx::query("ALTER TABLE auth ADD COLUMN metadata_privacy INTEGER NOT NULL DEFAULT 0")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("ALTER TABLE vault_items ADD COLUMN encrypted_metadata BLOB")
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("ALTER TABLE vault_items ADD COLUMN metadata_nonce BLOB")
+                .execute(&self.pool)
+                .await?;
+        }
+        if from_version < 5 {
+            for statement in [
+                "ALTER TABLE vault_items ADD COLUMN folder TEXT",
+                "ALTER TABLE vault_items ADD COLUMN tags TEXT NOT NULL DEFAULT '[]'",
+                "ALTER TABLE vault_items ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0",
+            ] {
+                sqlx::query(statement).execute(&self.pool).await?;
+            }
+        }
+        if from_version < 7 {
+            sqlx::query("ALTER TABLE vault_items ADD COLUMN deleted_at TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        if from_version < 9 {
+            for statement in [
+                "ALTER TABLE vault_items ADD COLUMN revision INTEGER NOT NULL DEFAULT 0",
+                "ALTER TABLE vault_items ADD COLUMN base_revision INTEGER NOT NULL DEFAULT 0",
+            ] {
+                sqlx::query(statement).execute(&self.pool).await?;
+            }
+        }
+        if from_version < 11 {
+            sqlx::query("ALTER TABLE vault_items ADD COLUMN change_seq INTEGER NOT NULL DEFAULT 1")
+                .execute(&self.pool)
+                .await?;
+            if from_version >= 8 {
+                sqlx::query(
+                    "ALTER TABLE tombstones ADD COLUMN change_seq INTEGER NOT NULL DEFAULT 1",
+                )
+                .execute(&self.pool)
+                .await?;
+            }
+        }
+        if (11..12).contains(&from_version) {
+            sqlx::query("ALTER TABLE sync_state ADD COLUMN manifest TEXT")
+                .execute(&self.pool)
+                .await?;
+        }
+        if from_version < 13 {
+            sqlx::query(
+                "ALTER TABLE schema_meta ADD COLUMN legacy_items INTEGER NOT NULL DEFAULT 1",
+            )
+            .execute(&self.pool)
+            .await?;
+        }
+        if (8..14).contains(&from_version) {
+            sqlx::query("ALTER TABLE tombstones ADD COLUMN revision INTEGER NOT NULL DEFAULT 0")
+                .execute(&self.pool)
+                .await?;
+        }
+        if from_version < 15 {
+            sqlx::query("ALTER TABLE auth ADD COLUMN revision INTEGER NOT NULL DEFAULT 0")
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn next_change_seq(conn: &mut SqliteConnection) -> ChacrabResult<i64> {
+        Ok(sqlx::query_scalar(
+            "UPDATE change_sequence SET value = value + 1 WHERE id = 1 RETURNING value",
+        )
+        .fetch_one(conn)
+        .await?)
+    }
+
+    async fn write_item(conn: &mut SqliteConnection, item: &VaultItem) -> ChacrabResult<()> {
+        let change_seq = Self::next_change_seq(&mut *conn).await?;
+        sqlx::query(
+            "INSERT INTO vault_items (id, item_type, title, username, url, encrypted_data, nonce, format_version, encrypted_metadata, metadata_nonce, folder, tags, favorite, deleted_at, revision, base_revision, created_at, updated_at, change_seq)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
+             ON CONFLICT(id) DO UPDATE SET
+               item_type=excluded.item_type,
+               title=excluded.title,
+               username=excluded.username,
+               url=excluded.url,
+               encrypted_data=excluded.encrypted_data,
+               nonce=excluded.nonce,
+               format_version=excluded.format_version,
+               encrypted_metadata=excluded.encrypted_metadata,
+               metadata_nonce=excluded.metadata_nonce,
+               folder=excluded.folder,
+               tags=excluded.tags,
+               favorite=excluded.favorite,
+               deleted_at=excluded.deleted_at,
+               revision=excluded.revision,
+               base_revision=excluded.base_revision,
+               created_at=excluded.created_at,
+               updated_at=excluded.updated_at,
+               change_seq=excluded.change_seq",
+        )
+        .bind(item.id.to_string())
+        .bind(Self::item_type_to_str(&item.r#type))
+        .bind(&item.title)
+        .bind(&item.username)
+        .bind(&item.url)
+        .bind(&item.encrypted_data)
+        .bind(item.nonce.to_vec())
+        .bind(item.format_version)
+        .bind(&item.encrypted_metadata)
+        .bind(item.metadata_nonce.map(|nonce| nonce.to_vec()))
+        .bind(&item.folder)
+        .bind(serde_json::to_string(&item.tags)?)
+        .bind(item.favorite)
+        .bind(item.deleted_at.map(|deleted_at| deleted_at.to_rfc3339()))
+        .bind(item.revision as i64)
+        .bind(item.base_revision as i64)
+        .bind(item.created_at.to_rfc3339())
+        .bind(item.updated_at.to_rfc3339())
+        .bind(change_seq)
+        .execute(&mut *conn)
+        .await?;
+
+        if item.format_version < ITEM_FORMAT_VERSION {
+            sqlx::query("UPDATE schema_meta SET legacy_items = 1 WHERE id = 1")
+                .execute(conn)
+                .await?;
         }
+        Ok(())
+    }
+
+    async fn write_tombstone(
+        conn: &mut SqliteConnection,
+        tombstone: &Tombstone,
+    ) -> ChacrabResult<()> {
+        let change_seq = Self::next_change_seq(&mut *conn).await?;
+        sqlx::query(
+            "INSERT INTO tombstones (id, deleted_at, revision, change_seq)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(id) DO UPDATE SET
+               deleted_at = excluded.deleted_at,
+               revision = excluded.revision,
+               change_seq = excluded.change_seq",
+        )
+        .bind(tombstone.id.to_string())
+        .bind(tombstone.deleted_at.to_rfc3339())
+        .bind(tombstone.revision as i64)
+        .bind(change_seq)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn write_auth<'e, E>(executor: E, auth: &AuthRecord) -> ChacrabResult<()>
+    where
+        E: Executor<'e, Database = Sqlite>,
+    {
+        sqlx::query(
+            "INSERT INTO auth (id, salt, verifier, argon2_m_cost, argon2_t_cost, argon2_p_cost, wrapped_vault_key, wrapped_vault_key_nonce, metadata_privacy, revision)
+             VALUES (1, ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+             ON CONFLICT(id) DO UPDATE SET
+               salt=excluded.salt,
+               verifier=excluded.verifier,
+               argon2_m_cost=excluded.argon2_m_cost,
+               argon2_t_cost=excluded.argon2_t_cost,
+               argon2_p_cost=excluded.argon2_p_cost,
+               wrapped_vault_key=excluded.wrapped_vault_key,
+               wrapped_vault_key_nonce=excluded.wrapped_vault_key_nonce,
+               metadata_privacy=excluded.metadata_privacy,
+               revision=excluded.revision",
+        )
+        .bind(&auth.salt)
+        .bind(&auth.verifier)
+        .bind(auth.argon2_m_cost)
+        .bind(auth.argon2_t_cost)
+        .bind(auth.argon2_p_cost)
+        .bind(&auth.wrapped_vault_key)
+        .bind(auth.wrapped_vault_key_nonce.map(|nonce| nonce.to_vec()))
+        .bind(auth.metadata_privacy)
+        .bind(auth.revision as i64)
+        .execute(executor)
+        .await?;
+
+        Ok(())
     }
 }
 
@@ -61,20 +362,17 @@ impl VaultRepository for SqliteRepository {
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS schema_meta (
                 id INTEGER PRIMARY KEY,
-                schema_version INTEGER NOT NULL
+                schema_version INTEGER NOT NULL,
+                legacy_items INTEGER NOT NULL DEFAULT 1
             )",
         )
         .execute(&self.pool)
         .await?;
 
-        sqlx::query(
-            "INSERT INTO schema_meta (id, schema_version)
-             VALUES (1, ?1)
-             ON CONFLICT(id) DO UPDATE SET schema_version = excluded.schema_version",
-        )
-        .bind(SCHEMA_VERSION)
-        .execute(&self.pool)
-        .await?;
+        let stored_version =
+            sqlx::query_scalar::<_, i64>("SELECT schema_version FROM schema_meta WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await?;
 
         sqlx::query(
             "CREATE TABLE IF NOT EXISTS auth (
@@ -83,7 +381,11 @@ impl VaultRepository for SqliteRepository {
                 verifier TEXT NOT NULL,
                 argon2_m_cost INTEGER NOT NULL,
                 argon2_t_cost INTEGER NOT NULL,
-                argon2_p_cost INTEGER NOT NULL
+                argon2_p_cost INTEGER NOT NULL,
+                wrapped_vault_key BLOB,
+                wrapped_vault_key_nonce BLOB,
+                metadata_privacy INTEGER NOT NULL DEFAULT 0,
+                revision INTEGER NOT NULL DEFAULT 0
             )",
         )
         .execute(&self.pool)
@@ -98,143 +400,375 @@ impl VaultRepository for SqliteRepository {
                 url TEXT,
                 encrypted_data BLOB NOT NULL,
                 nonce BLOB NOT NULL,
+                format_version INTEGER NOT NULL DEFAULT 1,
+                encrypted_metadata BLOB,
+                metadata_nonce BLOB,
+                folder TEXT,
+                tags TEXT NOT NULL DEFAULT '[]',
+                favorite INTEGER NOT NULL DEFAULT 0,
+                deleted_at TEXT,
+                revision INTEGER NOT NULL DEFAULT 0,
+                base_revision INTEGER NOT NULL DEFAULT 0,
                 created_at TEXT NOT NULL,
-                updated_at TEXT NOT NULL
+                updated_at TEXT NOT NULL,
+                change_seq INTEGER NOT NULL DEFAULT 1
             )",
         )
         .execute(&self.pool)
         .await?;
 
-        Ok(())
-    }
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS item_revisions (
+                item_id TEXT NOT NULL,
+                revision INTEGER NOT NULL,
+                encrypted_snapshot BLOB NOT NULL,
+                nonce BLOB NOT NULL,
+                created_at TEXT NOT NULL,
+                PRIMARY KEY (item_id, revision)
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
 
-    async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()> {
         sqlx::query(
-            "INSERT INTO vault_items (id, item_type, title, username, url, encrypted_data, nonce, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
-             ON CONFLICT(id) DO UPDATE SET
-               item_type=excluded.item_type,
-               title=excluded.title,
-               username=excluded.username,
-               url=excluded.url,
-               encrypted_data=excluded.encrypted_data,
-               nonce=excluded.nonce,
-               created_at=excluded.created_at,
-               updated_at=excluded.updated_at",
+            "CREATE TABLE IF NOT EXISTS tombstones (
+                id TEXT PRIMARY KEY,
+                deleted_at TEXT NOT NULL,
+                revision INTEGER NOT NULL DEFAULT 0,
+                change_seq INTEGER NOT NULL DEFAULT 1
+            )",
         )
-        .bind(item.id.to_string())
-        .bind(Self::item_type_to_str(&item.r#type))
-        .bind(&item.title)
-        .bind(&item.username)
-        .bind(&item.url)
-        .bind(&item.encrypted_data)
-        .bind(item.nonce.to_vec())
-        .bind(item.created_at.to_rfc3339())
-        .bind(item.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS sync_manifest (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                generation INTEGER NOT NULL,
+                entries TEXT NOT NULL,
+                mac TEXT NOT NULL
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        for statement in [
+            "CREATE TABLE IF NOT EXISTS change_sequence (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                value INTEGER NOT NULL
+            )",
+            "INSERT INTO change_sequence (id, value) VALUES (1, 1) ON CONFLICT(id) DO NOTHING",
+            "CREATE TABLE IF NOT EXISTS sync_state (
+                remote TEXT PRIMARY KEY,
+                local_cursor INTEGER NOT NULL,
+                remote_cursor INTEGER NOT NULL,
+                manifest TEXT
+            )",
+        ] {
+            sqlx::query(statement).execute(&self.pool).await?;
+        }
+
+        if let Some(version) = stored_version {
+            self.migrate(version).await?;
+        }
+
+        for statement in [
+            "CREATE INDEX IF NOT EXISTS vault_items_change_seq ON vault_items (change_seq)",
+            "CREATE INDEX IF NOT EXISTS tombstones_change_seq ON tombstones (change_seq)",
+        ] {
+            sqlx::query(statement).execute(&self.pool).await?;
+        }
+
+        sqlx::query(
+            "INSERT INTO schema_meta (id, schema_version)
+             VALUES (1, ?1)
+             ON CONFLICT(id) DO UPDATE SET schema_version = excluded.schema_version",
+        )
+        .bind(SCHEMA_VERSION)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    async fn list_items(&self) -> ChacrabResult<Vec<VaultItem>> {
-        let rows = sqlx::query(
-            "SELECT id, item_type, title, username, url, encrypted_data, nonce, created_at, updated_at FROM vault_items ORDER BY updated_at DESC",
+    async fn is_initialized(&self) -> ChacrabResult<bool> {
+        let tables = sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'schema_meta'",
         )
+        .fetch_one(&self.pool)
+        .await?;
+        if tables == 0 {
+            return Ok(false);
+        }
+        let version =
+            sqlx::query_scalar::<_, i64>("SELECT schema_version FROM schema_meta WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(version == Some(SCHEMA_VERSION))
+    }
+
+    async fn has_legacy_items(&self) -> ChacrabResult<bool> {
+        let flag =
+            sqlx::query_scalar::<_, bool>("SELECT legacy_items FROM schema_meta WHERE id = 1")
+                .fetch_optional(&self.pool)
+                .await?;
+        Ok(flag.unwrap_or(true))
+    }
+
+    async fn clear_legacy_items(&self) -> ChacrabResult<()> {
+        sqlx::query("UPDATE schema_meta SET legacy_items = 0 WHERE id = 1")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()> {
+        let mut tx = self.pool.begin().await?;
+        Self::write_item(&mut tx, item).await?;
+        sqlx::query("DELETE FROM tombstones WHERE id = ?1")
+            .bind(item.id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_items(&self) -> ChacrabResult<Vec<VaultItem>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {ITEM_COLUMNS} FROM vault_items ORDER BY updated_at DESC"
+        ))
         .fetch_all(&self.pool)
         .await?;
 
-        rows.into_iter()
-            .map(|row| {
-                let nonce_blob: Vec<u8> = row.try_get("nonce")?;
-                if nonce_blob.len() != 12 {
-                    return Err(ChacrabError::Storage);
-                }
-                let mut nonce = [0u8; 12];
-                nonce.copy_from_slice(&nonce_blob);
-
-                let id_text: String = row.try_get("id")?;
-                let item_type_text: String = row.try_get("item_type")?;
-                let created_at_text: String = row.try_get("created_at")?;
-                let updated_at_text: String = row.try_get("updated_at")?;
-
-                let created_at = DateTime::parse_from_rfc3339(&created_at_text)
-                    .map_err(|_| ChacrabError::Storage)?
-                    .with_timezone(&Utc);
-                let updated_at = DateTime::parse_from_rfc3339(&updated_at_text)
-                    .map_err(|_| ChacrabError::Storage)?
-                    .with_timezone(&Utc);
-
-                Ok(VaultItem {
-                    id: Uuid::parse_str(&id_text).map_err(|_| ChacrabError::Storage)?,
-                    r#type: Self::parse_item_type(&item_type_text)?,
-                    title: row.try_get("title")?,
-                    username: row.try_get("username")?,
-                    url: row.try_get("url")?,
-                    encrypted_data: row.try_get("encrypted_data")?,
-                    nonce,
-                    created_at,
-                    updated_at,
-                })
-            })
-            .collect::<Result<Vec<_>, ChacrabError>>()
+        storage::readable_items(rows.iter().map(Self::item_from_row))
     }
 
     async fn get_item(&self, id: Uuid) -> ChacrabResult<VaultItem> {
-        let row = sqlx::query(
-            "SELECT id, item_type, title, username, url, encrypted_data, nonce, created_at, updated_at FROM vault_items WHERE id = ?1",
-        )
+        let row = sqlx::query(&format!(
+            "SELECT {ITEM_COLUMNS} FROM vault_items WHERE id = ?1"
+        ))
         .bind(id.to_string())
         .fetch_optional(&self.pool)
         .await?
         .ok_or(ChacrabError::NotFound)?;
 
-        let nonce_blob: Vec<u8> = row.try_get("nonce")?;
-        if nonce_blob.len() != 12 {
-            return Err(ChacrabError::Storage);
-        }
-        let mut nonce = [0u8; 12];
-        nonce.copy_from_slice(&nonce_blob);
+        Self::item_from_row(&row)
+    }
 
-        let item_type_text: String = row.try_get("item_type")?;
-        let created_at_text: String = row.try_get("created_at")?;
-        let updated_at_text: String = row.try_get("updated_at")?;
+    async fn delete_item(&self, id: Uuid) -> ChacrabResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let revision: i64 =
+            sqlx::query_scalar("DELETE FROM vault_items WHERE id = ?1 RETURNING revision")
+                .bind(id.to_string())
+                .fetch_optional(&mut *tx)
+                .await?
+                .ok_or(ChacrabError::NotFound)?;
+        sqlx::query("DELETE FROM item_revisions WHERE item_id = ?1")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        Self::write_tombstone(
+            &mut tx,
+            &Tombstone {
+                id,
+                deleted_at: Utc::now(),
+                revision: revision as u64,
+            },
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
 
-        let created_at = DateTime::parse_from_rfc3339(&created_at_text)
-            .map_err(|_| ChacrabError::Storage)?
-            .with_timezone(&Utc);
-        let updated_at = DateTime::parse_from_rfc3339(&updated_at_text)
-            .map_err(|_| ChacrabError::Storage)?
-            .with_timezone(&Utc);
+    async fn add_revision(&self, revision: &ItemRevision) -> ChacrabResult<()> {
+        sqlx::query(
+            "INSERT INTO item_revisions (item_id, revision, encrypted_snapshot, nonce, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(revision.item_id.to_string())
+        .bind(revision.revision)
+        .bind(&revision.encrypted_snapshot)
+        .bind(revision.nonce.to_vec())
+        .bind(revision.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
 
-        Ok(VaultItem {
-            id,
-            r#type: Self::parse_item_type(&item_type_text)?,
-            title: row.try_get("title")?,
-            username: row.try_get("username")?,
-            url: row.try_get("url")?,
-            encrypted_data: row.try_get("encrypted_data")?,
-            nonce,
-            created_at,
-            updated_at,
+        Ok(())
+    }
+
+    async fn list_revisions(&self, item_id: Uuid) -> ChacrabResult<Vec<ItemRevision>> {
+        let rows = sqlx::query(
+            "SELECT item_id, revision, encrypted_snapshot, nonce, created_at
+             FROM item_revisions WHERE item_id = ?1 ORDER BY revision ASC",
+        )
+        .bind(item_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(Self::revision_from_row).collect()
+    }
+
+    async fn prune_revisions(&self, item_id: Uuid, keep: usize) -> ChacrabResult<()> {
+        sqlx::query(
+            "DELETE FROM item_revisions
+             WHERE item_id = ?1 AND revision NOT IN (
+               SELECT revision FROM item_revisions WHERE item_id = ?1
+               ORDER BY revision DESC LIMIT ?2
+             )",
+        )
+        .bind(item_id.to_string())
+        .bind(i64::try_from(keep).unwrap_or(i64::MAX))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_tombstones(&self) -> ChacrabResult<Vec<Tombstone>> {
+        let rows =
+            sqlx::query("SELECT id, deleted_at, revision FROM tombstones ORDER BY deleted_at ASC")
+                .fetch_all(&self.pool)
+                .await?;
+
+        rows.iter().map(Self::tombstone_from_row).collect()
+    }
+
+    async fn record_tombstone(&self, tombstone: &Tombstone) -> ChacrabResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for statement in [
+            "DELETE FROM vault_items WHERE id = ?1",
+            "DELETE FROM item_revisions WHERE item_id = ?1",
+        ] {
+            sqlx::query(statement)
+                .bind(tombstone.id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+        Self::write_tombstone(&mut tx, tombstone).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn changes_since(&self, cursor: u64) -> ChacrabResult<ChangeSet> {
+        let since = i64::try_from(cursor).unwrap_or(i64::MAX);
+        let mut tx = self.pool.begin().await?;
+        let current: i64 = sqlx::query_scalar("SELECT value FROM change_sequence WHERE id = 1")
+            .fetch_one(&mut *tx)
+            .await?;
+        let item_rows = sqlx::query(&format!(
+            "SELECT {ITEM_COLUMNS} FROM vault_items WHERE change_seq > ?1 ORDER BY change_seq ASC"
+        ))
+        .bind(since)
+        .fetch_all(&mut *tx)
+        .await?;
+        let tombstone_rows = sqlx::query(
+            "SELECT id, deleted_at, revision FROM tombstones WHERE change_seq > ?1 ORDER BY change_seq ASC",
+        )
+        .bind(since)
+        .fetch_all(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(ChangeSet {
+            items: item_rows
+                .iter()
+                .map(Self::item_from_row)
+                .collect::<ChacrabResult<_>>()?,
+            tombstones: tombstone_rows
+                .iter()
+                .map(Self::tombstone_from_row)
+                .collect::<ChacrabResult<_>>()?,
+            cursor: current as u64,
         })
     }
 
-    async fn delete_item(&self, id: Uuid) -> ChacrabResult<()> {
-        let result = sqlx::query("DELETE FROM vault_items WHERE id = ?1")
-            .bind(id.to_string())
-            .execute(&self.pool)
+    async fn get_manifest(&self) -> ChacrabResult<Option<VaultManifest>> {
+        let row = sqlx::query("SELECT generation, entries, mac FROM sync_manifest WHERE id = 1")
+            .fetch_optional(&self.pool)
             .await?;
 
-        if result.rows_affected() == 0 {
-            return Err(ChacrabError::NotFound);
-        }
+        row.map(|r| {
+            let entries_text: String = r.try_get("entries")?;
+            Ok(VaultManifest {
+                generation: r.try_get::<i64, _>("generation")? as u64,
+                entries: serde_json::from_str(&entries_text).map_err(|_| ChacrabError::Storage)?,
+                mac: r.try_get("mac")?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn set_manifest(&self, manifest: &VaultManifest) -> ChacrabResult<()> {
+        sqlx::query(
+            "INSERT INTO sync_manifest (id, generation, entries, mac)
+             VALUES (1, ?1, ?2, ?3)
+             ON CONFLICT(id) DO UPDATE SET
+               generation=excluded.generation,
+               entries=excluded.entries,
+               mac=excluded.mac",
+        )
+        .bind(manifest.generation as i64)
+        .bind(serde_json::to_string(&manifest.entries)?)
+        .bind(&manifest.mac)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_sync_state(&self, remote: &str) -> ChacrabResult<Option<SyncState>> {
+        let row = sqlx::query(
+            "SELECT remote, local_cursor, remote_cursor, manifest FROM sync_state WHERE remote = ?1",
+        )
+        .bind(remote)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|r| {
+            Ok(SyncState {
+                remote: r.try_get("remote")?,
+                local_cursor: r.try_get::<i64, _>("local_cursor")? as u64,
+                remote_cursor: r.try_get::<i64, _>("remote_cursor")? as u64,
+                manifest: r
+                    .try_get::<Option<String>, _>("manifest")?
+                    .map(|manifest| serde_json::from_str(&manifest))
+                    .transpose()
+                    .map_err(|_| ChacrabError::Storage)?,
+            })
+        })
+        .transpose()
+    }
+
+    async fn set_sync_state(&self, state: &SyncState) -> ChacrabResult<()> {
+        sqlx::query(
+            "INSERT INTO sync_state (remote, local_cursor, remote_cursor, manifest)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(remote) DO UPDATE SET
+               local_cursor=excluded.local_cursor,
+               remote_cursor=excluded.remote_cursor,
+               manifest=excluded.manifest",
+        )
+        .bind(&state.remote)
+        .bind(state.local_cursor as i64)
+        .bind(state.remote_cursor as i64)
+        .bind(
+            state
+                .manifest
+                .as_ref()
+                .map(serde_json::to_string)
+                .transpose()?,
+        )
+        .execute(&self.pool)
+        .await?;
+
         Ok(())
     }
 
     async fn get_auth_record(&self) -> ChacrabResult<Option<AuthRecord>> {
         let row = sqlx::query(
-            "SELECT salt, verifier, argon2_m_cost, argon2_t_cost, argon2_p_cost FROM auth WHERE id = 1",
+            "SELECT salt, verifier, argon2_m_cost, argon2_t_cost, argon2_p_cost, wrapped_vault_key, wrapped_vault_key_nonce,
+                    metadata_privacy, revision
+             FROM auth WHERE id = 1",
         )
         .fetch_optional(&self.pool)
         .await?;
@@ -246,30 +780,32 @@ impl VaultRepository for SqliteRepository {
                 argon2_m_cost: r.try_get("argon2_m_cost")?,
                 argon2_t_cost: r.try_get("argon2_t_cost")?,
                 argon2_p_cost: r.try_get("argon2_p_cost")?,
+                wrapped_vault_key: r.try_get("wrapped_vault_key")?,
+                wrapped_vault_key_nonce: Self::nonce_from_blob(
+                    r.try_get("wrapped_vault_key_nonce")?,
+                )?,
+                metadata_privacy: r.try_get("metadata_privacy")?,
+                revision: r.try_get::<i64, _>("revision")? as u64,
             })
         })
         .transpose()
     }
 
     async fn set_auth_record(&self, auth: &AuthRecord) -> ChacrabResult<()> {
-        sqlx::query(
-            "INSERT INTO auth (id, salt, verifier, argon2_m_cost, argon2_t_cost, argon2_p_cost)
-             VALUES (1, ?1, ?2, ?3, ?4, ?5)
-             ON CONFLICT(id) DO UPDATE SET
-               salt=excluded.salt,
-               verifier=excluded.verifier,
-               argon2_m_cost=excluded.argon2_m_cost,
-               argon2_t_cost=excluded.argon2_t_cost,
-               argon2_p_cost=excluded.argon2_p_cost",
-        )
-        .bind(&auth.salt)
-        .bind(&auth.verifier)
-        .bind(auth.argon2_m_cost)
-        .bind(auth.argon2_t_cost)
-        .bind(auth.argon2_p_cost)
-        .execute(&self.pool)
-        .await?;
+        Self::write_auth(&self.pool, auth).await
+    }
 
+    async fn replace_auth_and_items(
+        &self,
+        auth: &AuthRecord,
+        items: &[VaultItem],
+    ) -> ChacrabResult<()> {
+        let mut tx = self.pool.begin().await?;
+        for item in items {
+            Self::write_item(&mut tx, item).await?;
+        }
+        Self::write_auth(&mut *tx, auth).await?;
+        tx.commit().await?;
         Ok(())
     }
 }
@@ -3,17 +3,38 @@ use uuid::Uuid;
 
 use crate::core::{
     errors::ChacrabResult,
-    models::{AuthRecord, VaultItem},
+    models::{AuthRecord, ChangeSet, ItemRevision, SyncState, Tombstone, VaultItem, VaultManifest},
 };
 
 #[async_trait]
 pub trait VaultRepository: Send + Sync {
     async fn init(&self) -> ChacrabResult<()>;
+    async fn is_initialized(&self) -> ChacrabResult<bool>;
+    async fn has_legacy_items(&self) -> ChacrabResult<bool>;
+    async fn clear_legacy_items(&self) -> ChacrabResult<()>;
     async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()>;
     async fn list_items(&self) -> ChacrabResult<Vec<VaultItem>>;
     async fn get_item(&self, id: Uuid) -> ChacrabResult<VaultItem>;
     async fn delete_item(&self, id: Uuid) -> ChacrabResult<()>;
 
+    async fn add_revision(&self, revision: &ItemRevision) -> ChacrabResult<()>;
+    async fn list_revisions(&self, item_id: Uuid) -> ChacrabResult<Vec<ItemRevision>>;
+    async fn prune_revisions(&self, item_id: Uuid, keep: usize) -> ChacrabResult<()>;
+
+    async fn list_tombstones(&self) -> ChacrabResult<Vec<Tombstone>>;
+    async fn record_tombstone(&self, tombstone: &Tombstone) -> ChacrabResult<()>;
+    async fn changes_since(&self, cursor: u64) -> ChacrabResult<ChangeSet>;
+
+    async fn get_manifest(&self) -> ChacrabResult<Option<VaultManifest>>;
+    async fn set_manifest(&self, manifest: &VaultManifest) -> ChacrabResult<()>;
+    async fn get_sync_state(&self, remote: &str) -> ChacrabResult<Option<SyncState>>;
+    async fn set_sync_state(&self, state: &SyncState) -> ChacrabResult<()>;
+
     async fn get_auth_record(&self) -> ChacrabResult<Option<AuthRecord>>;
     async fn set_auth_record(&self, auth: &AuthRecord) -> ChacrabResult<()>;
+    async fn replace_auth_and_items(
+        &self,
+        auth: &AuthRecord,
+        items: &[VaultItem],
+    ) -> ChacrabResult<()>;
 }
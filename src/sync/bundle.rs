@@ -0,0 +1,94 @@
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::core::{
+    crypto,
+    errors::{ChacrabError, ChacrabResult},
+    models::{AuthRecord, Tombstone, VaultItem, VaultManifest},
+};
+
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+const BUNDLE_CONTEXT: &[u8] = b"chacrab-sync-bundle-v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeBundle {
+    pub exported_at: String,
+    pub since: u64,
+    pub sequence: u64,
+    pub auth: AuthRecord,
+    pub manifest: VaultManifest,
+    pub items: Vec<VaultItem>,
+    pub tombstones: Vec<Tombstone>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EncryptedBundleFile {
+    pub format_version: u32,
+    pub nonce_b64: String,
+    pub ciphertext_b64: String,
+}
+
+impl ChangeBundle {
+    pub fn new(
+        since: u64,
+        sequence: u64,
+        auth: AuthRecord,
+        manifest: VaultManifest,
+        items: Vec<VaultItem>,
+        tombstones: Vec<Tombstone>,
+    ) -> Self {
+        Self {
+            exported_at: Utc::now().to_rfc3339(),
+            since,
+            sequence,
+            auth,
+            manifest,
+            items,
+            tombstones,
+        }
+    }
+}
+
+pub fn seal(
+    bundle: &ChangeBundle,
+    key: &[u8; crypto::KEY_SIZE],
+) -> ChacrabResult<EncryptedBundleFile> {
+    let mut serialized = serde_json::to_vec(bundle)?;
+    let encrypted = crypto::encrypt_with_aad(key, &serialized, BUNDLE_CONTEXT);
+    crypto::zeroize_vec(&mut serialized);
+    let encrypted = encrypted?;
+
+    Ok(EncryptedBundleFile {
+        format_version: BUNDLE_FORMAT_VERSION,
+        nonce_b64: STANDARD.encode(encrypted.nonce),
+        ciphertext_b64: STANDARD.encode(encrypted.ciphertext),
+    })
+}
+
+pub fn open(
+    bundle_file: &EncryptedBundleFile,
+    key: &[u8; crypto::KEY_SIZE],
+) -> ChacrabResult<ChangeBundle> {
+    if bundle_file.format_version != BUNDLE_FORMAT_VERSION {
+        return Err(ChacrabError::Config(
+            "unsupported sync bundle format version".to_owned(),
+        ));
+    }
+
+    let nonce_bytes = STANDARD
+        .decode(bundle_file.nonce_b64.as_bytes())
+        .map_err(|_| ChacrabError::Serialization)?;
+    let nonce: [u8; crypto::NONCE_SIZE] = nonce_bytes
+        .try_into()
+        .map_err(|_| ChacrabError::Serialization)?;
+    let ciphertext = STANDARD
+        .decode(bundle_file.ciphertext_b64.as_bytes())
+        .map_err(|_| ChacrabError::Serialization)?;
+
+    let mut plaintext = crypto::decrypt_with_aad(key, &nonce, &ciphertext, BUNDLE_CONTEXT)
+        .map_err(|_| ChacrabError::Config("sync bundle could not be decrypted".to_owned()))?;
+    let bundle = serde_json::from_slice(&plaintext).map_err(Into::into);
+    crypto::zeroize_vec(&mut plaintext);
+    bundle
+}
@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use zeroize::Zeroize;
+
+use crate::core::{
+    crypto,
+    errors::{ChacrabError, ChacrabResult},
+    models::{ManifestEntry, VaultItem, VaultManifest},
+};
+
+const MAC_CONTEXT: &[u8] = b"chacrab-sync-manifest-v1";
+
+pub fn item_digest(item: &VaultItem) -> ChacrabResult<String> {
+    let fields = serde_json::to_vec(&(
+        item.id,
+        &item.r#type,
+        item.format_version,
+        &item.title,
+        &item.username,
+        &item.url,
+        &item.encrypted_data,
+        item.nonce,
+        &item.encrypted_metadata,
+        item.metadata_nonce,
+        &item.folder,
+        &item.tags,
+        item.favorite,
+        item.deleted_at.map(|deleted_at| deleted_at.timestamp()),
+    ))?;
+    Ok(hex::encode(Sha256::digest(&fields)))
+}
+
+pub fn seal(
+    generation: u64,
+    entries: BTreeMap<Uuid, ManifestEntry>,
+    key: &[u8; crypto::KEY_SIZE],
+) -> ChacrabResult<VaultManifest> {
+    let mac = manifest_mac(generation, &entries, key)?;
+    Ok(VaultManifest {
+        generation,
+        entries,
+        mac: hex::encode(mac.finalize().into_bytes()),
+    })
+}
+
+pub fn verify(manifest: &VaultManifest, key: &[u8; crypto::KEY_SIZE]) -> ChacrabResult<()> {
+    let tag = hex::decode(&manifest.mac).map_err(|_| ChacrabError::Crypto)?;
+    manifest_mac(manifest.generation, &manifest.entries, key)?
+        .verify_slice(&tag)
+        .map_err(|_| ChacrabError::Crypto)
+}
+
+fn manifest_mac(
+    generation: u64,
+    entries: &BTreeMap<Uuid, ManifestEntry>,
+    key: &[u8; crypto::KEY_SIZE],
+) -> ChacrabResult<Hmac<Sha256>> {
+    let mut subkey = <Hmac<Sha256> as Mac>::new_from_slice(key)
+        .map_err(|_| ChacrabError::Crypto)?
+        .chain_update(MAC_CONTEXT)
+        .finalize()
+        .into_bytes()
+        .to_vec();
+    let mac = <Hmac<Sha256> as Mac>::new_from_slice(&subkey).map_err(|_| ChacrabError::Crypto);
+    subkey.zeroize();
+    Ok(mac?.chain_update(serde_json::to_vec(&(generation, entries))?))
+}
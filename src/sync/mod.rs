@@ -1 +1,5 @@
+pub mod bundle;
+pub mod manifest;
+pub mod protocol;
+pub mod server;
 pub mod sync_engine;
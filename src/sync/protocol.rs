@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::models::{AuthRecord, VaultItem};
+
+pub const API_VERSION: u32 = 1;
+pub const API_PREFIX: &str = "/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusResponse {
+    pub api_version: u32,
+    pub server_version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangesQuery {
+    pub since: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneRequest {
+    pub keep: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceVaultRequest {
+    pub auth: AuthRecord,
+    pub items: Vec<VaultItem>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
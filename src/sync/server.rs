@@ -0,0 +1,308 @@
+use std::sync::Arc;
+
+use axum::{
+    Json, Router,
+    extract::{Path, Query, Request, State},
+    http::{StatusCode, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+};
+use clap::Parser;
+use secrecy::{ExposeSecret, SecretString};
+use tokio::net::TcpListener;
+use uuid::Uuid;
+
+use crate::{
+    core::{
+        errors::{ChacrabError, ChacrabResult},
+        models::{AuthRecord, ChangeSet, ItemRevision, Tombstone, VaultItem, VaultManifest},
+    },
+    storage::{app::AppRepository, r#trait::VaultRepository},
+    sync::{
+        protocol::{
+            API_PREFIX, API_VERSION, ChangesQuery, ErrorResponse, PruneRequest,
+            ReplaceVaultRequest, StatusResponse,
+        },
+        sync_engine::SyncEngine,
+    },
+};
+
+pub const DEFAULT_BIND: &str = "127.0.0.1:8787";
+pub const TOKEN_ENV: &str = "CHACRAB_SERVER_TOKEN";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "chacrab-server",
+    version,
+    about = "Encrypted blob sync server for chacrab"
+)]
+pub struct ServerArgs {
+    #[arg(long, default_value = DEFAULT_BIND)]
+    pub bind: String,
+
+    #[arg(long, default_value = "sqlite")]
+    pub backend: String,
+
+    #[arg(long, default_value = "sqlite://chacrab-server.db?mode=rwc")]
+    pub database_url: String,
+}
+
+struct ServerState<R> {
+    repository: R,
+    token: SecretString,
+}
+
+struct ApiError(ChacrabError);
+
+impl From<ChacrabError> for ApiError {
+    fn from(err: ChacrabError) -> Self {
+        Self(err)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self.0 {
+            ChacrabError::NotFound => (StatusCode::NOT_FOUND, "item not found".to_owned()),
+            ChacrabError::InvalidCredentials => {
+                (StatusCode::UNAUTHORIZED, "invalid sync token".to_owned())
+            }
+            ChacrabError::Config(message) => (StatusCode::BAD_REQUEST, message),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "storage operation failed".to_owned(),
+            ),
+        };
+        (status, Json(ErrorResponse { error: message })).into_response()
+    }
+}
+
+type ApiResult<T> = Result<Json<T>, ApiError>;
+type SharedState<R> = State<Arc<ServerState<R>>>;
+
+pub async fn run() -> ChacrabResult<()> {
+    let args = ServerArgs::parse();
+    let token = std::env::var(TOKEN_ENV)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| ChacrabError::Config(format!("set {TOKEN_ENV} for chacrab-server")))?;
+
+    let repository = AppRepository::connect(&args.backend, &args.database_url).await?;
+    repository.init().await?;
+    let listener = TcpListener::bind(&args.bind)
+        .await
+        .map_err(|_| ChacrabError::Config("unable to bind server address".to_owned()))?;
+    tracing::info!(address = %args.bind, "chacrab-server listening");
+
+    axum::serve(
+        listener,
+        router(repository, SecretString::new(token.into_boxed_str())),
+    )
+    .with_graceful_shutdown(async {
+        let _ = tokio::signal::ctrl_c().await;
+    })
+    .await
+    .map_err(|_| ChacrabError::Storage)
+}
+
+pub fn router<R: VaultRepository + 'static>(repository: R, token: SecretString) -> Router {
+    let state = Arc::new(ServerState { repository, token });
+    let api = Router::new()
+        .route("/items", get(list_items::<R>))
+        .route(
+            "/items/{id}",
+            get(get_item::<R>)
+                .put(put_item::<R>)
+                .delete(delete_item::<R>),
+        )
+        .route(
+            "/items/{id}/revisions",
+            get(list_revisions::<R>).post(add_revision::<R>),
+        )
+        .route("/items/{id}/revisions/prune", post(prune_revisions::<R>))
+        .route("/tombstones", get(list_tombstones::<R>))
+        .route("/tombstones/{id}", put(record_tombstone::<R>))
+        .route("/changes", get(changes_since::<R>))
+        .route("/manifest", get(get_manifest::<R>).put(put_manifest::<R>))
+        .route("/auth", get(get_auth::<R>).put(put_auth::<R>))
+        .route("/vault", put(replace_vault::<R>))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            require_token::<R>,
+        ))
+        .route("/status", get(status));
+
+    Router::new().nest(API_PREFIX, api).with_state(state)
+}
+
+async fn require_token<R>(State(state): SharedState<R>, request: Request, next: Next) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| {
+            constant_time_eq(token.as_bytes(), state.token.expose_secret().as_bytes())
+        });
+    if authorized {
+        next.run(request).await
+    } else {
+        ApiError(ChacrabError::InvalidCredentials).into_response()
+    }
+}
+
+async fn status() -> Json<StatusResponse> {
+    Json(StatusResponse {
+        api_version: API_VERSION,
+        server_version: env!("CARGO_PKG_VERSION").to_owned(),
+    })
+}
+
+async fn list_items<R: VaultRepository>(State(state): SharedState<R>) -> ApiResult<Vec<VaultItem>> {
+    Ok(Json(state.repository.list_items().await?))
+}
+
+async fn get_item<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<VaultItem> {
+    Ok(Json(state.repository.get_item(id).await?))
+}
+
+async fn put_item<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Path(id): Path<Uuid>,
+    Json(item): Json<VaultItem>,
+) -> Result<StatusCode, ApiError> {
+    ensure_path_id(id, item.id)?;
+    if !SyncEngine::validate_encrypted_blob_only(&item) {
+        return Err(
+            ChacrabError::Config("sync rejected invalid encrypted payload".to_owned()).into(),
+        );
+    }
+    state.repository.upsert_item(&item).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn delete_item<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    state.repository.delete_item(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_revisions<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Vec<ItemRevision>> {
+    Ok(Json(state.repository.list_revisions(id).await?))
+}
+
+async fn add_revision<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Path(id): Path<Uuid>,
+    Json(revision): Json<ItemRevision>,
+) -> Result<StatusCode, ApiError> {
+    ensure_path_id(id, revision.item_id)?;
+    state.repository.add_revision(&revision).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn prune_revisions<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PruneRequest>,
+) -> Result<StatusCode, ApiError> {
+    state.repository.prune_revisions(id, request.keep).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_tombstones<R: VaultRepository>(
+    State(state): SharedState<R>,
+) -> ApiResult<Vec<Tombstone>> {
+    Ok(Json(state.repository.list_tombstones().await?))
+}
+
+async fn record_tombstone<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Path(id): Path<Uuid>,
+    Json(tombstone): Json<Tombstone>,
+) -> Result<StatusCode, ApiError> {
+    ensure_path_id(id, tombstone.id)?;
+    state.repository.record_tombstone(&tombstone).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn changes_since<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Query(query): Query<ChangesQuery>,
+) -> ApiResult<ChangeSet> {
+    Ok(Json(state.repository.changes_since(query.since).await?))
+}
+
+async fn get_manifest<R: VaultRepository>(
+    State(state): SharedState<R>,
+) -> ApiResult<Option<VaultManifest>> {
+    Ok(Json(state.repository.get_manifest().await?))
+}
+
+async fn put_manifest<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Json(manifest): Json<VaultManifest>,
+) -> Result<StatusCode, ApiError> {
+    state.repository.set_manifest(&manifest).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_auth<R: VaultRepository>(
+    State(state): SharedState<R>,
+) -> ApiResult<Option<AuthRecord>> {
+    Ok(Json(state.repository.get_auth_record().await?))
+}
+
+async fn put_auth<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Json(auth): Json<AuthRecord>,
+) -> Result<StatusCode, ApiError> {
+    state.repository.set_auth_record(&auth).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn replace_vault<R: VaultRepository>(
+    State(state): SharedState<R>,
+    Json(request): Json<ReplaceVaultRequest>,
+) -> Result<StatusCode, ApiError> {
+    if !request
+        .items
+        .iter()
+        .all(SyncEngine::validate_encrypted_blob_only)
+    {
+        return Err(
+            ChacrabError::Config("sync rejected invalid encrypted payload".to_owned()).into(),
+        );
+    }
+    state
+        .repository
+        .replace_auth_and_items(&request.auth, &request.items)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn ensure_path_id(path_id: Uuid, body_id: Uuid) -> Result<(), ApiError> {
+    if path_id == body_id {
+        Ok(())
+    } else {
+        Err(ChacrabError::Config("request id does not match path".to_owned()).into())
+    }
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
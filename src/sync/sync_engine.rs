@@ -1,75 +1,940 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    str::FromStr,
+};
+
+use uuid::Uuid;
+
 use crate::{
     core::{
+        crypto,
         errors::{ChacrabError, ChacrabResult},
-        models::VaultItem,
+        models::{AuthRecord, ManifestEntry, SyncState, Tombstone, VaultItem, VaultManifest},
     },
     storage::r#trait::VaultRepository,
+    sync::{bundle::ChangeBundle, manifest},
 };
 
+const BUNDLE_EXPORT_STATE: &str = "bundle:export";
+const BUNDLE_IMPORT_STATE: &str = "bundle:import";
+
 pub struct SyncEngine;
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    PreferLocal,
+    PreferRemote,
+    #[default]
+    KeepBoth,
+    Interactive,
+}
+
+impl ConflictPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictPolicy::PreferLocal => "prefer-local",
+            ConflictPolicy::PreferRemote => "prefer-remote",
+            ConflictPolicy::KeepBoth => "keep-both",
+            ConflictPolicy::Interactive => "interactive",
+        }
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = ChacrabError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "prefer-local" => Ok(ConflictPolicy::PreferLocal),
+            "prefer-remote" => Ok(ConflictPolicy::PreferRemote),
+            "keep-both" => Ok(ConflictPolicy::KeepBoth),
+            "interactive" => Ok(ConflictPolicy::Interactive),
+            _ => Err(ChacrabError::Config("unknown conflict policy".to_owned())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictChoice {
+    Local,
+    Remote,
+    KeepBoth,
+}
+
+impl ConflictChoice {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictChoice::Local => "kept local",
+            ConflictChoice::Remote => "kept remote",
+            ConflictChoice::KeepBoth => "kept both",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncConflict {
+    pub id: Uuid,
+    pub local_revision: u64,
+    pub remote_revision: u64,
+    pub resolution: ConflictChoice,
+    pub copy_id: Option<Uuid>,
+}
+
+pub trait ConflictResolver {
+    fn choose(&mut self, local: &VaultItem, remote: &VaultItem) -> ChacrabResult<ConflictChoice>;
+    fn duplicate(&mut self, item: &VaultItem) -> ChacrabResult<VaultItem>;
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct SyncReport {
     pub uploaded: u64,
     pub downloaded: u64,
+    pub local_deletions: u64,
+    pub remote_deletions: u64,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncStep {
+    Upload(VaultItem),
+    Download(VaultItem),
+    Settle {
+        item: VaultItem,
+        write_local: bool,
+        write_remote: bool,
+    },
+    Conflict {
+        local: Box<VaultItem>,
+        remote: Box<VaultItem>,
+    },
+    Delete {
+        tombstone: Tombstone,
+        item: Option<VaultItem>,
+        write_local: bool,
+        write_remote: bool,
+        removes_local: bool,
+        removes_remote: bool,
+        revision: u64,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncPlan {
+    pub steps: Vec<SyncStep>,
+    pub policy: ConflictPolicy,
+    bootstrap: Option<AuthRecord>,
+    rewrap: Option<AuthRecord>,
+    state: SyncState,
+    next_state: SyncState,
+    seen: Option<VaultManifest>,
+    vouched: Option<VaultManifest>,
+    local_generation: u64,
+    one_way: bool,
+}
+
+impl SyncPlan {
+    pub fn remote(&self) -> &str {
+        &self.state.remote
+    }
+
+    pub fn bootstraps_remote(&self) -> bool {
+        self.bootstrap.is_some()
+    }
+
+    pub fn updates_remote_auth(&self) -> bool {
+        self.rewrap.is_some()
+    }
 }
 
 impl SyncEngine {
     pub async fn sync_bidirectional<R: VaultRepository>(
         local: &R,
         remote: &R,
+        remote_name: &str,
+        key: &[u8; crypto::KEY_SIZE],
+        policy: ConflictPolicy,
+        resolver: &mut impl ConflictResolver,
     ) -> ChacrabResult<SyncReport> {
-        let local_items = local.list_items().await?;
-        let remote_items = remote.list_items().await?;
-        let mut report = SyncReport::default();
+        let plan = Self::plan(local, remote, remote_name, key, policy).await?;
+        Self::apply(local, remote, key, plan, resolver, |_| {}).await
+    }
 
-        if local_items
-            .iter()
-            .any(|item| !Self::validate_encrypted_blob_only(item))
-            || remote_items
-                .iter()
-                .any(|item| !Self::validate_encrypted_blob_only(item))
-        {
+    pub async fn plan<R: VaultRepository>(
+        local: &R,
+        remote: &R,
+        remote_name: &str,
+        key: &[u8; crypto::KEY_SIZE],
+        policy: ConflictPolicy,
+    ) -> ChacrabResult<SyncPlan> {
+        let state = Self::load_state(local, remote_name).await?;
+        Self::plan_from(local, remote, key, policy, state).await
+    }
+
+    pub async fn export_bundle<R: VaultRepository>(
+        local: &R,
+        key: &[u8; crypto::KEY_SIZE],
+        full: bool,
+        deliver: impl FnOnce(&ChangeBundle) -> ChacrabResult<()>,
+    ) -> ChacrabResult<ChangeBundle> {
+        let auth = local
+            .get_auth_record()
+            .await?
+            .ok_or(ChacrabError::NoActiveSession)?;
+        let exported = Self::load_state(local, BUNDLE_EXPORT_STATE).await?;
+        let imported = Self::load_state(local, BUNDLE_IMPORT_STATE).await?;
+        let exported_manifest = Self::verify_manifest(exported.manifest.clone(), key, "local")?;
+        let imported_manifest = Self::verify_manifest(imported.manifest, key, "local")?;
+        let local_generation = Self::load_manifest(local, key, "local")
+            .await?
+            .map_or(0, |manifest| manifest.generation);
+
+        let full = full || exported.local_cursor == 0;
+        let since = if full { 0 } else { exported.local_cursor };
+        let changes = local.changes_since(since).await?;
+        let mut entries = exported_manifest
+            .as_ref()
+            .map(|manifest| manifest.entries.clone())
+            .unwrap_or_default();
+        let known = |id: &Uuid| {
+            [&exported_manifest, &imported_manifest]
+                .into_iter()
+                .flatten()
+                .filter(|_| !full)
+                .filter_map(|manifest| manifest.entries.get(id))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let mut items = Vec::new();
+        for item in &changes.items {
+            let digest = manifest::item_digest(item)?;
+            let unchanged = known(&item.id).iter().any(|entry| {
+                !entry.deleted && entry.revision == item.revision && entry.digest == digest
+            });
+            Self::vouch(&mut entries, item)?;
+            if !unchanged {
+                items.push(Self::synced(item));
+            }
+        }
+        let mut tombstones = Vec::new();
+        for tombstone in &changes.tombstones {
+            let unchanged = known(&tombstone.id).iter().any(|entry| entry.deleted);
+            Self::vouch_deleted(&mut entries, tombstone.id, tombstone.revision);
+            if !unchanged {
+                tombstones.push(*tombstone);
+            }
+        }
+
+        let generation = [&exported_manifest, &imported_manifest]
+            .into_iter()
+            .flatten()
+            .map(|manifest| manifest.generation)
+            .chain([local_generation])
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let bundle = ChangeBundle::new(
+            since,
+            changes.cursor,
+            auth,
+            manifest::seal(generation, entries, key)?,
+            items,
+            tombstones,
+        );
+        deliver(&bundle)?;
+
+        for item in &changes.items {
+            if item.base_revision != item.revision {
+                local.upsert_item(&Self::synced(item)).await?;
+            }
+        }
+        local.set_manifest(&bundle.manifest).await?;
+        local
+            .set_sync_state(&SyncState {
+                local_cursor: changes.cursor,
+                manifest: Some(bundle.manifest.clone()),
+                ..exported
+            })
+            .await?;
+        Ok(bundle)
+    }
+
+    pub async fn plan_bundle_import<R: VaultRepository>(
+        local: &R,
+        bundle: &R,
+        since: u64,
+        key: &[u8; crypto::KEY_SIZE],
+        policy: ConflictPolicy,
+    ) -> ChacrabResult<SyncPlan> {
+        let imported = Self::load_state(local, BUNDLE_IMPORT_STATE).await?;
+        if since > imported.remote_cursor {
             return Err(ChacrabError::Config(
-                "sync rejected invalid encrypted payload".to_owned(),
+                "sync bundle is missing earlier changes".to_owned(),
+            ));
+        }
+        let state = SyncState {
+            local_cursor: local.changes_since(u64::MAX).await?.cursor,
+            remote_cursor: if since == 0 {
+                0
+            } else {
+                imported.remote_cursor
+            },
+            ..imported.clone()
+        };
+        let mut plan = Self::plan_from(local, bundle, key, policy, state).await?;
+        plan.steps.retain_mut(|step| match step {
+            SyncStep::Upload(_) => false,
+            SyncStep::Settle {
+                write_local,
+                write_remote,
+                ..
+            } => {
+                *write_remote = false;
+                *write_local
+            }
+            SyncStep::Delete {
+                write_local,
+                write_remote,
+                removes_remote,
+                ..
+            } => {
+                *write_remote = false;
+                *removes_remote = false;
+                *write_local
+            }
+            SyncStep::Download(_) | SyncStep::Conflict { .. } => true,
+        });
+        plan.next_state.local_cursor = imported.local_cursor;
+        plan.state = imported;
+        plan.rewrap = None;
+        plan.one_way = true;
+        Ok(plan)
+    }
+
+    async fn load_state<R: VaultRepository>(
+        local: &R,
+        remote_name: &str,
+    ) -> ChacrabResult<SyncState> {
+        Ok(local
+            .get_sync_state(remote_name)
+            .await?
+            .unwrap_or_else(|| SyncState {
+                remote: remote_name.to_owned(),
+                ..SyncState::default()
+            }))
+    }
+
+    async fn plan_from<R: VaultRepository>(
+        local: &R,
+        remote: &R,
+        key: &[u8; crypto::KEY_SIZE],
+        policy: ConflictPolicy,
+        state: SyncState,
+    ) -> ChacrabResult<SyncPlan> {
+        let mut rewrap = None;
+        let bootstrap = match (
+            local.get_auth_record().await?,
+            remote.get_auth_record().await?,
+        ) {
+            (Some(local_auth), Some(remote_auth)) => {
+                if !Self::same_key_material(&local_auth, &remote_auth) {
+                    if !Self::holds_vault_key(remote, key).await? {
+                        return Err(ChacrabError::Config(
+                            "sync remote belongs to a different vault".to_owned(),
+                        ));
+                    }
+                    if local_auth.revision > remote_auth.revision {
+                        rewrap = Some(AuthRecord {
+                            metadata_privacy: remote_auth.metadata_privacy,
+                            ..local_auth
+                        });
+                    }
+                }
+                None
+            }
+            (local_auth, None) => local_auth,
+            (None, Some(_)) => None,
+        };
+        let seen = Self::verify_manifest(state.manifest.clone(), key, "local")?;
+        let local_generation = Self::load_manifest(local, key, "local")
+            .await?
+            .map_or(0, |manifest| manifest.generation);
+        let vouched = Self::load_manifest(remote, key, "remote").await?;
+        let local_changes = local.changes_since(state.local_cursor).await?;
+        let remote_changes = remote.changes_since(state.remote_cursor).await?;
+        if remote_changes.cursor < state.remote_cursor {
+            return Err(ChacrabError::Integrity(
+                "remote change sequence went backwards".to_owned(),
             ));
         }
 
-        let mut index = std::collections::HashMap::new();
-        for item in remote_items {
-            index.insert(item.id, item);
+        let mut ids = local_changes
+            .items
+            .iter()
+            .map(|item| item.id)
+            .chain(
+                local_changes
+                    .tombstones
+                    .iter()
+                    .map(|tombstone| tombstone.id),
+            )
+            .collect::<BTreeSet<_>>();
+        let mut local_items = Self::index_items(local_changes.items)?;
+        let remote_items = Self::index_items(remote_changes.items)?;
+        let remote_tombstones = Self::index_tombstones(remote_changes.tombstones);
+        for id in remote_items.keys().chain(remote_tombstones.keys()) {
+            ids.insert(*id);
+            if local_items.contains_key(id) {
+                continue;
+            }
+            match local.get_item(*id).await {
+                Ok(item) => {
+                    local_items.insert(*id, item);
+                }
+                Err(ChacrabError::NotFound) => {}
+                Err(err) => return Err(err),
+            }
         }
+        let local_tombstones = if remote_items.is_empty() && remote_tombstones.is_empty() {
+            Self::index_tombstones(local_changes.tombstones)
+        } else {
+            Self::index_tombstones(local.list_tombstones().await?)
+        };
+
+        let remote_complete = state.remote_cursor == 0;
+        Self::check_replay(
+            seen.as_ref(),
+            vouched.as_ref(),
+            &local_items,
+            &remote_items,
+            &local_tombstones,
+            &remote_tombstones,
+            remote_complete,
+        )?;
+
+        let agreed = vouched
+            .as_ref()
+            .or(seen.as_ref())
+            .map(|manifest| &manifest.entries);
+        let mut steps = Vec::new();
+        for id in ids {
+            let local_item = local_items.get(&id);
+            let remote_item = remote_items.get(&id);
+            let local_deleted = local_tombstones.get(&id).copied();
+            let remote_deleted = remote_tombstones.get(&id).copied();
+            if !remote_complete && remote_item.is_none() && remote_deleted.is_none() {
+                let entry = agreed.and_then(|entries| entries.get(&id));
+                steps.extend(Self::plan_push(local_item, local_deleted, entry)?);
+                continue;
+            }
 
-        for local_item in local_items {
-            if let Some(remote_item) = index.get(&local_item.id) {
-                if local_item.updated_at > remote_item.updated_at {
-                    remote.upsert_item(&local_item).await?;
+            let deleted = local_deleted
+                .into_iter()
+                .chain(remote_deleted)
+                .max_by_key(|tombstone| (tombstone.revision, tombstone.deleted_at));
+            let updated = local_item.into_iter().chain(remote_item).any(|item| {
+                deleted.is_none_or(|tombstone| Self::update_survives(item, &tombstone))
+            });
+            match (updated, deleted) {
+                (true, _) => match (local_item, remote_item) {
+                    (Some(local_item), Some(remote_item)) => {
+                        steps.push(Self::plan_reconcile(local_item, remote_item));
+                    }
+                    (Some(local_item), None) => steps.push(SyncStep::Upload(local_item.clone())),
+                    (None, Some(remote_item)) => {
+                        steps.push(SyncStep::Download(remote_item.clone()));
+                    }
+                    (None, None) => {}
+                },
+                (false, Some(deleted)) => {
+                    let revision = local_item
+                        .into_iter()
+                        .chain(remote_item)
+                        .map(|item| item.revision)
+                        .fold(deleted.revision, u64::max);
+                    let tombstone = Tombstone {
+                        revision,
+                        ..deleted
+                    };
+                    steps.push(SyncStep::Delete {
+                        tombstone,
+                        item: local_item.or(remote_item).cloned(),
+                        write_local: local_item.is_some() || local_deleted != Some(tombstone),
+                        write_remote: remote_item.is_some() || remote_deleted != Some(tombstone),
+                        removes_local: local_item.is_some(),
+                        removes_remote: remote_item.is_some(),
+                        revision,
+                    });
+                }
+                (false, None) => {}
+            }
+        }
+
+        let next_state = SyncState {
+            local_cursor: local_changes.cursor,
+            remote_cursor: remote_changes.cursor,
+            ..state.clone()
+        };
+        Ok(SyncPlan {
+            steps,
+            policy,
+            bootstrap,
+            rewrap,
+            state,
+            next_state,
+            seen,
+            vouched,
+            local_generation,
+            one_way: false,
+        })
+    }
+
+    pub async fn apply<R: VaultRepository>(
+        local: &R,
+        remote: &R,
+        key: &[u8; crypto::KEY_SIZE],
+        plan: SyncPlan,
+        resolver: &mut impl ConflictResolver,
+        mut on_step: impl FnMut(&SyncStep),
+    ) -> ChacrabResult<SyncReport> {
+        let mut entries = plan
+            .vouched
+            .as_ref()
+            .or(plan.seen.as_ref())
+            .map(|manifest| manifest.entries.clone())
+            .unwrap_or_default();
+        if let Some(auth) = plan.bootstrap.as_ref().or(plan.rewrap.as_ref()) {
+            remote.set_auth_record(auth).await?;
+        }
+        let mut report = SyncReport::default();
+        for step in &plan.steps {
+            match step {
+                SyncStep::Upload(item) => {
+                    Self::upload(local, remote, item).await?;
+                    Self::vouch(&mut entries, item)?;
                     report.uploaded += 1;
-                } else if remote_item.updated_at > local_item.updated_at {
-                    local.upsert_item(remote_item).await?;
+                }
+                SyncStep::Download(item) => {
+                    local.upsert_item(&Self::synced(item)).await?;
+                    Self::vouch(&mut entries, item)?;
                     report.downloaded += 1;
                 }
-            } else {
-                remote.upsert_item(&local_item).await?;
-                report.uploaded += 1;
+                SyncStep::Settle {
+                    item,
+                    write_local,
+                    write_remote,
+                } => {
+                    if *write_local {
+                        local.upsert_item(item).await?;
+                    }
+                    if *write_remote {
+                        remote.upsert_item(item).await?;
+                    }
+                    Self::vouch(&mut entries, item)?;
+                }
+                SyncStep::Conflict {
+                    local: local_item,
+                    remote: remote_item,
+                } => {
+                    let settled = Self::resolve_conflict(
+                        local,
+                        remote,
+                        local_item,
+                        remote_item,
+                        plan.policy,
+                        resolver,
+                        &mut report,
+                    )
+                    .await?;
+                    for item in &settled {
+                        Self::vouch(&mut entries, item)?;
+                    }
+                }
+                SyncStep::Delete {
+                    tombstone,
+                    write_local,
+                    write_remote,
+                    removes_local,
+                    removes_remote,
+                    revision,
+                    ..
+                } => {
+                    if *write_local {
+                        local.record_tombstone(tombstone).await?;
+                        report.local_deletions += u64::from(*removes_local);
+                    }
+                    if *write_remote {
+                        remote.record_tombstone(tombstone).await?;
+                        report.remote_deletions += u64::from(*removes_remote);
+                    }
+                    Self::vouch_deleted(&mut entries, tombstone.id, *revision);
+                }
             }
+            on_step(step);
         }
 
-        let local_ids: std::collections::HashSet<_> = local
-            .list_items()
+        let kept_manifest = plan.one_way.then(|| plan.vouched.clone());
+        let generation = plan.local_generation;
+        let agreed = Self::store_manifest(
+            local,
+            remote,
+            key,
+            plan.seen,
+            plan.vouched,
+            generation,
+            entries,
+        )
+        .await?;
+        let next_state = SyncState {
+            manifest: kept_manifest.unwrap_or(Some(agreed)),
+            ..plan.next_state
+        };
+        if next_state != plan.state {
+            local.set_sync_state(&next_state).await?;
+        }
+        Ok(report)
+    }
+
+    /// A master password change re-wraps the same vault key, so a remote whose auth record
+    /// differs still belongs to this vault if its manifest verifies under the vault key.
+    async fn holds_vault_key<R: VaultRepository>(
+        remote: &R,
+        key: &[u8; crypto::KEY_SIZE],
+    ) -> ChacrabResult<bool> {
+        Ok(remote
+            .get_manifest()
             .await?
-            .into_iter()
-            .map(|item| item.id)
-            .collect();
+            .is_some_and(|stored| manifest::verify(&stored, key).is_ok()))
+    }
+
+    fn same_key_material(local: &AuthRecord, remote: &AuthRecord) -> bool {
+        local.salt == remote.salt
+            && local.verifier == remote.verifier
+            && (
+                local.argon2_m_cost,
+                local.argon2_t_cost,
+                local.argon2_p_cost,
+            ) == (
+                remote.argon2_m_cost,
+                remote.argon2_t_cost,
+                remote.argon2_p_cost,
+            )
+            && local.wrapped_vault_key == remote.wrapped_vault_key
+            && local.wrapped_vault_key_nonce == remote.wrapped_vault_key_nonce
+    }
+
+    /// An update survives a deletion only if it was made on top of the deleted revision.
+    /// Tombstones written before schema version 14 carry no revision and fall back to
+    /// comparing timestamps.
+    fn update_survives(item: &VaultItem, tombstone: &Tombstone) -> bool {
+        if tombstone.revision == 0 {
+            return item.updated_at > tombstone.deleted_at;
+        }
+        item.revision > tombstone.revision && item.base_revision >= tombstone.revision
+    }
+
+    fn plan_push(
+        local_item: Option<&VaultItem>,
+        local_deleted: Option<Tombstone>,
+        agreed: Option<&ManifestEntry>,
+    ) -> ChacrabResult<Option<SyncStep>> {
+        if let Some(local_item) = local_item {
+            let unchanged = match agreed {
+                Some(entry) => {
+                    !entry.deleted
+                        && entry.revision == local_item.revision
+                        && entry.digest == manifest::item_digest(local_item)?
+                }
+                None => false,
+            };
+            return Ok((!unchanged).then(|| SyncStep::Upload(local_item.clone())));
+        }
+        Ok(local_deleted
+            .filter(|_| !agreed.is_some_and(|entry| entry.deleted))
+            .map(|tombstone| SyncStep::Delete {
+                tombstone,
+                item: None,
+                write_local: false,
+                write_remote: true,
+                removes_local: false,
+                removes_remote: agreed.is_some(),
+                revision: tombstone.revision,
+            }))
+    }
 
-        for remote_item in remote.list_items().await? {
-            if !local_ids.contains(&remote_item.id) {
-                local.upsert_item(&remote_item).await?;
-                report.downloaded += 1;
+    fn plan_reconcile(local_item: &VaultItem, remote_item: &VaultItem) -> SyncStep {
+        if Self::same_content(local_item, remote_item) {
+            let agreed = local_item.revision.max(remote_item.revision);
+            return SyncStep::Settle {
+                item: Self::at_revision(local_item, agreed),
+                write_local: local_item.revision != agreed || local_item.base_revision != agreed,
+                write_remote: remote_item.revision != agreed,
+            };
+        }
+
+        let base = local_item.base_revision;
+        match (local_item.revision != base, remote_item.revision != base) {
+            (true, false) => SyncStep::Upload(local_item.clone()),
+            (false, true) => SyncStep::Download(remote_item.clone()),
+            _ => SyncStep::Conflict {
+                local: Box::new(local_item.clone()),
+                remote: Box::new(remote_item.clone()),
+            },
+        }
+    }
+
+    async fn load_manifest<R: VaultRepository>(
+        repository: &R,
+        key: &[u8; crypto::KEY_SIZE],
+        side: &str,
+    ) -> ChacrabResult<Option<VaultManifest>> {
+        Self::verify_manifest(repository.get_manifest().await?, key, side)
+    }
+
+    fn verify_manifest(
+        stored: Option<VaultManifest>,
+        key: &[u8; crypto::KEY_SIZE],
+        side: &str,
+    ) -> ChacrabResult<Option<VaultManifest>> {
+        if let Some(stored) = &stored {
+            manifest::verify(stored, key).map_err(|_| {
+                ChacrabError::Integrity(format!("{side} vault manifest failed verification"))
+            })?;
+        }
+        Ok(stored)
+    }
+
+    fn check_replay(
+        seen: Option<&VaultManifest>,
+        vouched: Option<&VaultManifest>,
+        local_items: &HashMap<Uuid, VaultItem>,
+        remote_items: &HashMap<Uuid, VaultItem>,
+        local_tombstones: &HashMap<Uuid, Tombstone>,
+        remote_tombstones: &HashMap<Uuid, Tombstone>,
+        remote_complete: bool,
+    ) -> ChacrabResult<()> {
+        let integrity = |message: String| Err(ChacrabError::Integrity(message));
+        match (seen, vouched) {
+            (Some(_), None) if !remote_items.is_empty() || !remote_tombstones.is_empty() => {
+                return integrity("remote vault manifest is missing".to_owned());
             }
+            (Some(seen), Some(vouched)) if vouched.generation < seen.generation => {
+                return integrity("remote vault manifest was rolled back".to_owned());
+            }
+            _ => {}
         }
 
-        Ok(report)
+        for (id, item) in remote_items {
+            let digest = manifest::item_digest(item)?;
+            let own_upload = match local_items.get(id) {
+                Some(local_item) => {
+                    local_item.revision == item.revision
+                        && manifest::item_digest(local_item)? == digest
+                }
+                None => false,
+            };
+            if own_upload {
+                continue;
+            }
+            if let Some(entry) = seen.and_then(|seen| seen.entries.get(id))
+                && (item.revision < entry.revision
+                    || (entry.deleted && item.revision == entry.revision))
+            {
+                return integrity(format!(
+                    "remote replayed an old revision of item {}",
+                    Self::short_id(id)
+                ));
+            }
+            if let Some(vouched) = vouched
+                && vouched.entries.get(id).is_none_or(|entry| {
+                    entry.deleted || entry.revision != item.revision || entry.digest != digest
+                })
+            {
+                return integrity(format!(
+                    "remote item {} does not match the vault manifest",
+                    Self::short_id(id)
+                ));
+            }
+        }
+
+        let Some(vouched) = vouched else {
+            return Ok(());
+        };
+        for id in remote_tombstones.keys() {
+            let recorded = vouched.entries.get(id).is_some_and(|entry| entry.deleted);
+            if !recorded && !local_tombstones.contains_key(id) {
+                return integrity(format!(
+                    "remote deletion of item {} is not in the vault manifest",
+                    Self::short_id(id)
+                ));
+            }
+        }
+        if !remote_complete {
+            return Ok(());
+        }
+        for id in vouched.entries.keys() {
+            if !remote_items.contains_key(id) && !remote_tombstones.contains_key(id) {
+                return integrity(format!(
+                    "remote dropped item {} listed in the vault manifest",
+                    Self::short_id(id)
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn vouch(entries: &mut BTreeMap<Uuid, ManifestEntry>, item: &VaultItem) -> ChacrabResult<()> {
+        entries.insert(
+            item.id,
+            ManifestEntry {
+                revision: item.revision,
+                digest: manifest::item_digest(item)?,
+                deleted: false,
+            },
+        );
+        Ok(())
+    }
+
+    fn vouch_deleted(entries: &mut BTreeMap<Uuid, ManifestEntry>, id: Uuid, revision: u64) {
+        let revision = entries
+            .get(&id)
+            .map_or(revision, |entry| entry.revision.max(revision));
+        entries.insert(
+            id,
+            ManifestEntry {
+                revision,
+                digest: String::new(),
+                deleted: true,
+            },
+        );
+    }
+
+    async fn store_manifest<R: VaultRepository>(
+        local: &R,
+        remote: &R,
+        key: &[u8; crypto::KEY_SIZE],
+        seen: Option<VaultManifest>,
+        vouched: Option<VaultManifest>,
+        local_generation: u64,
+        entries: BTreeMap<Uuid, ManifestEntry>,
+    ) -> ChacrabResult<VaultManifest> {
+        let seen_generation = seen.as_ref().map(|seen| seen.generation);
+        if let Some(vouched) = vouched.as_ref()
+            && vouched.entries == entries
+            && seen_generation == Some(vouched.generation)
+        {
+            return Ok(vouched.clone());
+        }
+        let generation = seen_generation
+            .max(vouched.map(|vouched| vouched.generation))
+            .unwrap_or_default()
+            .max(local_generation)
+            + 1;
+        let next = manifest::seal(generation, entries, key)?;
+        remote.set_manifest(&next).await?;
+        local.set_manifest(&next).await?;
+        Ok(next)
+    }
+
+    fn short_id(id: &Uuid) -> String {
+        id.to_string().chars().take(8).collect()
+    }
+
+    async fn resolve_conflict<R: VaultRepository>(
+        local: &R,
+        remote: &R,
+        local_item: &VaultItem,
+        remote_item: &VaultItem,
+        policy: ConflictPolicy,
+        resolver: &mut impl ConflictResolver,
+        report: &mut SyncReport,
+    ) -> ChacrabResult<Vec<VaultItem>> {
+        let resolution = match policy {
+            ConflictPolicy::PreferLocal => ConflictChoice::Local,
+            ConflictPolicy::PreferRemote => ConflictChoice::Remote,
+            ConflictPolicy::KeepBoth => ConflictChoice::KeepBoth,
+            ConflictPolicy::Interactive => resolver.choose(local_item, remote_item)?,
+        };
+        let revision = local_item.revision.max(remote_item.revision) + 1;
+        let winner = match resolution {
+            ConflictChoice::Local | ConflictChoice::KeepBoth => local_item,
+            ConflictChoice::Remote => remote_item,
+        };
+        let winner = Self::at_revision(winner, revision);
+        local.upsert_item(&winner).await?;
+        remote.upsert_item(&winner).await?;
+        match resolution {
+            ConflictChoice::Remote => report.downloaded += 1,
+            ConflictChoice::Local | ConflictChoice::KeepBoth => report.uploaded += 1,
+        }
+
+        let mut copy_id = None;
+        let mut settled = vec![winner];
+        if resolution == ConflictChoice::KeepBoth {
+            let copy = Self::synced(&resolver.duplicate(remote_item)?);
+            local.upsert_item(&copy).await?;
+            remote.upsert_item(&copy).await?;
+            report.downloaded += 1;
+            copy_id = Some(copy.id);
+            settled.push(copy);
+        }
+
+        report.conflicts.push(SyncConflict {
+            id: local_item.id,
+            local_revision: local_item.revision,
+            remote_revision: remote_item.revision,
+            resolution,
+            copy_id,
+        });
+        Ok(settled)
+    }
+
+    async fn upload<R: VaultRepository>(
+        local: &R,
+        remote: &R,
+        item: &VaultItem,
+    ) -> ChacrabResult<()> {
+        let synced = Self::synced(item);
+        remote.upsert_item(&synced).await?;
+        if item.base_revision != item.revision {
+            local.upsert_item(&synced).await?;
+        }
+        Ok(())
+    }
+
+    fn synced(item: &VaultItem) -> VaultItem {
+        Self::at_revision(item, item.revision)
+    }
+
+    fn at_revision(item: &VaultItem, revision: u64) -> VaultItem {
+        VaultItem {
+            revision,
+            base_revision: revision,
+            ..item.clone()
+        }
+    }
+
+    fn same_content(local_item: &VaultItem, remote_item: &VaultItem) -> bool {
+        Self::at_revision(local_item, 0) == Self::at_revision(remote_item, 0)
+    }
+
+    fn index_items(items: Vec<VaultItem>) -> ChacrabResult<HashMap<Uuid, VaultItem>> {
+        if items
+            .iter()
+            .any(|item| !Self::validate_encrypted_blob_only(item))
+        {
+            return Err(ChacrabError::Config(
+                "sync rejected invalid encrypted payload".to_owned(),
+            ));
+        }
+        Ok(items.into_iter().map(|item| (item.id, item)).collect())
+    }
+
+    fn index_tombstones(tombstones: Vec<Tombstone>) -> HashMap<Uuid, Tombstone> {
+        tombstones
+            .into_iter()
+            .map(|tombstone| (tombstone.id, tombstone))
+            .collect()
     }
 
     pub fn validate_encrypted_blob_only(item: &VaultItem) -> bool {
@@ -85,22 +950,71 @@ mod tests {
     };
 
     use async_trait::async_trait;
-    use chrono::{Duration, Utc};
+    use chrono::{DateTime, Duration, Utc};
     use uuid::Uuid;
 
     use crate::{
         core::{
             errors::{ChacrabError, ChacrabResult},
-            models::{AuthRecord, VaultItem, VaultItemType},
+            models::{
+                AuthRecord, ChangeSet, ITEM_FORMAT_VERSION, ItemRevision, SyncState, Tombstone,
+                VaultItem, VaultItemType, VaultManifest,
+            },
         },
         storage::r#trait::VaultRepository,
+        sync::manifest,
+    };
+
+    use super::{
+        ConflictChoice, ConflictPolicy, ConflictResolver, SyncEngine, SyncReport, SyncStep,
     };
 
-    use super::SyncEngine;
+    const KEY: [u8; 32] = [9u8; 32];
+
+    struct FixedResolver(ConflictChoice);
+
+    impl ConflictResolver for FixedResolver {
+        fn choose(&mut self, _: &VaultItem, _: &VaultItem) -> ChacrabResult<ConflictChoice> {
+            Ok(self.0)
+        }
+
+        fn duplicate(&mut self, item: &VaultItem) -> ChacrabResult<VaultItem> {
+            Ok(VaultItem {
+                id: Uuid::new_v4(),
+                title: format!("{} (conflict copy)", item.title),
+                revision: 1,
+                base_revision: 0,
+                ..item.clone()
+            })
+        }
+    }
 
     #[derive(Clone, Default)]
     struct MemoryRepo {
+        auth: Arc<Mutex<Option<AuthRecord>>>,
         items: Arc<Mutex<HashMap<Uuid, VaultItem>>>,
+        tombstones: Arc<Mutex<HashMap<Uuid, Tombstone>>>,
+        manifest: Arc<Mutex<Option<VaultManifest>>>,
+        sequence: Arc<Mutex<u64>>,
+        changes: Arc<Mutex<HashMap<Uuid, u64>>>,
+        sync_states: Arc<Mutex<HashMap<String, SyncState>>>,
+        touched: Arc<Mutex<usize>>,
+    }
+
+    impl MemoryRepo {
+        fn record_change(&self, id: Uuid) {
+            let mut sequence = self.sequence.lock().expect("poisoned");
+            *sequence += 1;
+            self.changes.lock().expect("poisoned").insert(id, *sequence);
+        }
+
+        fn touch(&self, count: usize) {
+            *self.touched.lock().expect("poisoned") += count;
+        }
+
+        fn touched(&self) -> usize {
+            std::mem::take(&mut *self.touched.lock().expect("poisoned"))
+        }
     }
 
     #[async_trait]
@@ -109,25 +1023,43 @@ mod tests {
             Ok(())
         }
 
+        async fn is_initialized(&self) -> ChacrabResult<bool> {
+            Ok(true)
+        }
+
+        async fn has_legacy_items(&self) -> ChacrabResult<bool> {
+            Ok(true)
+        }
+
+        async fn clear_legacy_items(&self) -> ChacrabResult<()> {
+            Ok(())
+        }
+
         async fn upsert_item(&self, item: &VaultItem) -> ChacrabResult<()> {
+            self.touch(1);
             self.items
                 .lock()
                 .expect("poisoned")
                 .insert(item.id, item.clone());
+            self.tombstones.lock().expect("poisoned").remove(&item.id);
+            self.record_change(item.id);
             Ok(())
         }
 
         async fn list_items(&self) -> ChacrabResult<Vec<VaultItem>> {
-            Ok(self
+            let items = self
                 .items
                 .lock()
                 .expect("poisoned")
                 .values()
                 .cloned()
-                .collect())
+                .collect::<Vec<_>>();
+            self.touch(items.len());
+            Ok(items)
         }
 
         async fn get_item(&self, id: Uuid) -> ChacrabResult<VaultItem> {
+            self.touch(1);
             self.items
                 .lock()
                 .expect("poisoned")
@@ -137,20 +1069,125 @@ mod tests {
         }
 
         async fn delete_item(&self, id: Uuid) -> ChacrabResult<()> {
-            self.items.lock().expect("poisoned").remove(&id);
+            let revision = self.get_item(id).await?.revision;
+            self.record_tombstone(&Tombstone {
+                id,
+                deleted_at: Utc::now(),
+                revision,
+            })
+            .await
+        }
+
+        async fn add_revision(&self, _: &ItemRevision) -> ChacrabResult<()> {
+            Ok(())
+        }
+
+        async fn list_revisions(&self, _: Uuid) -> ChacrabResult<Vec<ItemRevision>> {
+            Ok(Vec::new())
+        }
+
+        async fn prune_revisions(&self, _: Uuid, _: usize) -> ChacrabResult<()> {
+            Ok(())
+        }
+
+        async fn list_tombstones(&self) -> ChacrabResult<Vec<Tombstone>> {
+            Ok(self
+                .tombstones
+                .lock()
+                .expect("poisoned")
+                .values()
+                .copied()
+                .collect())
+        }
+
+        async fn record_tombstone(&self, tombstone: &Tombstone) -> ChacrabResult<()> {
+            self.items.lock().expect("poisoned").remove(&tombstone.id);
+            self.tombstones
+                .lock()
+                .expect("poisoned")
+                .insert(tombstone.id, *tombstone);
+            self.record_change(tombstone.id);
+            Ok(())
+        }
+
+        async fn changes_since(&self, cursor: u64) -> ChacrabResult<ChangeSet> {
+            let changed = self
+                .changes
+                .lock()
+                .expect("poisoned")
+                .iter()
+                .filter(|(_, sequence)| **sequence > cursor)
+                .map(|(id, _)| *id)
+                .collect::<Vec<_>>();
+            let items = self.items.lock().expect("poisoned");
+            let tombstones = self.tombstones.lock().expect("poisoned");
+            let changes = ChangeSet {
+                items: changed
+                    .iter()
+                    .filter_map(|id| items.get(id).cloned())
+                    .collect(),
+                tombstones: changed
+                    .iter()
+                    .filter_map(|id| tombstones.get(id).copied())
+                    .collect(),
+                cursor: *self.sequence.lock().expect("poisoned"),
+            };
+            self.touch(changes.items.len());
+            Ok(changes)
+        }
+
+        async fn get_manifest(&self) -> ChacrabResult<Option<VaultManifest>> {
+            Ok(self.manifest.lock().expect("poisoned").clone())
+        }
+
+        async fn set_manifest(&self, manifest: &VaultManifest) -> ChacrabResult<()> {
+            *self.manifest.lock().expect("poisoned") = Some(manifest.clone());
+            Ok(())
+        }
+
+        async fn get_sync_state(&self, remote: &str) -> ChacrabResult<Option<SyncState>> {
+            Ok(self
+                .sync_states
+                .lock()
+                .expect("poisoned")
+                .get(remote)
+                .cloned())
+        }
+
+        async fn set_sync_state(&self, state: &SyncState) -> ChacrabResult<()> {
+            self.sync_states
+                .lock()
+                .expect("poisoned")
+                .insert(state.remote.clone(), state.clone());
             Ok(())
         }
 
         async fn get_auth_record(&self) -> ChacrabResult<Option<AuthRecord>> {
-            Ok(None)
+            Ok(self.auth.lock().expect("poisoned").clone())
         }
 
-        async fn set_auth_record(&self, _: &AuthRecord) -> ChacrabResult<()> {
+        async fn set_auth_record(&self, auth: &AuthRecord) -> ChacrabResult<()> {
+            *self.auth.lock().expect("poisoned") = Some(auth.clone());
+            Ok(())
+        }
+
+        async fn replace_auth_and_items(
+            &self,
+            _: &AuthRecord,
+            items: &[VaultItem],
+        ) -> ChacrabResult<()> {
+            for item in items {
+                self.items
+                    .lock()
+                    .expect("poisoned")
+                    .insert(item.id, item.clone());
+                self.record_change(item.id);
+            }
             Ok(())
         }
     }
 
-    fn build_item(id: Uuid, title: &str, updated_at: chrono::DateTime<Utc>) -> VaultItem {
+    fn build_item(id: Uuid, title: &str, updated_at: DateTime<Utc>) -> VaultItem {
         VaultItem {
             id,
             r#type: VaultItemType::Password,
@@ -159,11 +1196,67 @@ mod tests {
             url: None,
             encrypted_data: vec![1, 2, 3],
             nonce: [7u8; 12],
+            format_version: ITEM_FORMAT_VERSION,
+            encrypted_metadata: None,
+            metadata_nonce: None,
+            folder: None,
+            tags: Vec::new(),
+            favorite: false,
+            deleted_at: None,
+            revision: 1,
+            base_revision: 0,
             created_at: updated_at,
             updated_at,
         }
     }
 
+    fn auth_record(salt: &str) -> AuthRecord {
+        AuthRecord {
+            salt: salt.to_owned(),
+            verifier: format!("verifier-{salt}"),
+            argon2_m_cost: 65536,
+            argon2_t_cost: 3,
+            argon2_p_cost: 1,
+            wrapped_vault_key: Some(vec![4; 48]),
+            wrapped_vault_key_nonce: Some([5; 12]),
+            metadata_privacy: false,
+            revision: 0,
+        }
+    }
+
+    fn revised(item: VaultItem, revision: u64, base_revision: u64) -> VaultItem {
+        VaultItem {
+            revision,
+            base_revision,
+            ..item
+        }
+    }
+
+    async fn try_sync(local: &MemoryRepo, remote: &MemoryRepo) -> ChacrabResult<SyncReport> {
+        SyncEngine::sync_bidirectional(
+            local,
+            remote,
+            "test",
+            &KEY,
+            ConflictPolicy::KeepBoth,
+            &mut FixedResolver(ConflictChoice::Remote),
+        )
+        .await
+    }
+
+    async fn sync(local: &MemoryRepo, remote: &MemoryRepo, policy: ConflictPolicy) -> SyncReport {
+        SyncEngine::sync_bidirectional(
+            local,
+            remote,
+            "test",
+            &KEY,
+            policy,
+            &mut FixedResolver(ConflictChoice::Remote),
+        )
+        .await
+        .expect("sync should succeed")
+    }
+
     #[tokio::test]
     async fn sync_reports_uploads_and_downloads() {
         let local = MemoryRepo::default();
@@ -171,8 +1264,8 @@ mod tests {
         let now = Utc::now();
         let same_id = Uuid::new_v4();
 
-        let local_newer = build_item(same_id, "local newer", now + Duration::seconds(60));
-        let remote_older = build_item(same_id, "remote older", now);
+        let local_newer = revised(build_item(same_id, "local newer", now), 2, 1);
+        let remote_older = revised(build_item(same_id, "remote older", now), 1, 1);
         let local_only = build_item(Uuid::new_v4(), "local only", now);
         let remote_only = build_item(Uuid::new_v4(), "remote only", now);
 
@@ -187,13 +1280,775 @@ mod tests {
             .await
             .expect("remote upsert");
 
-        let report = SyncEngine::sync_bidirectional(&local, &remote)
-            .await
-            .expect("sync should succeed");
+        let report = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
 
         assert_eq!(report.uploaded, 2);
         assert_eq!(report.downloaded, 1);
         assert_eq!(local.list_items().await.expect("local list").len(), 3);
         assert_eq!(remote.list_items().await.expect("remote list").len(), 3);
     }
+
+    async fn titles(repo: &MemoryRepo) -> Vec<String> {
+        let mut titles = repo
+            .list_items()
+            .await
+            .expect("list")
+            .into_iter()
+            .map(|item| item.title)
+            .collect::<Vec<_>>();
+        titles.sort();
+        titles
+    }
+
+    async fn tombstone(repo: &MemoryRepo, id: Uuid, revision: u64, deleted_at: DateTime<Utc>) {
+        repo.record_tombstone(&Tombstone {
+            id,
+            deleted_at,
+            revision,
+        })
+        .await
+        .expect("tombstone");
+    }
+
+    #[tokio::test]
+    async fn deletion_propagates_instead_of_resurrecting() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        local
+            .upsert_item(&build_item(id, "shared", now))
+            .await
+            .expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        tombstone(&local, id, 1, now + Duration::seconds(10)).await;
+
+        let report = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        assert_eq!(report.downloaded, 0);
+        assert_eq!(report.remote_deletions, 1);
+        assert!(titles(&local).await.is_empty());
+        assert!(titles(&remote).await.is_empty());
+        assert_eq!(remote.list_tombstones().await.expect("tombstones").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn update_after_deletion_wins_and_clears_tombstone() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+
+        tombstone(&local, id, 1, now).await;
+        remote
+            .upsert_item(&revised(
+                build_item(id, "edited later", now + Duration::seconds(5)),
+                2,
+                1,
+            ))
+            .await
+            .expect("upsert");
+
+        let report = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        assert_eq!(report.downloaded, 1);
+        assert_eq!(report.remote_deletions, 0);
+        assert_eq!(titles(&local).await, vec!["edited later"]);
+        assert!(
+            local
+                .list_tombstones()
+                .await
+                .expect("tombstones")
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn conflict_matrix_resolves_deletes_and_updates() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let later = now + Duration::seconds(30);
+        let remote_deleted = Uuid::new_v4();
+        let both_deleted = Uuid::new_v4();
+        let tie = Uuid::new_v4();
+        let unknown = Uuid::new_v4();
+        let both_updated = Uuid::new_v4();
+        let slow_clock = Uuid::new_v4();
+        let fast_clock = Uuid::new_v4();
+
+        local
+            .upsert_item(&build_item(remote_deleted, "stale local edit", now))
+            .await
+            .expect("upsert");
+        tombstone(&remote, remote_deleted, 1, later).await;
+
+        tombstone(&local, both_deleted, 1, now).await;
+        tombstone(&remote, both_deleted, 1, later).await;
+
+        local
+            .upsert_item(&build_item(tie, "same instant", later))
+            .await
+            .expect("upsert");
+        tombstone(&remote, tie, 0, later).await;
+
+        tombstone(&remote, unknown, 0, now).await;
+
+        local
+            .upsert_item(&revised(
+                build_item(
+                    slow_clock,
+                    "edit behind the clock",
+                    now - Duration::hours(1),
+                ),
+                2,
+                1,
+            ))
+            .await
+            .expect("upsert");
+        tombstone(&remote, slow_clock, 1, later).await;
+
+        local
+            .upsert_item(&revised(
+                build_item(
+                    fast_clock,
+                    "edit ahead of the clock",
+                    now + Duration::days(1),
+                ),
+                2,
+                1,
+            ))
+            .await
+            .expect("upsert");
+        tombstone(&remote, fast_clock, 3, now).await;
+
+        local
+            .upsert_item(&revised(build_item(both_updated, "older", now), 1, 1))
+            .await
+            .expect("upsert");
+        remote
+            .upsert_item(&revised(build_item(both_updated, "newer", later), 2, 1))
+            .await
+            .expect("upsert");
+
+        let report = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        assert_eq!(report.uploaded, 1);
+        assert_eq!(report.downloaded, 1);
+        assert_eq!(report.local_deletions, 3);
+        assert_eq!(report.remote_deletions, 0);
+        assert_eq!(titles(&local).await, vec!["edit behind the clock", "newer"]);
+        assert_eq!(
+            titles(&remote).await,
+            vec!["edit behind the clock", "newer"]
+        );
+
+        let local_tombstones = local
+            .list_tombstones()
+            .await
+            .expect("tombstones")
+            .into_iter()
+            .map(|tombstone| (tombstone.id, tombstone.deleted_at))
+            .collect::<HashMap<_, _>>();
+        assert_eq!(local_tombstones.len(), 5);
+        assert_eq!(local_tombstones[&both_deleted], later);
+        assert_eq!(local_tombstones[&unknown], now);
+
+        let again = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        assert_eq!(
+            (
+                again.uploaded,
+                again.downloaded,
+                again.local_deletions,
+                again.remote_deletions
+            ),
+            (0, 0, 0, 0)
+        );
+    }
+
+    #[tokio::test]
+    async fn revisions_decide_the_winner_regardless_of_clocks() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let skewed = Uuid::new_v4();
+        let same_instant = Uuid::new_v4();
+
+        local
+            .upsert_item(&revised(build_item(skewed, "local edit", now), 3, 2))
+            .await
+            .expect("upsert");
+        remote
+            .upsert_item(&revised(
+                build_item(skewed, "remote stale", now + Duration::hours(1)),
+                2,
+                2,
+            ))
+            .await
+            .expect("upsert");
+        local
+            .upsert_item(&revised(build_item(same_instant, "local stale", now), 4, 4))
+            .await
+            .expect("upsert");
+        remote
+            .upsert_item(&revised(build_item(same_instant, "remote edit", now), 5, 4))
+            .await
+            .expect("upsert");
+
+        let report = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        assert_eq!((report.uploaded, report.downloaded), (1, 1));
+        assert!(report.conflicts.is_empty());
+        assert_eq!(titles(&local).await, vec!["local edit", "remote edit"]);
+        assert_eq!(titles(&remote).await, vec!["local edit", "remote edit"]);
+        let stored = local.get_item(skewed).await.expect("item");
+        assert_eq!((stored.revision, stored.base_revision), (3, 3));
+    }
+
+    #[tokio::test]
+    async fn concurrent_edits_are_conflicts_resolved_by_policy() {
+        let cases = [
+            (ConflictPolicy::PreferLocal, vec!["local edit"]),
+            (ConflictPolicy::PreferRemote, vec!["remote edit"]),
+            (ConflictPolicy::Interactive, vec!["remote edit"]),
+            (
+                ConflictPolicy::KeepBoth,
+                vec!["local edit", "remote edit (conflict copy)"],
+            ),
+        ];
+
+        for (policy, expected) in cases {
+            let local = MemoryRepo::default();
+            let remote = MemoryRepo::default();
+            let now = Utc::now();
+            let id = Uuid::new_v4();
+            local
+                .upsert_item(&revised(build_item(id, "local edit", now), 2, 1))
+                .await
+                .expect("upsert");
+            remote
+                .upsert_item(&revised(build_item(id, "remote edit", now), 2, 1))
+                .await
+                .expect("upsert");
+
+            let report = sync(&local, &remote, policy).await;
+
+            assert_eq!(report.conflicts.len(), 1, "{}", policy.as_str());
+            let conflict = &report.conflicts[0];
+            assert_eq!(conflict.id, id);
+            assert_eq!((conflict.local_revision, conflict.remote_revision), (2, 2));
+            assert_eq!(
+                conflict.copy_id.is_some(),
+                policy == ConflictPolicy::KeepBoth
+            );
+            assert_eq!(titles(&local).await, expected, "{}", policy.as_str());
+            assert_eq!(titles(&remote).await, expected, "{}", policy.as_str());
+            assert_eq!(local.get_item(id).await.expect("item").revision, 3);
+
+            let again = sync(&local, &remote, policy).await;
+            assert!(again.conflicts.is_empty());
+            assert_eq!((again.uploaded, again.downloaded), (0, 0));
+        }
+    }
+
+    #[tokio::test]
+    async fn items_rewritten_on_one_side_are_uploaded_without_conflicts() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+        local
+            .upsert_item(&build_item(id, "plain", now))
+            .await
+            .expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        let synced = local.get_item(id).await.expect("synced item");
+        let rewritten = VaultItem {
+            encrypted_data: vec![4, 5, 6],
+            ..revised(
+                build_item(id, "sealed", now),
+                synced.revision + 1,
+                synced.base_revision,
+            )
+        };
+        local.upsert_item(&rewritten).await.expect("upsert");
+
+        let report = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        assert!(report.conflicts.is_empty());
+        assert_eq!(report.uploaded, 1);
+        assert_eq!(titles(&remote).await, vec!["sealed"]);
+    }
+
+    fn assert_integrity_error(result: ChacrabResult<SyncReport>) {
+        assert!(
+            matches!(result, Err(ChacrabError::Integrity(_))),
+            "expected integrity error, got {result:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn rolled_back_items_are_rejected_before_anything_is_applied() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+        let original = build_item(id, "v1", now);
+        local.upsert_item(&original).await.expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        local
+            .upsert_item(&revised(build_item(id, "v2", now), 2, 1))
+            .await
+            .expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        let replayed = VaultItem {
+            updated_at: now + Duration::hours(1),
+            ..revised(original.clone(), 1, 1)
+        };
+        remote.upsert_item(&replayed).await.expect("replay");
+        assert_integrity_error(try_sync(&local, &remote).await);
+
+        let relabeled = VaultItem {
+            updated_at: now + Duration::hours(1),
+            ..revised(original, 7, 7)
+        };
+        remote.upsert_item(&relabeled).await.expect("replay");
+        assert_integrity_error(try_sync(&local, &remote).await);
+        assert_eq!(titles(&local).await, vec!["v2"]);
+    }
+
+    #[tokio::test]
+    async fn deleted_items_cannot_be_removed_and_readded() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let id = Uuid::new_v4();
+        let item = build_item(id, "retired", now);
+        local.upsert_item(&item).await.expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        tombstone(&local, id, 1, now + Duration::seconds(5)).await;
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        remote
+            .upsert_item(&VaultItem {
+                updated_at: now + Duration::hours(1),
+                ..revised(item, 1, 1)
+            })
+            .await
+            .expect("readd");
+
+        assert_integrity_error(try_sync(&local, &remote).await);
+        assert!(titles(&local).await.is_empty());
+        assert_eq!(local.list_tombstones().await.expect("tombstones").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn forged_or_rolled_back_manifests_are_rejected() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let kept = build_item(Uuid::new_v4(), "kept", now);
+        local.upsert_item(&kept).await.expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        let snapshot = remote.get_manifest().await.expect("manifest");
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "added", now))
+            .await
+            .expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        let current = remote.get_manifest().await.expect("manifest").expect("set");
+        *remote.manifest.lock().expect("poisoned") = snapshot;
+        assert_integrity_error(try_sync(&local, &remote).await);
+
+        let mut forged = current.clone();
+        forged.entries.remove(&kept.id);
+        *remote.manifest.lock().expect("poisoned") = Some(forged);
+        assert_integrity_error(try_sync(&local, &remote).await);
+
+        *remote.manifest.lock().expect("poisoned") = Some(current);
+        remote
+            .record_tombstone(&Tombstone {
+                id: kept.id,
+                deleted_at: now + Duration::hours(1),
+                revision: 1,
+            })
+            .await
+            .expect("tombstone");
+        assert_integrity_error(try_sync(&local, &remote).await);
+        assert_eq!(titles(&local).await, vec!["added", "kept"]);
+    }
+
+    #[tokio::test]
+    async fn manifest_is_only_rewritten_when_the_vault_changes() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "stable", Utc::now()))
+            .await
+            .expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        let first = remote.get_manifest().await.expect("manifest").expect("set");
+
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        assert_eq!(first.generation, 1);
+        assert_eq!(remote.get_manifest().await.expect("manifest"), Some(first));
+    }
+
+    #[tokio::test]
+    async fn settled_sync_touches_no_items() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        for title in ["one", "two", "three"] {
+            local
+                .upsert_item(&build_item(Uuid::new_v4(), title, now))
+                .await
+                .expect("upsert");
+        }
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        local.touched();
+        remote.touched();
+
+        let report = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        assert_eq!((report.uploaded, report.downloaded), (0, 0));
+        assert!(report.conflicts.is_empty());
+        assert_eq!(local.touched(), 0);
+        assert_eq!(remote.touched(), 0);
+    }
+
+    #[tokio::test]
+    async fn incremental_sync_only_moves_changed_items() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let edited = build_item(Uuid::new_v4(), "edited", now);
+        local.upsert_item(&edited).await.expect("upsert");
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "untouched", now))
+            .await
+            .expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        local
+            .upsert_item(&VaultItem {
+                title: "edited again".to_owned(),
+                ..revised(edited, 2, 1)
+            })
+            .await
+            .expect("upsert");
+        let other = MemoryRepo::default();
+        sync(&other, &remote, ConflictPolicy::KeepBoth).await;
+        other
+            .upsert_item(&build_item(Uuid::new_v4(), "remote only", now))
+            .await
+            .expect("upsert");
+        sync(&other, &remote, ConflictPolicy::KeepBoth).await;
+        local.touched();
+        remote.touched();
+
+        let report = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        assert_eq!((report.uploaded, report.downloaded), (1, 1));
+        assert!(remote.touched() <= 3);
+        assert_eq!(
+            titles(&remote).await,
+            vec!["edited again", "remote only", "untouched"]
+        );
+        assert_eq!(titles(&local).await, titles(&remote).await);
+    }
+
+    #[tokio::test]
+    async fn sync_state_is_kept_per_remote() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "item", Utc::now()))
+            .await
+            .expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        let state = local
+            .get_sync_state("test")
+            .await
+            .expect("state")
+            .expect("saved");
+        assert_eq!(state.remote, "test");
+        assert_eq!(
+            state.local_cursor,
+            *local.sequence.lock().expect("poisoned")
+        );
+        assert_eq!(
+            state.remote_cursor,
+            *remote.sequence.lock().expect("poisoned")
+        );
+        assert_eq!(local.get_sync_state("other").await.expect("state"), None);
+
+        *remote.sequence.lock().expect("poisoned") = 0;
+        assert_integrity_error(try_sync(&local, &remote).await);
+    }
+
+    #[tokio::test]
+    async fn one_vault_syncs_with_several_remotes() {
+        let local = MemoryRepo::default();
+        let first = MemoryRepo::default();
+        let second = MemoryRepo::default();
+        let sync_named = |remote: &MemoryRepo, name: &'static str| {
+            let (local, remote) = (local.clone(), remote.clone());
+            async move {
+                SyncEngine::sync_bidirectional(
+                    &local,
+                    &remote,
+                    name,
+                    &KEY,
+                    ConflictPolicy::KeepBoth,
+                    &mut FixedResolver(ConflictChoice::Remote),
+                )
+                .await
+            }
+        };
+
+        let now = Utc::now();
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "first", now))
+            .await
+            .expect("upsert");
+        sync_named(&first, "first").await.expect("sync first");
+        sync_named(&second, "second").await.expect("sync second");
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "second", now))
+            .await
+            .expect("upsert");
+        sync_named(&first, "first").await.expect("sync first");
+        let stale = second.get_manifest().await.expect("manifest");
+        sync_named(&second, "second").await.expect("sync second");
+
+        assert_eq!(titles(&first).await, titles(&local).await);
+        assert_eq!(titles(&second).await, titles(&local).await);
+        assert_eq!(
+            local
+                .get_sync_state("second")
+                .await
+                .expect("state")
+                .and_then(|state| state.manifest),
+            second.get_manifest().await.expect("manifest")
+        );
+
+        *second.manifest.lock().expect("poisoned") = stale;
+        assert_integrity_error(sync_named(&second, "second").await);
+    }
+
+    #[tokio::test]
+    async fn plan_previews_changes_without_writing() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let now = Utc::now();
+        let shared = build_item(Uuid::new_v4(), "shared", now);
+        let retired = build_item(Uuid::new_v4(), "retired", now);
+        local.upsert_item(&shared).await.expect("upsert");
+        local.upsert_item(&retired).await.expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        local
+            .upsert_item(&revised(build_item(shared.id, "local edit", now), 2, 1))
+            .await
+            .expect("upsert");
+        let other = MemoryRepo::default();
+        sync(&other, &remote, ConflictPolicy::KeepBoth).await;
+        other
+            .upsert_item(&revised(build_item(shared.id, "remote edit", now), 2, 1))
+            .await
+            .expect("upsert");
+        sync(&other, &remote, ConflictPolicy::KeepBoth).await;
+        tombstone(&local, retired.id, 1, now + Duration::seconds(5)).await;
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "new", now))
+            .await
+            .expect("upsert");
+        let before = (titles(&local).await, titles(&remote).await);
+        let manifest = remote.get_manifest().await.expect("manifest");
+
+        let plan = SyncEngine::plan(&local, &remote, "test", &KEY, ConflictPolicy::PreferLocal)
+            .await
+            .expect("plan");
+
+        assert_eq!((titles(&local).await, titles(&remote).await), before);
+        assert_eq!(remote.get_manifest().await.expect("manifest"), manifest);
+        let uploads = plan
+            .steps
+            .iter()
+            .filter(|step| matches!(step, SyncStep::Upload(_)))
+            .count();
+        let conflicts = plan
+            .steps
+            .iter()
+            .filter(|step| matches!(step, SyncStep::Conflict { .. }))
+            .count();
+        assert_eq!((uploads, conflicts), (1, 1));
+        assert!(plan.steps.iter().any(|step| matches!(
+            step,
+            SyncStep::Delete { removes_remote: true, tombstone, .. } if tombstone.id == retired.id
+        )));
+
+        let mut applied = 0;
+        let report = SyncEngine::apply(
+            &local,
+            &remote,
+            &KEY,
+            plan,
+            &mut FixedResolver(ConflictChoice::Remote),
+            |_| applied += 1,
+        )
+        .await
+        .expect("apply");
+        assert_eq!(applied, 3);
+        assert_eq!((report.uploaded, report.remote_deletions), (2, 1));
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(titles(&remote).await, vec!["local edit", "new"]);
+    }
+
+    #[tokio::test]
+    async fn empty_remote_is_bootstrapped_with_the_local_vault() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        local
+            .set_auth_record(&auth_record("laptop"))
+            .await
+            .expect("auth");
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "item", Utc::now()))
+            .await
+            .expect("upsert");
+
+        let plan = SyncEngine::plan(&local, &remote, "test", &KEY, ConflictPolicy::KeepBoth)
+            .await
+            .expect("plan");
+        assert!(plan.bootstraps_remote());
+        assert!(remote.get_auth_record().await.expect("auth").is_none());
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        let bootstrapped = remote.get_auth_record().await.expect("auth").expect("set");
+        assert_eq!(bootstrapped.salt, "laptop");
+        let other = MemoryRepo::default();
+        other
+            .set_auth_record(&AuthRecord {
+                metadata_privacy: true,
+                ..auth_record("laptop")
+            })
+            .await
+            .expect("auth");
+        assert_eq!(
+            sync(&other, &remote, ConflictPolicy::KeepBoth)
+                .await
+                .downloaded,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn password_change_pushes_the_new_key_wrapping() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        let other = MemoryRepo::default();
+        local
+            .set_auth_record(&auth_record("laptop"))
+            .await
+            .expect("auth");
+        other
+            .set_auth_record(&auth_record("laptop"))
+            .await
+            .expect("auth");
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "before", Utc::now()))
+            .await
+            .expect("upsert");
+        sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+
+        local
+            .set_auth_record(&AuthRecord {
+                wrapped_vault_key: Some(vec![6; 48]),
+                revision: 1,
+                ..auth_record("rotated")
+            })
+            .await
+            .expect("auth");
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "after", Utc::now()))
+            .await
+            .expect("upsert");
+
+        let plan = SyncEngine::plan(&local, &remote, "test", &KEY, ConflictPolicy::KeepBoth)
+            .await
+            .expect("plan");
+        assert!(plan.updates_remote_auth());
+        assert!(!plan.bootstraps_remote());
+        let report = sync(&local, &remote, ConflictPolicy::KeepBoth).await;
+        assert_eq!(report.uploaded, 1);
+        let pushed = remote.get_auth_record().await.expect("auth").expect("set");
+        assert_eq!((pushed.salt.as_str(), pushed.revision), ("rotated", 1));
+
+        let stale = sync(&other, &remote, ConflictPolicy::KeepBoth).await;
+        assert_eq!(stale.downloaded, 2);
+        let kept = remote.get_auth_record().await.expect("auth").expect("set");
+        assert_eq!((kept.salt.as_str(), kept.revision), ("rotated", 1));
+        let again = SyncEngine::plan(&local, &remote, "test", &KEY, ConflictPolicy::KeepBoth)
+            .await
+            .expect("plan");
+        assert!(!again.updates_remote_auth());
+    }
+
+    #[tokio::test]
+    async fn sync_refuses_a_remote_from_another_vault() {
+        let local = MemoryRepo::default();
+        let remote = MemoryRepo::default();
+        local
+            .set_auth_record(&auth_record("laptop"))
+            .await
+            .expect("auth");
+        remote
+            .set_auth_record(&auth_record("stranger"))
+            .await
+            .expect("auth");
+        local
+            .upsert_item(&build_item(Uuid::new_v4(), "local", Utc::now()))
+            .await
+            .expect("upsert");
+        remote
+            .upsert_item(&build_item(Uuid::new_v4(), "remote", Utc::now()))
+            .await
+            .expect("upsert");
+
+        let result = try_sync(&local, &remote).await;
+
+        assert!(matches!(
+            result,
+            Err(ChacrabError::Config(message)) if message == "sync remote belongs to a different vault"
+        ));
+        assert_eq!(titles(&local).await, vec!["local"]);
+        assert_eq!(titles(&remote).await, vec!["remote"]);
+        assert_eq!(
+            remote
+                .get_auth_record()
+                .await
+                .expect("auth")
+                .expect("set")
+                .salt,
+            "stranger"
+        );
+
+        let foreign = manifest::seal(1, Default::default(), &[3u8; 32]).expect("seal");
+        remote.set_manifest(&foreign).await.expect("manifest");
+        assert!(matches!(
+            try_sync(&local, &remote).await,
+            Err(ChacrabError::Config(message)) if message == "sync remote belongs to a different vault"
+        ));
+    }
 }
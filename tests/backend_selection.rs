@@ -1,11 +1,24 @@
+use std::path::PathBuf;
+
 use secrecy::SecretString;
+use uuid::Uuid;
 
 use chacrab::{
     auth::login,
-    core::errors::{ChacrabError, ChacrabResult},
+    core::{
+        errors::{ChacrabError, ChacrabResult},
+        vault::VaultService,
+    },
     storage::{app::AppRepository, r#trait::VaultRepository},
+    sync::sync_engine::{ConflictPolicy, SyncEngine, SyncStep},
 };
 
+fn temp_db_url() -> (String, PathBuf) {
+    let mut path = std::env::temp_dir();
+    path.push(format!("chacrab-remote-{}.db", Uuid::new_v4()));
+    (format!("sqlite://{}?mode=rwc", path.display()), path)
+}
+
 #[tokio::test]
 async fn app_repository_rejects_unknown_backend() {
     let result = AppRepository::connect("unknown", "ignored").await;
@@ -18,13 +31,51 @@ async fn sqlite_backend_selection_and_auth_roundtrip() -> ChacrabResult<()> {
     repo.init().await?;
 
     let master = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
-    login::register(&repo, master).await?;
+    login::register(&repo, master, false).await?;
 
     let auth = repo.get_auth_record().await?;
     assert!(auth.is_some());
     Ok(())
 }
 
+#[tokio::test]
+async fn dry_run_remote_is_opened_without_writing() -> ChacrabResult<()> {
+    let (missing_url, missing_path) = temp_db_url();
+    assert!(matches!(
+        AppRepository::open_existing("sqlite", &missing_url).await,
+        Err(ChacrabError::Config(message)) if message == "sync remote is not initialised"
+    ));
+    assert!(!missing_path.exists());
+
+    let master = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
+    let local = AppRepository::connect("sqlite", "sqlite::memory:").await?;
+    local.init().await?;
+    login::register(&local, master.clone(), false).await?;
+    let key = login::unlock(&local, &master).await?;
+    VaultService::new(local.clone())
+        .add_note(
+            "Recovery".to_owned(),
+            SecretString::new("backup-codes".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+
+    let (url, path) = temp_db_url();
+    let remote = AppRepository::connect("sqlite", &url).await?;
+    remote.init().await?;
+    remote
+        .set_auth_record(&local.get_auth_record().await?.expect("auth record"))
+        .await?;
+    let before = std::fs::read(&path).expect("remote database");
+
+    let remote = AppRepository::open_existing("sqlite", &url).await?;
+    let plan = SyncEngine::plan(&local, &remote, "remote", &key, ConflictPolicy::KeepBoth).await?;
+    assert!(matches!(plan.steps.as_slice(), [SyncStep::Upload(_)]));
+    assert_eq!(std::fs::read(&path).expect("remote database"), before);
+    let _ = std::fs::remove_file(path);
+    Ok(())
+}
+
 #[tokio::test]
 async fn postgres_backend_selection_if_env_configured() -> ChacrabResult<()> {
     let Ok(url) = std::env::var("CHACRAB_TEST_POSTGRES_URL") else {
@@ -0,0 +1,189 @@
+use std::{
+    io::Write,
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+use chrono::{Duration, Utc};
+use secrecy::SecretString;
+use uuid::Uuid;
+
+use chacrab::{
+    auth::login,
+    core::{errors::ChacrabResult, vault::VaultService},
+    storage::{app::AppRepository, r#trait::VaultRepository},
+};
+
+const MASTER_PASSWORD: &str = "MasterPass12!";
+
+struct CliEnv {
+    dir: PathBuf,
+    profile: String,
+}
+
+impl CliEnv {
+    fn new() -> Self {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("chacrab-cli-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).expect("temp dir");
+        Self {
+            dir,
+            profile: format!("cli-{}", &Uuid::new_v4().simple().to_string()[..12]),
+        }
+    }
+
+    fn database_url(&self) -> String {
+        format!("sqlite://{}?mode=rwc", self.dir.join("vault.db").display())
+    }
+
+    fn run(&self, args: &[&str], stdin: Option<&str>) -> Output {
+        self.run_with_env(args, stdin, &[])
+    }
+
+    fn run_with_env(&self, args: &[&str], stdin: Option<&str>, envs: &[(&str, &str)]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_chacrab"))
+            .args(["--profile", &self.profile, "--no-input"])
+            .args(args)
+            .env("CHACRAB_CONFIG_PATH", self.dir.join("config.toml"))
+            .env("HOME", &self.dir)
+            .envs(envs.iter().copied())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .expect("spawn chacrab");
+        let mut pipe = child.stdin.take().expect("stdin");
+        if let Some(input) = stdin {
+            pipe.write_all(input.as_bytes()).expect("write stdin");
+        }
+        drop(pipe);
+        child.wait_with_output().expect("chacrab output")
+    }
+}
+
+impl Drop for CliEnv {
+    fn drop(&mut self) {
+        let _ = self.run(&["logout"], None);
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn output_text(output: &Output) -> String {
+    format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    )
+}
+
+fn login_or_skip(env: &CliEnv, url: &str) -> bool {
+    let created = env.run(&["profile", "create", &env.profile, url], None);
+    assert!(created.status.success(), "{}", output_text(&created));
+
+    let login = env.run(&["login", "--password-stdin"], Some(MASTER_PASSWORD));
+    if !login.status.success() && output_text(&login).contains("keyring") {
+        eprintln!("skipping CLI test: OS keyring unavailable");
+        return false;
+    }
+    assert!(login.status.success(), "{}", output_text(&login));
+    true
+}
+
+async fn init_vault(url: &str) -> ChacrabResult<AppRepository> {
+    let repo = AppRepository::connect("sqlite", url).await?;
+    repo.init().await?;
+    let master = SecretString::new(MASTER_PASSWORD.to_owned().into_boxed_str());
+    login::register(&repo, master, false).await?;
+    Ok(repo)
+}
+
+#[tokio::test]
+async fn add_password_without_input_reads_secret_from_stdin() -> ChacrabResult<()> {
+    let env = CliEnv::new();
+    let url = env.database_url();
+    let repo = init_vault(&url).await?;
+    if !login_or_skip(&env, &url) {
+        return Ok(());
+    }
+
+    let added = env.run(
+        &[
+            "add-password",
+            "--title",
+            "GitHub",
+            "--username",
+            "moonliez",
+            "--url",
+            "https://github.com",
+            "--password-stdin",
+        ],
+        Some("Secret#123\n"),
+    );
+    assert!(added.status.success(), "{}", output_text(&added));
+
+    let master = SecretString::new(MASTER_PASSWORD.to_owned().into_boxed_str());
+    let key = login::unlock(&repo, &master).await?;
+    let service = VaultService::new(repo);
+    let items = service.list().await?;
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].title, "GitHub");
+    let (_item, payload) = service.show_decrypted(items[0].id, &key).await?;
+    assert_eq!(payload["password"].as_str(), Some("Secret#123"));
+
+    let refused = env.run(&["delete", "GitHub"], None);
+    assert!(!refused.status.success());
+    assert!(output_text(&refused).contains("--yes"));
+    assert_eq!(service.list().await?.len(), 1);
+
+    let deleted = env.run(&["delete", "GitHub", "--yes"], None);
+    assert!(deleted.status.success(), "{}", output_text(&deleted));
+    let items = service.list().await?;
+    assert!(items[0].deleted_at.is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn sync_dry_run_leaves_expired_trash_alone() -> ChacrabResult<()> {
+    let env = CliEnv::new();
+    let url = env.database_url();
+    let repo = init_vault(&url).await?;
+    let master = SecretString::new(MASTER_PASSWORD.to_owned().into_boxed_str());
+    let key = login::unlock(&repo, &master).await?;
+    let service = VaultService::new(repo.clone());
+    let item = service
+        .add_note(
+            "Old".to_owned(),
+            SecretString::new("expired".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+
+    let remote_path = env.dir.join("remote.db");
+    std::fs::copy(env.dir.join("vault.db"), &remote_path).expect("copy remote");
+    let wal = env.dir.join("vault.db-wal");
+    if wal.exists() {
+        std::fs::copy(wal, env.dir.join("remote.db-wal")).expect("copy remote wal");
+    }
+    let remote_url = format!("sqlite://{}?mode=rwc", remote_path.display());
+
+    service.move_to_trash(item.id).await?;
+    let mut trashed = repo.get_item(item.id).await?;
+    trashed.deleted_at = Some(Utc::now() - Duration::days(365));
+    repo.upsert_item(&trashed).await?;
+
+    if !login_or_skip(&env, &url) {
+        return Ok(());
+    }
+    let planned = env.run_with_env(
+        &["sync", "--dry-run"],
+        None,
+        &[
+            ("CHACRAB_SYNC_BACKEND", "sqlite"),
+            ("CHACRAB_SYNC_DATABASE_URL", &remote_url),
+        ],
+    );
+    assert!(planned.status.success(), "{}", output_text(&planned));
+    assert!(repo.get_item(item.id).await?.deleted_at.is_some());
+    assert!(repo.list_tombstones().await?.is_empty());
+    Ok(())
+}
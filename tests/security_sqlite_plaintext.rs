@@ -2,7 +2,7 @@ use secrecy::SecretString;
 
 use chacrab::{
     auth::login,
-    core::{crypto, errors::ChacrabResult, vault::VaultService},
+    core::{errors::ChacrabResult, vault::VaultService},
     storage::{sqlite::SqliteRepository, r#trait::VaultRepository},
 };
 
@@ -10,11 +10,7 @@ async fn session_key(
     repo: &SqliteRepository,
     master_password: &SecretString,
 ) -> ChacrabResult<[u8; 32]> {
-    let auth = repo
-        .get_auth_record()
-        .await?
-        .ok_or(chacrab::core::errors::ChacrabError::Storage)?;
-    crypto::verify_password(master_password, &auth.salt, &auth.verifier)
+    login::unlock(repo, master_password).await
 }
 
 #[tokio::test]
@@ -23,7 +19,7 @@ async fn sqlite_ciphertext_never_contains_password_or_note_plaintext() -> Chacra
     repo.init().await?;
 
     let master_password = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
-    login::register(&repo, master_password.clone()).await?;
+    login::register(&repo, master_password.clone(), false).await?;
     let key = session_key(&repo, &master_password).await?;
 
     let vault = VaultService::new(repo.clone());
@@ -63,3 +59,87 @@ async fn sqlite_ciphertext_never_contains_password_or_note_plaintext() -> Chacra
 
     Ok(())
 }
+
+#[tokio::test]
+async fn sqlite_private_metadata_never_stores_titles_usernames_or_urls() -> ChacrabResult<()> {
+    let repo = SqliteRepository::connect("sqlite::memory:").await?;
+    repo.init().await?;
+
+    let master_password = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
+    login::register(&repo, master_password.clone(), false).await?;
+    let key = session_key(&repo, &master_password).await?;
+
+    let vault = VaultService::new(repo.clone());
+    let note = vault
+        .add_note(
+            "Bank Recovery".to_owned(),
+            SecretString::new("added before privacy".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    assert_eq!(vault.set_metadata_privacy(true, &key).await?, 1);
+    assert_eq!(repo.get_item(note.id).await?.revision, note.revision + 1);
+
+    let email = vault
+        .add_password(
+            "Email".to_owned(),
+            Some("alice@example.com".to_owned()),
+            Some("https://mail.example.com".to_owned()),
+            SecretString::new("SuperSecret#123".to_owned().into_boxed_str()),
+            None,
+            &key,
+        )
+        .await?;
+    vault
+        .add_tags(email.id, vec!["Mailbox".to_owned()], &key)
+        .await?;
+    vault.move_to_folder(email.id, "Personal", &key).await?;
+    vault.set_favorite(email.id, true, &key).await?;
+
+    let rows = repo.list_items().await?;
+    assert_eq!(rows.len(), 2);
+
+    for row in &rows {
+        assert!(row.title.is_empty());
+        assert!(row.username.is_none());
+        assert!(row.url.is_none());
+        assert!(row.folder.is_none());
+        assert!(row.tags.is_empty());
+        assert!(!row.favorite);
+        let metadata = row
+            .encrypted_metadata
+            .as_deref()
+            .expect("metadata should be sealed");
+        for blob in [row.encrypted_data.as_slice(), metadata] {
+            let blob_view = String::from_utf8_lossy(blob);
+            assert!(!blob_view.contains("Email"));
+            assert!(!blob_view.contains("Bank Recovery"));
+            assert!(!blob_view.contains("alice@example.com"));
+            assert!(!blob_view.contains("mail.example.com"));
+            assert!(!blob_view.contains("Mailbox"));
+            assert!(!blob_view.contains("Personal"));
+        }
+    }
+
+    let mut titles: Vec<String> = vault
+        .list_revealed(&key)
+        .await?
+        .into_iter()
+        .map(|item| item.title)
+        .collect();
+    titles.sort();
+    assert_eq!(titles, vec!["Bank Recovery".to_owned(), "Email".to_owned()]);
+
+    let (revealed, _) = vault.show_decrypted(email.id, &key).await?;
+    assert_eq!(revealed.username.as_deref(), Some("alice@example.com"));
+    assert_eq!(revealed.folder.as_deref(), Some("Personal"));
+    assert_eq!(revealed.tags, vec!["Mailbox".to_owned()]);
+    assert!(revealed.favorite);
+
+    assert_eq!(vault.set_metadata_privacy(false, &key).await?, 2);
+    let rows = repo.list_items().await?;
+    assert!(rows.iter().all(|row| row.encrypted_metadata.is_none()));
+    assert!(rows.iter().any(|row| row.title == "Email" && row.favorite));
+
+    Ok(())
+}
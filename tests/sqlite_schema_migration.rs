@@ -0,0 +1,98 @@
+use std::path::PathBuf;
+
+use secrecy::SecretString;
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use chacrab::{
+    auth::login,
+    core::errors::{ChacrabError, ChacrabResult},
+    storage::{sqlite::SqliteRepository, r#trait::VaultRepository},
+};
+
+fn temp_db_url() -> (String, PathBuf) {
+    let mut path = std::env::temp_dir();
+    path.push(format!("chacrab-migration-{}.db", Uuid::new_v4()));
+    (format!("sqlite://{}?mode=rwc", path.display()), path)
+}
+
+#[tokio::test]
+async fn init_upgrades_version_one_schema() -> ChacrabResult<()> {
+    let (url, path) = temp_db_url();
+    let pool = SqlitePool::connect(&url).await?;
+    for statement in [
+        "CREATE TABLE schema_meta (id INTEGER PRIMARY KEY, schema_version INTEGER NOT NULL)",
+        "INSERT INTO schema_meta (id, schema_version) VALUES (1, 1)",
+        "CREATE TABLE auth (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            salt TEXT NOT NULL,
+            verifier TEXT NOT NULL,
+            argon2_m_cost INTEGER NOT NULL,
+            argon2_t_cost INTEGER NOT NULL,
+            argon2_p_cost INTEGER NOT NULL
+        )",
+        "CREATE TABLE vault_items (
+            id TEXT PRIMARY KEY,
+            item_type TEXT NOT NULL,
+            title TEXT NOT NULL,
+            username TEXT,
+            url TEXT,
+            encrypted_data BLOB NOT NULL,
+            nonce BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )",
+    ] {
+        sqlx::query(statement).execute(&pool).await?;
+    }
+
+    let repo = SqliteRepository::connect(&url).await?;
+    repo.init().await?;
+    repo.init().await?;
+
+    let master = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
+    login::register(&repo, master.clone(), false).await?;
+    let auth = repo.get_auth_record().await?.expect("auth record");
+    assert!(auth.wrapped_vault_key.is_some());
+    assert!(auth.wrapped_vault_key_nonce.is_some());
+    assert!(repo.has_legacy_items().await?);
+    login::unlock(&repo, &master).await?;
+    assert!(!repo.has_legacy_items().await?);
+
+    let _ = std::fs::remove_file(path);
+    Ok(())
+}
+
+#[tokio::test]
+async fn item_type_from_newer_client_is_skipped_in_listings() -> ChacrabResult<()> {
+    let (url, path) = temp_db_url();
+    let repo = SqliteRepository::connect(&url).await?;
+    repo.init().await?;
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let pool = SqlitePool::connect(&url).await?;
+    let future_id = Uuid::new_v4();
+    for (id, item_type) in [(future_id, "passkey"), (Uuid::new_v4(), "note")] {
+        sqlx::query(
+            "INSERT INTO vault_items (id, item_type, title, encrypted_data, nonce, format_version, created_at, updated_at)
+             VALUES (?, ?, 'Stored item', X'00', X'000000000000000000000000', 2, ?, ?)",
+        )
+        .bind(id.to_string())
+        .bind(item_type)
+        .bind(&now)
+        .bind(&now)
+        .execute(&pool)
+        .await?;
+    }
+
+    let items = repo.list_items().await?;
+    assert_eq!(items.len(), 1);
+    assert!(items.iter().all(|item| item.id != future_id));
+    assert!(matches!(
+        repo.get_item(future_id).await,
+        Err(ChacrabError::UnsupportedItemType(item_type)) if item_type == "passkey"
+    ));
+
+    let _ = std::fs::remove_file(path);
+    Ok(())
+}
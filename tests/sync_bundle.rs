@@ -0,0 +1,182 @@
+use secrecy::SecretString;
+
+use chacrab::{
+    auth::login,
+    core::{
+        crypto,
+        errors::{ChacrabError, ChacrabResult},
+        models::{ItemChanges, VaultItem},
+        vault::{self, VaultService},
+    },
+    storage::{app::AppRepository, bundle::BundleRepository, r#trait::VaultRepository},
+    sync::{
+        bundle::{self, ChangeBundle, EncryptedBundleFile},
+        sync_engine::{ConflictChoice, ConflictPolicy, ConflictResolver, SyncEngine, SyncReport},
+    },
+};
+
+struct KeepCopies<'a>(&'a [u8; 32]);
+
+impl ConflictResolver for KeepCopies<'_> {
+    fn choose(&mut self, _: &VaultItem, _: &VaultItem) -> ChacrabResult<ConflictChoice> {
+        Ok(ConflictChoice::KeepBoth)
+    }
+
+    fn duplicate(&mut self, item: &VaultItem) -> ChacrabResult<VaultItem> {
+        vault::duplicate_item(item, self.0)
+    }
+}
+
+fn secret(value: &str) -> SecretString {
+    SecretString::new(value.to_owned().into_boxed_str())
+}
+
+async fn local_vault() -> ChacrabResult<(AppRepository, VaultService<AppRepository>)> {
+    let repo = AppRepository::connect("sqlite", "sqlite::memory:").await?;
+    repo.init().await?;
+    Ok((repo.clone(), VaultService::new(repo)))
+}
+
+async fn paired_vaults() -> ChacrabResult<(
+    [u8; 32],
+    (AppRepository, VaultService<AppRepository>),
+    (AppRepository, VaultService<AppRepository>),
+)> {
+    let master = secret("MasterPass12!");
+    let laptop = local_vault().await?;
+    login::register(&laptop.0, master.clone(), false).await?;
+    let key = login::unlock(&laptop.0, &master).await?;
+    let desktop = local_vault().await?;
+    let auth = laptop.0.get_auth_record().await?.expect("auth record");
+    desktop.0.set_auth_record(&auth).await?;
+    Ok((key, laptop, desktop))
+}
+
+async fn carry(from: &AppRepository, key: &[u8; 32], full: bool) -> ChacrabResult<ChangeBundle> {
+    let mut written = None;
+    SyncEngine::export_bundle(from, key, full, |bundle| {
+        written = Some(serde_json::to_string(&bundle::seal(bundle, key)?)?);
+        Ok(())
+    })
+    .await?;
+    let file: EncryptedBundleFile = serde_json::from_str(&written.expect("bundle written"))?;
+    bundle::open(&file, key)
+}
+
+async fn import(
+    to: &AppRepository,
+    bundle: ChangeBundle,
+    key: &[u8; 32],
+) -> ChacrabResult<SyncReport> {
+    let since = bundle.since;
+    let remote = AppRepository::Bundle(BundleRepository::new(bundle));
+    let plan =
+        SyncEngine::plan_bundle_import(to, &remote, since, key, ConflictPolicy::KeepBoth).await?;
+    SyncEngine::apply(to, &remote, key, plan, &mut KeepCopies(key), |_| {}).await
+}
+
+#[tokio::test]
+async fn offline_vaults_converge_through_bundles() -> ChacrabResult<()> {
+    let (key, (laptop, laptop_vault), (desktop, desktop_vault)) = paired_vaults().await?;
+    laptop_vault
+        .add_note("Recovery".to_owned(), secret("backup-codes"), &key)
+        .await?;
+    let login = laptop_vault
+        .add_password(
+            "GitHub".to_owned(),
+            Some("moonliez".to_owned()),
+            None,
+            secret("Secret#123"),
+            None,
+            &key,
+        )
+        .await?;
+
+    let first = carry(&laptop, &key, false).await?;
+    assert_eq!((first.since, first.items.len()), (0, 2));
+    assert_eq!(import(&desktop, first, &key).await?.downloaded, 2);
+    let (_item, payload) = desktop_vault.show_decrypted(login.id, &key).await?;
+    assert_eq!(payload["password"].as_str(), Some("Secret#123"));
+
+    desktop_vault.move_to_trash(login.id).await?;
+    desktop_vault.empty_trash().await?;
+    desktop_vault
+        .add_note("Wifi".to_owned(), secret("hunter2"), &key)
+        .await?;
+    let reply = carry(&desktop, &key, false).await?;
+    assert_eq!(
+        (reply.since, reply.items.len(), reply.tombstones.len()),
+        (0, 2, 1)
+    );
+    let report = import(&laptop, reply, &key).await?;
+    assert_eq!((report.downloaded, report.local_deletions), (1, 1));
+    assert_eq!(laptop.list_items().await?.len(), 2);
+    assert!(matches!(
+        laptop.get_item(login.id).await,
+        Err(ChacrabError::NotFound)
+    ));
+
+    let echo = carry(&laptop, &key, false).await?;
+    assert!(echo.items.is_empty() && echo.tombstones.is_empty());
+    assert_eq!(import(&desktop, echo, &key).await?.downloaded, 0);
+    assert_eq!(carry(&laptop, &key, true).await?.items.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn bundles_are_applied_in_order_and_only_once() -> ChacrabResult<()> {
+    let (key, (laptop, laptop_vault), (desktop, _desktop_vault)) = paired_vaults().await?;
+    laptop_vault
+        .add_note("First".to_owned(), secret("one"), &key)
+        .await?;
+    let first = carry(&laptop, &key, false).await?;
+    laptop_vault
+        .add_note("Second".to_owned(), secret("two"), &key)
+        .await?;
+    let second = carry(&laptop, &key, false).await?;
+    assert_eq!(second.items.len(), 1);
+
+    assert!(matches!(
+        import(&desktop, second.clone(), &key).await,
+        Err(ChacrabError::Config(message)) if message == "sync bundle is missing earlier changes"
+    ));
+    import(&desktop, first.clone(), &key).await?;
+    import(&desktop, second, &key).await?;
+    assert_eq!(desktop.list_items().await?.len(), 2);
+    assert!(matches!(
+        import(&desktop, first.clone(), &key).await,
+        Err(ChacrabError::Integrity(_))
+    ));
+
+    let sealed = bundle::seal(&first, &key)?;
+    assert!(matches!(
+        bundle::open(&sealed, &crypto::generate_vault_key()),
+        Err(ChacrabError::Config(message)) if message == "sync bundle could not be decrypted"
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn concurrent_offline_edits_are_resolved_as_conflicts() -> ChacrabResult<()> {
+    let (key, (laptop, laptop_vault), (desktop, desktop_vault)) = paired_vaults().await?;
+    let note = laptop_vault
+        .add_note("Shared".to_owned(), secret("v1"), &key)
+        .await?;
+    import(&desktop, carry(&laptop, &key, false).await?, &key).await?;
+
+    let edit = |title: &str| ItemChanges {
+        title: Some(title.to_owned()),
+        ..ItemChanges::default()
+    };
+    laptop_vault
+        .update_item(note.id, edit("Laptop edit"), &key)
+        .await?;
+    desktop_vault
+        .update_item(note.id, edit("Desktop edit"), &key)
+        .await?;
+
+    let report = import(&desktop, carry(&laptop, &key, false).await?, &key).await?;
+    assert_eq!(report.conflicts.len(), 1);
+    assert_eq!(desktop.list_items().await?.len(), 2);
+    Ok(())
+}
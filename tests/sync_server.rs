@@ -0,0 +1,201 @@
+use secrecy::SecretString;
+use tokio::net::TcpListener;
+
+use chacrab::{
+    auth::login,
+    core::{
+        crypto,
+        errors::{ChacrabError, ChacrabResult},
+        models::VaultItem,
+        vault::VaultService,
+    },
+    storage::{
+        app::AppRepository, remote::RemoteRepository, sqlite::SqliteRepository,
+        r#trait::VaultRepository,
+    },
+    sync::{
+        server,
+        sync_engine::{ConflictChoice, ConflictPolicy, ConflictResolver, SyncEngine},
+    },
+};
+
+const TOKEN: &str = "local-test-token";
+
+struct NoConflicts;
+
+impl ConflictResolver for NoConflicts {
+    fn choose(&mut self, _: &VaultItem, _: &VaultItem) -> ChacrabResult<ConflictChoice> {
+        Err(ChacrabError::Config("unexpected conflict".to_owned()))
+    }
+
+    fn duplicate(&mut self, _: &VaultItem) -> ChacrabResult<VaultItem> {
+        Err(ChacrabError::Config("unexpected conflict".to_owned()))
+    }
+}
+
+async fn start_server() -> ChacrabResult<(SqliteRepository, String)> {
+    let storage = SqliteRepository::connect("sqlite::memory:").await?;
+    storage.init().await?;
+    let listener = TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("bind localhost");
+    let address = listener.local_addr().expect("local address");
+    let router = server::router(
+        storage.clone(),
+        SecretString::new(TOKEN.to_owned().into_boxed_str()),
+    );
+    tokio::spawn(async move { axum::serve(listener, router).await });
+    Ok((storage, address.to_string()))
+}
+
+async fn local_vault() -> ChacrabResult<(AppRepository, VaultService<AppRepository>)> {
+    let repo = AppRepository::connect("sqlite", "sqlite::memory:").await?;
+    repo.init().await?;
+    Ok((repo.clone(), VaultService::new(repo)))
+}
+
+async fn sync(
+    local: &AppRepository,
+    remote: &AppRepository,
+    key: &[u8; 32],
+) -> ChacrabResult<(u64, u64, u64)> {
+    let report = SyncEngine::sync_bidirectional(
+        local,
+        remote,
+        "sync-server",
+        key,
+        ConflictPolicy::KeepBoth,
+        &mut NoConflicts,
+    )
+    .await?;
+    Ok((report.uploaded, report.downloaded, report.local_deletions))
+}
+
+#[tokio::test]
+async fn items_and_deletions_travel_through_the_sync_server() -> ChacrabResult<()> {
+    let (storage, address) = start_server().await?;
+    let remote = AppRepository::connect("http", &format!("http://:{TOKEN}@{address}")).await?;
+    remote.init().await?;
+
+    let master = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
+    let key = crypto::derive_key(&master, &crypto::generate_salt())?;
+    let (laptop, laptop_vault) = local_vault().await?;
+    let (desktop, desktop_vault) = local_vault().await?;
+
+    let item = laptop_vault
+        .add_password(
+            "GitHub".to_owned(),
+            Some("moonliez".to_owned()),
+            None,
+            SecretString::new("Secret#123".to_owned().into_boxed_str()),
+            None,
+            &key,
+        )
+        .await?;
+
+    assert_eq!(sync(&laptop, &remote, &key).await?, (1, 0, 0));
+    assert_eq!(sync(&desktop, &remote, &key).await?, (0, 1, 0));
+    let (_stored, payload) = desktop_vault.show_decrypted(item.id, &key).await?;
+    assert_eq!(payload["password"].as_str(), Some("Secret#123"));
+
+    let stored = storage.get_item(item.id).await?;
+    assert!(
+        !stored
+            .encrypted_data
+            .windows(b"Secret#123".len())
+            .any(|window| window == b"Secret#123")
+    );
+
+    desktop_vault.delete(item.id).await?;
+    sync(&desktop, &remote, &key).await?;
+    assert_eq!(storage.list_tombstones().await?.len(), 1);
+    assert_eq!(sync(&laptop, &remote, &key).await?, (0, 0, 1));
+    assert!(laptop.list_items().await?.is_empty());
+    assert_eq!(sync(&laptop, &remote, &key).await?, (0, 0, 0));
+    Ok(())
+}
+
+#[tokio::test]
+async fn sync_bootstraps_the_server_and_refuses_other_vaults() -> ChacrabResult<()> {
+    let (storage, address) = start_server().await?;
+    let remote = AppRepository::connect("http", &format!("http://:{TOKEN}@{address}")).await?;
+    remote.init().await?;
+    let master = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
+
+    let (laptop, laptop_vault) = local_vault().await?;
+    login::register(&laptop, master.clone(), false).await?;
+    let key = login::unlock(&laptop, &master).await?;
+    laptop_vault
+        .add_note(
+            "Recovery".to_owned(),
+            SecretString::new("backup-codes".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    assert_eq!(sync(&laptop, &remote, &key).await?, (1, 0, 0));
+    let bootstrapped = storage.get_auth_record().await?.expect("remote auth");
+    assert_eq!(
+        bootstrapped.salt,
+        laptop.get_auth_record().await?.expect("local auth").salt
+    );
+
+    let (stranger, stranger_vault) = local_vault().await?;
+    login::register(&stranger, master.clone(), false).await?;
+    let stranger_key = login::unlock(&stranger, &master).await?;
+    stranger_vault
+        .add_note(
+            "Other".to_owned(),
+            SecretString::new("other-codes".to_owned().into_boxed_str()),
+            &stranger_key,
+        )
+        .await?;
+    assert!(matches!(
+        sync(&stranger, &remote, &stranger_key).await,
+        Err(ChacrabError::Config(message)) if message == "sync remote belongs to a different vault"
+    ));
+    assert_eq!(storage.list_items().await?.len(), 1);
+    assert_eq!(stranger.list_items().await?.len(), 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn sync_server_rejects_bad_tokens_and_invalid_blobs() -> ChacrabResult<()> {
+    let (_storage, address) = start_server().await?;
+
+    let intruder = RemoteRepository::connect(&format!("http://:wrong-token@{address}"))?;
+    intruder.init().await?;
+    assert!(matches!(
+        intruder.list_items().await,
+        Err(ChacrabError::Config(message)) if message == "sync server rejected the access token"
+    ));
+
+    let (_laptop, laptop_vault) = local_vault().await?;
+    let key = crypto::generate_vault_key();
+    let mut item = laptop_vault
+        .add_note(
+            "Recovery".to_owned(),
+            SecretString::new("backup-codes".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    item.encrypted_data.clear();
+
+    let remote = RemoteRepository::connect(&format!("http://:{TOKEN}@{address}"))?;
+    assert!(matches!(
+        remote.upsert_item(&item).await,
+        Err(ChacrabError::Config(_))
+    ));
+    assert!(matches!(
+        remote.get_item(item.id).await,
+        Err(ChacrabError::NotFound)
+    ));
+    Ok(())
+}
+
+#[test]
+fn remote_repository_requires_https_off_localhost() {
+    assert!(RemoteRepository::connect("http://sync.example.com").is_err());
+    assert!(RemoteRepository::connect("ftp://localhost").is_err());
+    assert!(RemoteRepository::connect("https://sync.example.com").is_ok());
+    assert!(RemoteRepository::connect("http://localhost:8787").is_ok());
+}
@@ -2,7 +2,17 @@ use secrecy::SecretString;
 use uuid::Uuid;
 
 use chacrab::{
-    core::{crypto, errors::ChacrabResult, vault::VaultService},
+    auth::login,
+    core::{
+        crypto,
+        errors::{ChacrabError, ChacrabResult},
+        models::{
+            CardDetails, CustomField, CustomFieldKind, EncryptedPayload, ItemChanges, ItemFilter,
+            LEGACY_ITEM_FORMAT_VERSION, NewVaultItem, VaultItemType,
+        },
+        totp,
+        vault::{self, VaultService},
+    },
     storage::{sqlite::SqliteRepository, r#trait::VaultRepository},
 };
 
@@ -39,6 +49,534 @@ async fn add_and_show_password_item() -> ChacrabResult<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn update_item_rotates_password_and_keeps_identity() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let item = service
+        .add_password(
+            "GitHub".to_owned(),
+            Some("moonliez".to_owned()),
+            Some("https://github.com".to_owned()),
+            SecretString::new("Secret#123".to_owned().into_boxed_str()),
+            Some("2FA enabled".to_owned()),
+            &key,
+        )
+        .await?;
+
+    let updated = service
+        .update_item(
+            item.id,
+            ItemChanges {
+                url: Some(None),
+                password: Some(SecretString::new("Rotated#456".to_owned().into_boxed_str())),
+                ..ItemChanges::default()
+            },
+            &key,
+        )
+        .await?;
+
+    assert_eq!(updated.id, item.id);
+    assert_eq!(updated.created_at, item.created_at);
+    assert!(updated.updated_at >= item.updated_at);
+    assert_ne!(updated.nonce, item.nonce);
+    assert_eq!((item.revision, updated.revision), (1, 2));
+    assert_eq!(updated.base_revision, item.base_revision);
+    assert_eq!(repo.list_items().await?.len(), 1);
+
+    let (stored, payload) = service.show_decrypted(item.id, &key).await?;
+    assert_eq!(stored.title, "GitHub");
+    assert_eq!(stored.username.as_deref(), Some("moonliez"));
+    assert_eq!(stored.url, None);
+    assert_eq!(payload["password"].as_str(), Some("Rotated#456"));
+    assert_eq!(payload["notes"].as_str(), Some("2FA enabled"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn totp_secret_is_stored_encrypted_and_generates_codes() -> ChacrabResult<()> {
+    let (_repo, service, key) = build_service().await?;
+    let mut payload = EncryptedPayload::for_password(
+        SecretString::new("Secret#123".to_owned().into_boxed_str()),
+        None,
+    );
+    payload.totp = Some(totp::parse_otpauth_uri(
+        "otpauth://totp/GitHub:moonliez?secret=JBSWY3DPEHPK3PXP&digits=8",
+    )?);
+    let item = service
+        .add_item(
+            NewVaultItem {
+                r#type: VaultItemType::Password,
+                title: "GitHub".to_owned(),
+                username: Some("moonliez".to_owned()),
+                url: None,
+                payload,
+            },
+            &key,
+        )
+        .await?;
+
+    let (_stored, code) = service.totp_code(item.id, &key).await?;
+    assert_eq!(code.code.len(), 8);
+    assert!(code.code.chars().all(|ch| ch.is_ascii_digit()));
+    assert!((1..=30).contains(&code.remaining_secs));
+
+    service
+        .update_item(
+            item.id,
+            ItemChanges {
+                totp: Some(None),
+                ..ItemChanges::default()
+            },
+            &key,
+        )
+        .await?;
+    assert!(matches!(
+        service.totp_code(item.id, &key).await,
+        Err(ChacrabError::Config(_))
+    ));
+    let (_stored, payload) = service.show_decrypted(item.id, &key).await?;
+    assert_eq!(payload["password"].as_str(), Some("Secret#123"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn card_payload_roundtrips_as_typed_schema() -> ChacrabResult<()> {
+    let (_repo, service, key) = build_service().await?;
+    let card = CardDetails {
+        cardholder_name: Some("Alice Example".to_owned()),
+        number: "4111111111111111".to_owned(),
+        expiry: Some("12/30".to_owned()),
+        security_code: Some("123".to_owned()),
+    };
+    let item = service
+        .add_item(
+            NewVaultItem {
+                r#type: VaultItemType::Card,
+                title: "Visa".to_owned(),
+                username: None,
+                url: None,
+                payload: EncryptedPayload::for_card(card.clone(), None),
+            },
+            &key,
+        )
+        .await?;
+
+    let (stored, payload) = service.show_payload(item.id, &key).await?;
+    assert_eq!(stored.r#type, VaultItemType::Card);
+    assert_eq!(payload.card, Some(card));
+    assert!(payload.password.is_none());
+
+    let (_stored, raw) = service.show_decrypted(item.id, &key).await?;
+    assert!(raw.get("identity").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn custom_fields_are_encrypted_and_replaced_on_update() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let mut payload = EncryptedPayload::for_password(
+        SecretString::new("Secret#123".to_owned().into_boxed_str()),
+        None,
+    );
+    payload.custom_fields = vec![
+        CustomField::new(
+            "Recovery PIN".to_owned(),
+            CustomFieldKind::Hidden,
+            "908172".to_owned(),
+        )?,
+        CustomField::new(
+            "Renewal".to_owned(),
+            CustomFieldKind::Date,
+            "2027-01-31".to_owned(),
+        )?,
+    ];
+    let item = service
+        .add_item(
+            NewVaultItem {
+                r#type: VaultItemType::Password,
+                title: "Bank".to_owned(),
+                username: None,
+                url: None,
+                payload,
+            },
+            &key,
+        )
+        .await?;
+
+    let rows = repo.list_items().await?;
+    assert!(!String::from_utf8_lossy(&rows[0].encrypted_data).contains("908172"));
+
+    let (_stored, payload) = service.show_payload(item.id, &key).await?;
+    assert_eq!(payload.custom_fields.len(), 2);
+    assert!(payload.custom_fields[0].is_hidden());
+
+    service
+        .update_item(
+            item.id,
+            ItemChanges {
+                custom_fields: Some(payload.custom_fields[1..].to_vec()),
+                ..ItemChanges::default()
+            },
+            &key,
+        )
+        .await?;
+    let (_stored, payload) = service.show_payload(item.id, &key).await?;
+    assert_eq!(payload.custom_fields.len(), 1);
+    assert_eq!(payload.custom_fields[0].name, "Renewal");
+    assert_eq!(payload.password.as_deref(), Some("Secret#123"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn tags_folders_and_favorites_filter_items() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let work = service
+        .add_note(
+            "Work VPN".to_owned(),
+            SecretString::new("vpn config".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    let personal = service
+        .add_note(
+            "Home Router".to_owned(),
+            SecretString::new("router config".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+
+    let tagged = service
+        .add_tags(
+            work.id,
+            vec![" VPN ".to_owned(), "work".to_owned(), "vpn".to_owned()],
+            &key,
+        )
+        .await?;
+    assert_eq!(tagged.tags, vec!["VPN".to_owned(), "work".to_owned()]);
+    service
+        .move_to_folder(work.id, " Work / Infra ", &key)
+        .await?;
+    service.set_favorite(personal.id, true, &key).await?;
+
+    let stored = repo.get_item(work.id).await?;
+    assert_eq!(stored.folder.as_deref(), Some("Work/Infra"));
+    assert_eq!(stored.tags, vec!["VPN".to_owned(), "work".to_owned()]);
+
+    let items = service.list_revealed(&key).await?;
+    let matching = |filter: ItemFilter| {
+        items
+            .iter()
+            .filter(|item| filter.matches(item))
+            .map(|item| item.id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        matching(ItemFilter {
+            tag: Some("Vpn".to_owned()),
+            ..ItemFilter::default()
+        }),
+        vec![work.id]
+    );
+    assert_eq!(
+        matching(ItemFilter {
+            folder: Some("Work".to_owned()),
+            ..ItemFilter::default()
+        }),
+        vec![work.id]
+    );
+    assert!(
+        matching(ItemFilter {
+            folder: Some("Wor".to_owned()),
+            ..ItemFilter::default()
+        })
+        .is_empty()
+    );
+    assert_eq!(
+        matching(ItemFilter {
+            favorites_only: true,
+            ..ItemFilter::default()
+        }),
+        vec![personal.id]
+    );
+
+    let untagged = service
+        .remove_tags(work.id, &["WORK".to_owned()], &key)
+        .await?;
+    assert_eq!(untagged.tags, vec!["VPN".to_owned()]);
+    assert_eq!(untagged.folder.as_deref(), Some("Work/Infra"));
+    let moved = service.move_to_folder(work.id, "/", &key).await?;
+    assert!(moved.folder.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn search_ranks_metadata_and_deep_matches() -> ChacrabResult<()> {
+    let (_repo, service, key) = build_service().await?;
+    let github = service
+        .add_password(
+            "GitHub".to_owned(),
+            Some("octocat".to_owned()),
+            Some("https://www.github.com/login".to_owned()),
+            SecretString::new("Secret#123".to_owned().into_boxed_str()),
+            None,
+            &key,
+        )
+        .await?;
+    let gist = service
+        .add_password(
+            "Gist mirror".to_owned(),
+            None,
+            Some("https://gist.github.com".to_owned()),
+            SecretString::new("Secret#456".to_owned().into_boxed_str()),
+            None,
+            &key,
+        )
+        .await?;
+    let recovery = service
+        .add_note(
+            "Bank".to_owned(),
+            SecretString::new("recovery codes for the safe".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    service
+        .add_tags(recovery.id, vec!["finance".to_owned()], &key)
+        .await?;
+
+    let hits = service.search("github", false, &key).await?;
+    let ids = hits.iter().map(|hit| hit.item.id).collect::<Vec<_>>();
+    assert_eq!(ids, vec![github.id, gist.id]);
+    assert_eq!(hits[0].matched, "title");
+    assert_eq!(hits[1].matched, "url");
+
+    let fuzzy = service.search("gthb", false, &key).await?;
+    assert_eq!(fuzzy.first().map(|hit| hit.item.id), Some(github.id));
+
+    let tagged = service.search("financ", false, &key).await?;
+    assert_eq!(tagged.len(), 1);
+    assert_eq!(tagged[0].matched, "tag");
+
+    assert!(service.search("recovery", false, &key).await?.is_empty());
+    let deep = service.search("bank recovery", true, &key).await?;
+    assert_eq!(deep.len(), 1);
+    assert_eq!(deep[0].item.id, recovery.id);
+
+    assert!(service.search("   ", false, &key).await.is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn updates_keep_encrypted_revisions_that_can_be_restored() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let item = service
+        .add_password(
+            "GitHub".to_owned(),
+            Some("octocat".to_owned()),
+            None,
+            SecretString::new("Original#123".to_owned().into_boxed_str()),
+            None,
+            &key,
+        )
+        .await?;
+    assert!(service.history(item.id, &key).await?.is_empty());
+
+    service
+        .update_item(
+            item.id,
+            ItemChanges {
+                title: Some("GitHub Work".to_owned()),
+                password: Some(SecretString::new("Rotated#456".to_owned().into_boxed_str())),
+                ..ItemChanges::default()
+            },
+            &key,
+        )
+        .await?;
+    service
+        .add_tags(item.id, vec!["work".to_owned()], &key)
+        .await?;
+
+    let stored = repo.list_revisions(item.id).await?;
+    assert_eq!(stored.len(), 2);
+    for revision in &stored {
+        let blob = String::from_utf8_lossy(&revision.encrypted_snapshot);
+        assert!(!blob.contains("GitHub"));
+        assert!(!blob.contains("octocat"));
+    }
+
+    let history = service.history(item.id, &key).await?;
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].revision, 1);
+    assert_eq!(history[0].item.title, "GitHub");
+    assert!(history[0].secrets_changed);
+    assert_eq!(history[0].changes.len(), 1);
+    assert_eq!(history[0].changes[0].field, "title");
+    assert_eq!(history[0].changes[0].after, "GitHub Work");
+    assert!(!history[1].secrets_changed);
+    assert_eq!(history[1].changes[0].field, "tags");
+
+    let restored = service.restore_revision(item.id, 1, &key).await?;
+    assert_eq!(restored.title, "GitHub");
+    assert!(restored.tags.is_empty());
+    let (_, payload) = service.show_payload(item.id, &key).await?;
+    assert_eq!(payload.password.as_deref(), Some("Original#123"));
+    assert_eq!(service.history(item.id, &key).await?.len(), 3);
+
+    assert!(matches!(
+        service.restore_revision(item.id, 42, &key).await,
+        Err(ChacrabError::Config(message)) if message == "revision not found"
+    ));
+
+    service.delete(item.id).await?;
+    assert!(repo.list_revisions(item.id).await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn restoring_a_revision_keeps_the_item_in_the_trash() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let item = service
+        .add_note(
+            "Draft".to_owned(),
+            SecretString::new("first".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    service
+        .update_item(
+            item.id,
+            ItemChanges {
+                title: Some("Final".to_owned()),
+                ..ItemChanges::default()
+            },
+            &key,
+        )
+        .await?;
+    service.move_to_trash(item.id).await?;
+    let trashed_at = repo.get_item(item.id).await?.deleted_at;
+    assert!(trashed_at.is_some());
+
+    let restored = service.restore_revision(item.id, 1, &key).await?;
+    assert_eq!(restored.title, "Draft");
+    assert_eq!(restored.deleted_at, trashed_at);
+    assert_eq!(repo.get_item(item.id).await?.deleted_at, trashed_at);
+
+    service.restore_from_trash(item.id, &key).await?;
+    service.restore_revision(item.id, 1, &key).await?;
+    assert!(repo.get_item(item.id).await?.deleted_at.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn revision_retention_prunes_oldest_revisions() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let service = service.with_revision_retention(2);
+    let item = service
+        .add_note(
+            "Rotating".to_owned(),
+            SecretString::new("note".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+
+    for index in 0..4 {
+        service
+            .move_to_folder(item.id, &format!("Folder {index}"), &key)
+            .await?;
+    }
+
+    let revisions = repo
+        .list_revisions(item.id)
+        .await?
+        .into_iter()
+        .map(|revision| revision.revision)
+        .collect::<Vec<_>>();
+    assert_eq!(revisions, vec![3, 4]);
+
+    let disabled = VaultService::new(repo.clone()).with_revision_retention(0);
+    disabled.set_favorite(item.id, true, &key).await?;
+    assert_eq!(repo.list_revisions(item.id).await?.len(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn trashed_items_are_hidden_restorable_and_purged() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let kept = service
+        .add_note(
+            "Kept".to_owned(),
+            SecretString::new("kept".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    let trashed = service
+        .add_note(
+            "Old Router".to_owned(),
+            SecretString::new("router".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+
+    service.move_to_trash(trashed.id).await?;
+    let visible = service.list_revealed(&key).await?;
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].id, kept.id);
+    assert!(service.search("router", false, &key).await?.is_empty());
+    let trash = service.list_trash(&key).await?;
+    assert_eq!(trash.len(), 1);
+    assert!(trash[0].deleted_at.is_some());
+    assert!(service.move_to_trash(trashed.id).await.is_err());
+
+    let restored = service.restore_from_trash(trashed.id, &key).await?;
+    assert!(restored.deleted_at.is_none());
+    assert_eq!(service.list_revealed(&key).await?.len(), 2);
+    assert!(matches!(
+        service.restore_from_trash(kept.id, &key).await,
+        Err(ChacrabError::Config(message)) if message == "item is not in trash"
+    ));
+
+    service.move_to_trash(trashed.id).await?;
+    let mut expired = repo.get_item(kept.id).await?;
+    expired.deleted_at = Some(chrono::Utc::now() - chrono::Duration::days(31));
+    repo.upsert_item(&expired).await?;
+
+    assert_eq!(service.purge_expired_trash().await?, 1);
+    assert!(matches!(
+        repo.get_item(kept.id).await,
+        Err(ChacrabError::NotFound)
+    ));
+    assert_eq!(service.list_trash(&key).await?.len(), 1);
+
+    assert_eq!(service.empty_trash().await?, 1);
+    assert!(repo.list_items().await?.is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn conflict_copy_is_reencrypted_under_a_new_id() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let item = service
+        .add_password(
+            "GitHub".to_owned(),
+            None,
+            None,
+            SecretString::new("Secret#123".to_owned().into_boxed_str()),
+            None,
+            &key,
+        )
+        .await?;
+
+    let copy = vault::duplicate_item(&item, &key)?;
+    assert_ne!(copy.id, item.id);
+    assert_eq!(copy.title, "GitHub (conflict copy)");
+    assert_eq!((copy.revision, copy.base_revision), (1, 0));
+    repo.upsert_item(&copy).await?;
+
+    let (_stored, payload) = service.show_decrypted(copy.id, &key).await?;
+    assert_eq!(payload["password"].as_str(), Some("Secret#123"));
+    Ok(())
+}
+
 #[tokio::test]
 async fn delete_removes_item() -> ChacrabResult<()> {
     let (_repo, service, key) = build_service().await?;
@@ -53,6 +591,45 @@ async fn delete_removes_item() -> ChacrabResult<()> {
     service.delete(item.id).await?;
     let result = service.show_decrypted(item.id, &key).await;
     assert!(result.is_err());
+
+    let tombstones = service.repository().list_tombstones().await?;
+    assert_eq!(tombstones.len(), 1);
+    assert_eq!(tombstones[0].id, item.id);
+    assert_eq!(tombstones[0].revision, item.revision);
+    Ok(())
+}
+
+#[tokio::test]
+async fn changes_since_returns_only_newer_changes() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let kept = service
+        .add_note(
+            "Kept".to_owned(),
+            SecretString::new("first".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    let removed = service
+        .add_note(
+            "Removed".to_owned(),
+            SecretString::new("second".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    let cursor = repo.changes_since(0).await?.cursor;
+    assert!(repo.changes_since(cursor).await?.items.is_empty());
+
+    service.delete(removed.id).await?;
+    let changes = repo.changes_since(cursor).await?;
+    assert!(changes.items.is_empty());
+    assert_eq!(changes.tombstones.len(), 1);
+    assert_eq!(changes.tombstones[0].id, removed.id);
+    assert!(changes.cursor > cursor);
+
+    let all = repo.changes_since(0).await?;
+    assert_eq!(all.items.len(), 1);
+    assert_eq!(all.items[0].id, kept.id);
+    assert_eq!(all.cursor, changes.cursor);
     Ok(())
 }
 
@@ -63,3 +640,119 @@ async fn show_missing_item_fails() -> ChacrabResult<()> {
     assert!(result.is_err());
     Ok(())
 }
+
+#[tokio::test]
+async fn change_master_password_rewraps_vault_key() -> ChacrabResult<()> {
+    let repo = SqliteRepository::connect("sqlite::memory:").await?;
+    repo.init().await?;
+    let old_password = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
+    let new_password = SecretString::new("RotatedPass34#".to_owned().into_boxed_str());
+    login::register(&repo, old_password.clone(), false).await?;
+
+    let vault_key = login::unlock(&repo, &old_password).await?;
+    let service = VaultService::new(repo.clone());
+    let item = service
+        .add_password(
+            "GitHub".to_owned(),
+            None,
+            None,
+            SecretString::new("Secret#123".to_owned().into_boxed_str()),
+            None,
+            &vault_key,
+        )
+        .await?;
+    let before = repo.get_item(item.id).await?;
+
+    let wrong = service
+        .change_master_password(&new_password, &new_password)
+        .await;
+    assert!(wrong.is_err());
+
+    service
+        .change_master_password(&old_password, &new_password)
+        .await?;
+
+    assert!(login::unlock(&repo, &old_password).await.is_err());
+    assert_eq!(login::unlock(&repo, &new_password).await?, vault_key);
+    assert_eq!(repo.get_auth_record().await?.expect("auth").revision, 1);
+
+    let after = repo.get_item(item.id).await?;
+    assert_eq!(after.encrypted_data, before.encrypted_data);
+    let (_, payload) = service.show_decrypted(item.id, &vault_key).await?;
+    assert_eq!(payload["password"].as_str(), Some("Secret#123"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn swapped_ciphertext_is_rejected() -> ChacrabResult<()> {
+    let (repo, service, key) = build_service().await?;
+    let first = service
+        .add_note(
+            "First".to_owned(),
+            SecretString::new("first secret".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    let second = service
+        .add_note(
+            "Second".to_owned(),
+            SecretString::new("second secret".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+
+    let mut tampered = repo.get_item(second.id).await?;
+    tampered.encrypted_data = first.encrypted_data.clone();
+    tampered.nonce = first.nonce;
+    repo.upsert_item(&tampered).await?;
+    let result = service.show_decrypted(second.id, &key).await;
+    assert!(matches!(result, Err(ChacrabError::Crypto)));
+
+    let mut retyped = repo.get_item(first.id).await?;
+    retyped.r#type = VaultItemType::Password;
+    repo.upsert_item(&retyped).await?;
+    let result = service.show_decrypted(first.id, &key).await;
+    assert!(matches!(result, Err(ChacrabError::Crypto)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn unlock_upgrades_legacy_item_format() -> ChacrabResult<()> {
+    let repo = SqliteRepository::connect("sqlite::memory:").await?;
+    repo.init().await?;
+    let master = SecretString::new("MasterPass12!".to_owned().into_boxed_str());
+    login::register(&repo, master.clone(), false).await?;
+    let key = login::unlock(&repo, &master).await?;
+    assert!(!repo.has_legacy_items().await?);
+
+    let service = VaultService::new(repo.clone());
+    let item = service
+        .add_note(
+            "Legacy".to_owned(),
+            SecretString::new("placeholder".to_owned().into_boxed_str()),
+            &key,
+        )
+        .await?;
+    let legacy_blob = crypto::encrypt(
+        &key,
+        br#"{"password":null,"notes":"legacy","custom_fields":{}}"#,
+    )?;
+    let mut legacy = item.clone();
+    legacy.encrypted_data = legacy_blob.ciphertext;
+    legacy.nonce = legacy_blob.nonce;
+    legacy.format_version = LEGACY_ITEM_FORMAT_VERSION;
+    repo.upsert_item(&legacy).await?;
+    assert!(repo.has_legacy_items().await?);
+
+    let (_, payload) = service.show_decrypted(item.id, &key).await?;
+    assert_eq!(payload["notes"].as_str(), Some("legacy"));
+
+    login::unlock(&repo, &master).await?;
+    let upgraded = repo.get_item(item.id).await?;
+    assert_eq!(upgraded.format_version, item.format_version);
+    assert!(!repo.has_legacy_items().await?);
+    assert!(crypto::decrypt(&key, &upgraded.nonce, &upgraded.encrypted_data).is_err());
+    let (_, payload) = service.show_decrypted(item.id, &key).await?;
+    assert_eq!(payload["notes"].as_str(), Some("legacy"));
+    Ok(())
+}